@@ -0,0 +1,291 @@
+import {
+  Box,
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogContentText,
+  DialogTitle,
+  Icon,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  TextField,
+} from '@mui/material';
+import { useRequest } from 'ahooks';
+import { useCallback, useEffect, useState } from 'react';
+import { Controller, useForm } from 'react-hook-form';
+import {
+  SSHUpstreamProxyType,
+  getUpstreamProxy,
+  setUpstreamProxy,
+} from 'tauri-plugin-ssh';
+import { Loading, TextFieldPassword } from 'shared';
+
+import useMessage from '@/hooks/useMessage';
+
+type UpstreamProxyFormValues = {
+  type: SSHUpstreamProxyType | '';
+  hostname: string;
+  port?: number;
+  username: string;
+  password: string;
+};
+
+export default function UpstreamProxySettings() {
+  const message = useMessage();
+  const [open, setOpen] = useState(false);
+  const formApi = useForm<UpstreamProxyFormValues>({
+    defaultValues: {
+      type: '',
+      hostname: '',
+      port: undefined,
+      username: '',
+      password: '',
+    },
+  });
+  const type = formApi.watch('type');
+
+  const { data: upstreamProxy, refresh } = useRequest(getUpstreamProxy);
+
+  const { run: onSubmit, loading } = useRequest(
+    async (values: UpstreamProxyFormValues) => {
+      await setUpstreamProxy(
+        values.type
+          ? {
+              type: values.type,
+              hostname: values.hostname.trim(),
+              port: Number(values.port),
+              username: values.username.trim() || undefined,
+              password:
+                values.type !== SSHUpstreamProxyType.Socks4
+                  ? values.password || undefined
+                  : undefined,
+            }
+          : null
+      );
+    },
+    {
+      manual: true,
+      onSuccess: () => {
+        message.success({
+          message: 'Save global proxy success',
+        });
+        refresh();
+        setOpen(false);
+      },
+      onError: () => {
+        message.error({
+          message: 'Save global proxy failed',
+        });
+      },
+    }
+  );
+
+  const onOpen = useCallback(() => {
+    setOpen(true);
+  }, []);
+
+  const onCancel = useCallback(() => {
+    setOpen(false);
+  }, []);
+
+  useEffect(() => {
+    if (!open) {
+      return;
+    }
+    formApi.reset({
+      type: upstreamProxy?.type ?? '',
+      hostname: upstreamProxy?.hostname ?? '',
+      port: upstreamProxy?.port,
+      username: upstreamProxy?.username ?? '',
+      password: upstreamProxy?.password ?? '',
+    });
+  }, [open, upstreamProxy, formApi]);
+
+  return (
+    <>
+      <List>
+        <ListItem>
+          <ListItemText
+            primary="Global Proxy"
+            secondary={
+              upstreamProxy
+                ? `${upstreamProxy.type} ${upstreamProxy.hostname}:${upstreamProxy.port}`
+                : 'None'
+            }
+          />
+          <IconButton onClick={onOpen}>
+            <Icon className="icon-arrow-right" />
+          </IconButton>
+        </ListItem>
+      </List>
+      <Dialog open={open}>
+        <DialogTitle>Global Proxy</DialogTitle>
+        <Loading loading={loading} size={32}>
+          <DialogContent>
+            <DialogContentText>
+              Used by hosts without their own proxy, proxy command or jump
+              hosts
+            </DialogContentText>
+            <Box component="form" noValidate autoComplete="off">
+              <Box sx={{ mt: 4 }}>
+                <Controller
+                  name="type"
+                  control={formApi.control}
+                  render={({ field, fieldState }) => (
+                    <TextField
+                      {...field}
+                      select
+                      fullWidth
+                      label="Proxy type"
+                      error={fieldState.invalid}
+                      helperText={fieldState.error?.message}
+                      slotProps={{
+                        select: {
+                          displayEmpty: true,
+                        },
+                        inputLabel: {
+                          shrink: true,
+                        },
+                      }}
+                    >
+                      <MenuItem value="">None</MenuItem>
+                      <MenuItem value={SSHUpstreamProxyType.Socks4}>
+                        SOCKS4a
+                      </MenuItem>
+                      <MenuItem value={SSHUpstreamProxyType.Socks5}>
+                        SOCKS5
+                      </MenuItem>
+                      <MenuItem value={SSHUpstreamProxyType.Http}>
+                        HTTP CONNECT
+                      </MenuItem>
+                    </TextField>
+                  )}
+                />
+              </Box>
+              {type && (
+                <Box sx={{ mt: 4, display: 'flex', gap: 2 }}>
+                  <Controller
+                    name="hostname"
+                    control={formApi.control}
+                    rules={{
+                      required: {
+                        value: true,
+                        message: 'Please enter the proxy hostname',
+                      },
+                      maxLength: {
+                        value: 255,
+                        message: 'Please enter no more than 255 characters',
+                      },
+                    }}
+                    render={({ field, fieldState }) => (
+                      <TextField
+                        {...field}
+                        sx={{ flex: 1 }}
+                        required
+                        label="Proxy hostname"
+                        placeholder="Proxy hostname"
+                        error={fieldState.invalid}
+                        helperText={fieldState.error?.message}
+                      />
+                    )}
+                  />
+                  <Controller
+                    name="port"
+                    control={formApi.control}
+                    rules={{
+                      required: {
+                        value: true,
+                        message: 'Please enter the proxy port',
+                      },
+                      pattern: {
+                        value: /^\d+$/,
+                        message: 'Please enter the number',
+                      },
+                      min: {
+                        value: 1,
+                        message: 'The port cannot be less than 1',
+                      },
+                      max: {
+                        value: 65535,
+                        message: 'The port cannot be greater than 65535',
+                      },
+                    }}
+                    render={({ field, fieldState }) => (
+                      <TextField
+                        {...field}
+                        value={field.value ?? ''}
+                        sx={{ width: 120 }}
+                        required
+                        label="Proxy port"
+                        placeholder="1080"
+                        type="number"
+                        error={fieldState.invalid}
+                        helperText={fieldState.error?.message}
+                      />
+                    )}
+                  />
+                </Box>
+              )}
+              {type && (
+                <Box sx={{ mt: 4 }}>
+                  <Controller
+                    name="username"
+                    control={formApi.control}
+                    rules={{
+                      maxLength: {
+                        value: 255,
+                        message: 'Please enter no more than 255 characters',
+                      },
+                    }}
+                    render={({ field, fieldState }) => (
+                      <TextField
+                        {...field}
+                        fullWidth
+                        label="Proxy username"
+                        placeholder="Proxy username (optional)"
+                        error={fieldState.invalid}
+                        helperText={fieldState.error?.message}
+                      />
+                    )}
+                  />
+                </Box>
+              )}
+              {type && type !== SSHUpstreamProxyType.Socks4 && (
+                <Box sx={{ mt: 4 }}>
+                  <Controller
+                    name="password"
+                    control={formApi.control}
+                    rules={{
+                      maxLength: {
+                        value: 255,
+                        message: 'Please enter no more than 255 characters',
+                      },
+                    }}
+                    render={({ field, fieldState }) => (
+                      <TextFieldPassword
+                        {...field}
+                        fullWidth
+                        label="Proxy password"
+                        placeholder="Proxy password (optional)"
+                        error={fieldState.invalid}
+                        helperText={fieldState.error?.message}
+                      />
+                    )}
+                  />
+                </Box>
+              )}
+            </Box>
+            <DialogActions sx={{ pt: 4 }}>
+              <Button onClick={onCancel}>Cancel</Button>
+              <Button onClick={formApi.handleSubmit(onSubmit)}>Submit</Button>
+            </DialogActions>
+          </DialogContent>
+        </Loading>
+      </Dialog>
+    </>
+  );
+}
@@ -23,6 +23,7 @@ import openUrl from '@/utils/openUrl';
 import useMessage from '@/hooks/useMessage';
 
 import CryptoSettings from './CryptoSettings';
+import UpstreamProxySettings from './UpstreamProxySettings';
 
 export default function Settings() {
   const [themeMode, setThemeMode] = useAtom(modeAtom);
@@ -172,6 +173,16 @@ export default function Settings() {
         <CryptoSettings />
       </Paper>
 
+      <Paper
+        sx={{
+          maxWidth: 560,
+          my: 2,
+          mx: 'auto',
+        }}
+      >
+        <UpstreamProxySettings />
+      </Paper>
+
       <Paper
         sx={{
           maxWidth: 560,
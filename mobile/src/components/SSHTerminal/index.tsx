@@ -92,6 +92,7 @@ export default function SSHTerminal({
           host={currentJumpHostChainItem?.host || item.host}
           loading={currentJumpHostChainItem?.loading}
           error={error}
+          banner={currentJumpHostChainItem?.banner}
           sx={{
             width: '100%',
             height: '100%',
@@ -10,11 +10,17 @@ import {
   EditHostForm,
   DEFAULT_TERMINAL_TYPE,
   type EditHostFormFields,
+  formatKeyboardInteractiveRules,
+  parseKeyboardInteractiveRules,
+  formatConnectionProfile,
+  parseConnectionProfile,
 } from 'shared';
 import {
   AuthenticationMethod,
   type Env,
   type Host,
+  StrictHostKeyChecking,
+  UpstreamProxyType,
   addHost,
   updateHost,
 } from 'tauri-plugin-data';
@@ -44,9 +50,27 @@ export default function AddHost({ open, data, onOk, onCancel }: AddHostProps) {
       port: 22,
       username: '',
       authenticationMethod: AuthenticationMethod.Password,
+      authenticationChain: [],
       password: '',
+      totpSecret: '',
+      keyboardInteractiveRules: '',
       keyId: '',
+      agentSocketPath: '',
       startupCommand: '',
+      agentForwarding: false,
+      x11Forwarding: false,
+      reconnectEnabled: false,
+      reconnectMaxAttempts: undefined,
+      multiplexEnabled: false,
+      multiplexLingerSecs: undefined,
+      connectionProfile: formatConnectionProfile(),
+      proxyCommand: '',
+      upstreamProxyType: '',
+      upstreamProxyHostname: '',
+      upstreamProxyPort: undefined,
+      upstreamProxyUsername: '',
+      upstreamProxyPassword: '',
+      strictHostKeyChecking: StrictHostKeyChecking.Ask,
       terminalType: DEFAULT_TERMINAL_TYPE,
       envs: '',
       jumpHostEnabled: false,
@@ -66,9 +90,30 @@ export default function AddHost({ open, data, onOk, onCancel }: AddHostProps) {
       username: data?.username ?? '',
       authenticationMethod:
         data?.authenticationMethod ?? AuthenticationMethod.Password,
+      authenticationChain: data?.authenticationChain ?? [],
       password: data?.password ?? '',
+      totpSecret: data?.totpSecret ?? '',
+      keyboardInteractiveRules: formatKeyboardInteractiveRules(
+        data?.keyboardInteractiveRules
+      ),
       keyId: data?.keyId ?? '',
+      agentSocketPath: data?.agentSocketPath ?? '',
       startupCommand: data?.startupCommand ?? '',
+      agentForwarding: data?.agentForwarding ?? false,
+      x11Forwarding: data?.x11Forwarding ?? false,
+      reconnectEnabled: !!data?.reconnect,
+      reconnectMaxAttempts: data?.reconnect?.maxAttempts,
+      multiplexEnabled: !!data?.multiplex,
+      multiplexLingerSecs: data?.multiplex?.lingerSecs,
+      connectionProfile: formatConnectionProfile(data?.connectionProfile),
+      proxyCommand: data?.proxyCommand ?? '',
+      upstreamProxyType: data?.upstreamProxy?.type ?? '',
+      upstreamProxyHostname: data?.upstreamProxy?.hostname ?? '',
+      upstreamProxyPort: data?.upstreamProxy?.port,
+      upstreamProxyUsername: data?.upstreamProxy?.username ?? '',
+      upstreamProxyPassword: data?.upstreamProxy?.password ?? '',
+      strictHostKeyChecking:
+        data?.strictHostKeyChecking ?? StrictHostKeyChecking.Ask,
       terminalType: data?.terminalType ?? DEFAULT_TERMINAL_TYPE,
       envs: data?.envs?.map((env) => `${env.key}=${env.value}`).join(',') ?? '',
       jumpHostEnabled: !!data?.jumpHostIds?.length,
@@ -89,6 +134,13 @@ export default function AddHost({ open, data, onOk, onCancel }: AddHostProps) {
     async (values: EditHostFormFields) => {
       const authenticationMethod =
         values.authenticationMethod || AuthenticationMethod.Password;
+      const authenticationChain = values.authenticationChain?.filter(
+        (item) => item !== authenticationMethod
+      );
+      const authenticationMethods = [
+        authenticationMethod,
+        ...(authenticationChain ?? []),
+      ];
       const hostData = {
         name: values.name || '',
         tags: values.tags || [],
@@ -96,16 +148,74 @@ export default function AddHost({ open, data, onOk, onCancel }: AddHostProps) {
         port: Number(values.port || 22),
         username: values.username || '',
         authenticationMethod,
+        authenticationChain: authenticationChain?.length
+          ? authenticationChain
+          : undefined,
         password:
-          authenticationMethod === AuthenticationMethod.Password
+          authenticationMethods.includes(AuthenticationMethod.Password) ||
+          authenticationMethods.includes(
+            AuthenticationMethod.KeyboardInteractive
+          )
             ? values.password
             : undefined,
+        totpSecret:
+          authenticationMethods.includes(AuthenticationMethod.Password) ||
+          authenticationMethods.includes(
+            AuthenticationMethod.KeyboardInteractive
+          )
+            ? values.totpSecret?.trim() || undefined
+            : undefined,
+        keyboardInteractiveRules:
+          authenticationMethods.includes(AuthenticationMethod.Password) ||
+          authenticationMethods.includes(
+            AuthenticationMethod.KeyboardInteractive
+          )
+            ? parseKeyboardInteractiveRules(values.keyboardInteractiveRules)
+            : undefined,
         keyId:
-          authenticationMethod === AuthenticationMethod.PublicKey ||
-          authenticationMethod === AuthenticationMethod.Certificate
+          authenticationMethods.includes(AuthenticationMethod.PublicKey) ||
+          authenticationMethods.includes(AuthenticationMethod.Certificate)
             ? values.keyId
             : undefined,
+        agentSocketPath:
+          authenticationMethods.includes(AuthenticationMethod.Agent)
+            ? values.agentSocketPath || undefined
+            : undefined,
         startupCommand: values.startupCommand || undefined,
+        agentForwarding: !!values.agentForwarding,
+        x11Forwarding: !!values.x11Forwarding,
+        reconnect: values.reconnectEnabled
+          ? {
+              maxAttempts: values.reconnectMaxAttempts
+                ? Number(values.reconnectMaxAttempts)
+                : undefined,
+            }
+          : undefined,
+        multiplex: values.multiplexEnabled
+          ? {
+              lingerSecs:
+                values.multiplexLingerSecs !== undefined &&
+                `${values.multiplexLingerSecs}` !== ''
+                  ? Number(values.multiplexLingerSecs)
+                  : undefined,
+            }
+          : undefined,
+        connectionProfile: parseConnectionProfile(values.connectionProfile),
+        proxyCommand: values.proxyCommand?.trim() || undefined,
+        upstreamProxy: values.upstreamProxyType
+          ? {
+              type: values.upstreamProxyType,
+              hostname: values.upstreamProxyHostname?.trim() || '',
+              port: Number(values.upstreamProxyPort),
+              username: values.upstreamProxyUsername?.trim() || undefined,
+              password:
+                values.upstreamProxyType !== UpstreamProxyType.Socks4
+                  ? values.upstreamProxyPassword || undefined
+                  : undefined,
+            }
+          : undefined,
+        strictHostKeyChecking:
+          values.strictHostKeyChecking || StrictHostKeyChecking.Ask,
         terminalType: values.terminalType || DEFAULT_TERMINAL_TYPE,
         envs: values.envs?.split(',').reduce<Env[]>((envs, env) => {
           let [key, value] = env.split('=');
@@ -24,6 +24,7 @@ import { useIsShowPaywallAtom, useIsSubscription } from '@/atom/iap';
 import useMessage from '@/hooks/useMessage';
 
 import CryptoSettings from './CryptoSettings';
+import UpstreamProxySettings from './UpstreamProxySettings';
 
 function IOSIAP() {
   const [, setOpen] = useIsShowPaywallAtom();
@@ -216,6 +217,16 @@ export default function Settings() {
         <CryptoSettings />
       </Paper>
 
+      <Paper
+        sx={{
+          maxWidth: 560,
+          my: 2,
+          mx: 'auto',
+        }}
+      >
+        <UpstreamProxySettings />
+      </Paper>
+
       <Paper
         sx={{
           maxWidth: 560,
@@ -2,6 +2,7 @@ import { v4 as uuidV4 } from 'uuid';
 import { atom, useAtom, useAtomValue } from 'jotai';
 import { useMemo } from 'react';
 import { type Host } from 'tauri-plugin-data';
+import { type SSHSessionReconnectingEvent } from 'tauri-plugin-ssh';
 import { useLatest, useMemoizedFn } from 'ahooks';
 
 import { useHosts } from '@/hooks/useHosts';
@@ -22,6 +23,10 @@ export type TerminalAtom = {
   jumpHostChain: JumpHostChainItem[];
   status: 'pending' | 'success' | 'failed';
   error?: unknown;
+  /**
+   * 连接断开后正在自动重连
+   */
+  reconnecting?: SSHSessionReconnectingEvent['data'];
 };
 
 const terminalsAtom = atom<Map<string, TerminalAtom>>(new Map());
@@ -58,24 +63,43 @@ export function useTerminalsAtomWithApi() {
     });
   });
 
+  const updateJumpHostChainItem = useMemoizedFn(
+    (uuid: string, jumpHostChainItem: JumpHostChainItem) => {
+      const currentItem = stateRef.current.get(uuid);
+      if (!currentItem) {
+        return;
+      }
+
+      updateTerminal({
+        ...currentItem,
+        jumpHostChain: currentItem.jumpHostChain.map((it) => {
+          return it.host.id === jumpHostChainItem.host.id
+            ? jumpHostChainItem
+            : it;
+        }),
+      });
+    }
+  );
+
+  const updateTerminalReconnecting = useMemoizedFn(
+    (uuid: string, reconnecting?: SSHSessionReconnectingEvent['data']) => {
+      const currentItem = stateRef.current.get(uuid);
+      if (!currentItem) {
+        return;
+      }
+
+      updateTerminal({
+        ...currentItem,
+        reconnecting,
+      });
+    }
+  );
+
   const establishTerminal = useMemoizedFn((terminalAtom: TerminalAtom) => {
     return establishJumpHostChainConnections(terminalAtom.jumpHostChain, {
       keysMap: new Map(keys.map((key) => [key.id, key])),
-      onJumpHostChainItemUpdate: (jumpHostChainItem) => {
-        const currentItem = stateRef.current.get(terminalAtom.uuid);
-        if (!currentItem) {
-          return;
-        }
-
-        updateTerminal({
-          ...currentItem,
-          jumpHostChain: currentItem.jumpHostChain.map((it) => {
-            return it.host.id === jumpHostChainItem.host.id
-              ? jumpHostChainItem
-              : it;
-          }),
-        });
-      },
+      onJumpHostChainItemUpdate: (jumpHostChainItem) =>
+        updateJumpHostChainItem(terminalAtom.uuid, jumpHostChainItem),
     });
   });
 
@@ -118,6 +142,10 @@ export function useTerminalsAtomWithApi() {
       const jumpHostChain = resolveJumpHostChain(host, {
         hostsMap,
         onDisconnect: () => deleteTerminal(uuid),
+        onAuthBanner: (jumpHostChainItem) =>
+          updateJumpHostChainItem(uuid, jumpHostChainItem),
+        onReconnecting: ({ data }) => updateTerminalReconnecting(uuid, data),
+        onReconnected: () => updateTerminalReconnecting(uuid, undefined),
       });
 
       const item: TerminalAtom = {
@@ -13,7 +13,11 @@ import {
   ListItemText,
 } from '@mui/material';
 import { useMemo } from 'react';
-import { AuthenticationMethod } from 'tauri-plugin-data';
+import {
+  AuthenticationMethod,
+  StrictHostKeyChecking,
+  UpstreamProxyType,
+} from 'tauri-plugin-data';
 
 import { useHosts } from '@/hooks/useHosts';
 import { useKeys } from '@/hooks/useKeys';
@@ -22,6 +26,7 @@ import { TextFieldPassword } from '../TextFieldPassword';
 
 import type { EditHostFormApi } from './types';
 import { TERMINAL_TYPES } from './terminalTypes';
+import { validateKeyboardInteractiveRules } from './keyboardInteractiveRules';
 
 type BasicFormProps = {
   formApi: EditHostFormApi;
@@ -37,6 +42,14 @@ export default function BasicForm({
   const { data: hosts } = useHosts();
   const { data: keys } = useKeys();
   const authenticationMethod = formApi.watch('authenticationMethod');
+  const authenticationChain = formApi.watch('authenticationChain');
+  const reconnectEnabled = formApi.watch('reconnectEnabled');
+  const multiplexEnabled = formApi.watch('multiplexEnabled');
+  const upstreamProxyType = formApi.watch('upstreamProxyType');
+  const authenticationMethods = useMemo(
+    () => [authenticationMethod, ...(authenticationChain ?? [])],
+    [authenticationMethod, authenticationChain]
+  );
 
   const tags = useMemo(() => {
     return hosts.reduce<string[]>((acc, cur) => {
@@ -271,11 +284,86 @@ export default function BasicForm({
             <MenuItem value={AuthenticationMethod.Certificate}>
               Certificate
             </MenuItem>
+            <MenuItem value={AuthenticationMethod.Agent}>Agent</MenuItem>
+            <MenuItem value={AuthenticationMethod.KeyboardInteractive}>
+              KeyboardInteractive
+            </MenuItem>
           </TextField>
         )}
       />
 
-      {authenticationMethod === AuthenticationMethod.Password && (
+      <Controller
+        name="authenticationChain"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            value={field.value ?? []}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="Then authenticate with"
+            placeholder="Then authenticate with"
+            error={fieldState.invalid}
+            helperText={
+              fieldState.error?.message ||
+              'Used when the server requires more than one method (optional)'
+            }
+            slotProps={{
+              select: {
+                multiple: true,
+              },
+            }}
+          >
+            {[
+              AuthenticationMethod.Password,
+              AuthenticationMethod.PublicKey,
+              AuthenticationMethod.Certificate,
+              AuthenticationMethod.Agent,
+              AuthenticationMethod.KeyboardInteractive,
+            ]
+              .filter((item) => item !== authenticationMethod)
+              .map((item) => (
+                <MenuItem key={item} value={item}>
+                  {item}
+                </MenuItem>
+              ))}
+          </TextField>
+        )}
+      />
+
+      {authenticationMethods.includes(AuthenticationMethod.Agent) && (
+        <Controller
+          name="agentSocketPath"
+          control={formApi.control}
+          rules={{
+            maxLength: {
+              value: 500,
+              message: 'Please enter no more than 500 characters',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label="Agent socket path"
+              placeholder="Defaults to SSH_AUTH_SOCK (optional)"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message}
+            />
+          )}
+        />
+      )}
+
+      {(authenticationMethods.includes(AuthenticationMethod.Password) ||
+        authenticationMethods.includes(
+          AuthenticationMethod.KeyboardInteractive
+        )) && (
         <Controller
           name="password"
           control={formApi.control}
@@ -301,8 +389,68 @@ export default function BasicForm({
         />
       )}
 
-      {(authenticationMethod === AuthenticationMethod.PublicKey ||
-        authenticationMethod === AuthenticationMethod.Certificate) && (
+      {(authenticationMethods.includes(AuthenticationMethod.Password) ||
+        authenticationMethods.includes(
+          AuthenticationMethod.KeyboardInteractive
+        )) && (
+        <Controller
+          name="totpSecret"
+          control={formApi.control}
+          rules={{
+            maxLength: {
+              value: 500,
+              message: 'Please enter no more than 500 characters',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextFieldPassword
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label="TOTP secret"
+              placeholder="Base32 secret or otpauth:// URI (optional)"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message}
+            />
+          )}
+        />
+      )}
+
+      {(authenticationMethods.includes(AuthenticationMethod.Password) ||
+        authenticationMethods.includes(
+          AuthenticationMethod.KeyboardInteractive
+        )) && (
+        <Controller
+          name="keyboardInteractiveRules"
+          control={formApi.control}
+          rules={{
+            validate: validateKeyboardInteractiveRules,
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              multiline
+              minRows={2}
+              label="Keyboard interactive rules"
+              placeholder="One per line, e.g. Verification code => Totp"
+              error={fieldState.invalid}
+              helperText={
+                fieldState.error?.message ||
+                'Answer with Password, Totp or Fixed:value'
+              }
+            />
+          )}
+        />
+      )}
+
+      {(authenticationMethods.includes(AuthenticationMethod.PublicKey) ||
+        authenticationMethods.includes(AuthenticationMethod.Certificate)) && (
         <Controller
           name="keyId"
           control={formApi.control}
@@ -342,6 +490,366 @@ export default function BasicForm({
         />
       )}
 
+      {(authenticationMethod === AuthenticationMethod.Agent ||
+        authenticationMethod === AuthenticationMethod.PublicKey ||
+        authenticationMethod === AuthenticationMethod.Certificate) && (
+        <Controller
+          name="agentForwarding"
+          control={formApi.control}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              value={field.value ? 'on' : 'off'}
+              onChange={(event) => field.onChange(event.target.value === 'on')}
+              sx={{
+                mb: 3,
+              }}
+              select
+              fullWidth
+              label="Agent forwarding"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message}
+            >
+              <MenuItem value="off">Disabled</MenuItem>
+              <MenuItem value="on">Enabled</MenuItem>
+            </TextField>
+          )}
+        />
+      )}
+
+      <Controller
+        name="x11Forwarding"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            value={field.value ? 'on' : 'off'}
+            onChange={(event) => field.onChange(event.target.value === 'on')}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="X11 forwarding"
+            error={fieldState.invalid}
+            helperText={fieldState.error?.message}
+          >
+            <MenuItem value="off">Disabled</MenuItem>
+            <MenuItem value="on">Enabled</MenuItem>
+          </TextField>
+        )}
+      />
+
+      <Controller
+        name="reconnectEnabled"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            value={field.value ? 'on' : 'off'}
+            onChange={(event) => field.onChange(event.target.value === 'on')}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="Auto reconnect"
+            error={fieldState.invalid}
+            helperText={
+              fieldState.error?.message ??
+              'Reconnect and reopen shells when the connection drops'
+            }
+          >
+            <MenuItem value="off">Disabled</MenuItem>
+            <MenuItem value="on">Enabled</MenuItem>
+          </TextField>
+        )}
+      />
+
+      {reconnectEnabled && (
+        <Controller
+          name="reconnectMaxAttempts"
+          control={formApi.control}
+          rules={{
+            pattern: {
+              value: /^\d+$/,
+              message: 'Please enter the number',
+            },
+            min: {
+              value: 1,
+              message: 'The attempts cannot be less than 1',
+            },
+            max: {
+              value: 100,
+              message: 'The attempts cannot be greater than 100',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label="Reconnect attempts"
+              placeholder="5"
+              type="number"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message}
+            />
+          )}
+        />
+      )}
+
+      <Controller
+        name="multiplexEnabled"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            value={field.value ? 'on' : 'off'}
+            onChange={(event) => field.onChange(event.target.value === 'on')}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="Connection reuse"
+            error={fieldState.invalid}
+            helperText={
+              fieldState.error?.message ??
+              'Tabs to this host share one authenticated connection'
+            }
+          >
+            <MenuItem value="off">Disabled</MenuItem>
+            <MenuItem value="on">Enabled</MenuItem>
+          </TextField>
+        )}
+      />
+
+      {multiplexEnabled && (
+        <Controller
+          name="multiplexLingerSecs"
+          control={formApi.control}
+          rules={{
+            pattern: {
+              value: /^\d+$/,
+              message: 'Please enter the number',
+            },
+            max: {
+              value: 3600,
+              message: 'The linger time cannot be greater than 3600',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label="Linger seconds"
+              placeholder="60"
+              type="number"
+              error={fieldState.invalid}
+              helperText={
+                fieldState.error?.message ??
+                'How long the connection stays open after the last tab closes'
+              }
+            />
+          )}
+        />
+      )}
+
+      <Controller
+        name="proxyCommand"
+        control={formApi.control}
+        rules={{
+          maxLength: {
+            value: 500,
+            message: 'Please enter no more than 500 characters',
+          },
+        }}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            sx={{
+              mb: 3,
+            }}
+            fullWidth
+            label="Proxy Command"
+            placeholder="e.g. cloudflared access ssh --hostname %h (optional)"
+            error={fieldState.invalid}
+            helperText={
+              fieldState.error?.message ??
+              '%h, %p and %r are replaced by hostname, port and username'
+            }
+          />
+        )}
+      />
+
+      <Controller
+        name="upstreamProxyType"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            value={field.value ?? ''}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="Upstream proxy"
+            error={fieldState.invalid}
+            helperText={
+              fieldState.error?.message ??
+              'Ignored when a proxy command or jump hosts are set'
+            }
+            slotProps={{
+              select: {
+                displayEmpty: true,
+              },
+              inputLabel: {
+                shrink: true,
+              },
+            }}
+          >
+            <MenuItem value="">None</MenuItem>
+            <MenuItem value={UpstreamProxyType.Socks4}>SOCKS4a</MenuItem>
+            <MenuItem value={UpstreamProxyType.Socks5}>SOCKS5</MenuItem>
+            <MenuItem value={UpstreamProxyType.Http}>HTTP CONNECT</MenuItem>
+          </TextField>
+        )}
+      />
+
+      {upstreamProxyType && (
+        <Box
+          sx={{
+            display: 'flex',
+            gap: 2,
+          }}
+        >
+          <Controller
+            name="upstreamProxyHostname"
+            control={formApi.control}
+            rules={{
+              required: {
+                value: true,
+                message: 'Please enter the proxy hostname',
+              },
+              maxLength: {
+                value: 255,
+                message: 'Please enter no more than 255 characters',
+              },
+            }}
+            render={({ field, fieldState }) => (
+              <TextField
+                {...field}
+                sx={{
+                  mb: 3,
+                  flex: 1,
+                }}
+                required
+                label="Proxy hostname"
+                placeholder="Proxy hostname"
+                error={fieldState.invalid}
+                helperText={fieldState.error?.message}
+              />
+            )}
+          />
+          <Controller
+            name="upstreamProxyPort"
+            control={formApi.control}
+            rules={{
+              required: {
+                value: true,
+                message: 'Please enter the proxy port',
+              },
+              pattern: {
+                value: /^\d+$/,
+                message: 'Please enter the number',
+              },
+              min: {
+                value: 1,
+                message: 'The port cannot be less than 1',
+              },
+              max: {
+                value: 65535,
+                message: 'The port cannot be greater than 65535',
+              },
+            }}
+            render={({ field, fieldState }) => (
+              <TextField
+                {...field}
+                sx={{
+                  mb: 3,
+                  width: 120,
+                }}
+                required
+                label="Proxy port"
+                placeholder="1080"
+                type="number"
+                error={fieldState.invalid}
+                helperText={fieldState.error?.message}
+              />
+            )}
+          />
+        </Box>
+      )}
+
+      {upstreamProxyType && (
+        <Controller
+          name="upstreamProxyUsername"
+          control={formApi.control}
+          rules={{
+            maxLength: {
+              value: 255,
+              message: 'Please enter no more than 255 characters',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label="Proxy username"
+              placeholder="Proxy username (optional)"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message}
+            />
+          )}
+        />
+      )}
+
+      {upstreamProxyType && upstreamProxyType !== UpstreamProxyType.Socks4 && (
+        <Controller
+          name="upstreamProxyPassword"
+          control={formApi.control}
+          rules={{
+            maxLength: {
+              value: 255,
+              message: 'Please enter no more than 255 characters',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextFieldPassword
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label="Proxy password"
+              placeholder="Proxy password (optional)"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message}
+            />
+          )}
+        />
+      )}
+
       <Controller
         name="startupCommand"
         control={formApi.control}
@@ -406,6 +914,37 @@ export default function BasicForm({
           </TextField>
         )}
       />
+
+      <Controller
+        name="strictHostKeyChecking"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="Host key checking"
+            error={fieldState.invalid}
+            helperText={fieldState.error?.message}
+          >
+            <MenuItem value={StrictHostKeyChecking.Ask}>
+              Ask for unknown and changed keys
+            </MenuItem>
+            <MenuItem value={StrictHostKeyChecking.AcceptNew}>
+              Accept new keys, reject changed keys
+            </MenuItem>
+            <MenuItem value={StrictHostKeyChecking.Yes}>
+              Reject unknown and changed keys
+            </MenuItem>
+            <MenuItem value={StrictHostKeyChecking.Off}>
+              Accept all keys (insecure)
+            </MenuItem>
+          </TextField>
+        )}
+      />
       <Controller
         name="envs"
         control={formApi.control}
@@ -0,0 +1,173 @@
+import { Controller } from 'react-hook-form';
+import {
+  Box,
+  Divider,
+  MenuItem,
+  TextField,
+  Typography,
+  type SxProps,
+  type Theme,
+} from '@mui/material';
+
+import type { EditHostFormApi } from './types';
+
+type ConnectionProfileFormProps = {
+  formApi: EditHostFormApi;
+  sx?: SxProps<Theme>;
+};
+
+const NUMBER_FIELDS = [
+  {
+    name: 'connectionProfile.connectTimeoutSecs',
+    label: 'Connect timeout (seconds)',
+    placeholder: '5',
+    helperText: 'Covers connecting and the key exchange',
+  },
+  {
+    name: 'connectionProfile.inactivityTimeoutSecs',
+    label: 'Inactivity timeout (seconds)',
+    placeholder: '1800',
+    helperText: '0 never closes an idle connection',
+  },
+  {
+    name: 'connectionProfile.keepaliveIntervalSecs',
+    label: 'Keepalive interval (seconds)',
+    placeholder: '5',
+    helperText: '0 sends no keepalives',
+  },
+  {
+    name: 'connectionProfile.keepaliveMax',
+    label: 'Keepalive max',
+    placeholder: '3',
+    helperText: 'Unanswered keepalives before disconnecting',
+  },
+  {
+    name: 'connectionProfile.rekeyLimitBytes',
+    label: 'Rekey data limit (bytes)',
+    placeholder: '1073741824',
+    helperText: 'Keys are renewed after this much data',
+  },
+  {
+    name: 'connectionProfile.rekeyTimeLimitSecs',
+    label: 'Rekey time limit (seconds)',
+    placeholder: '3600',
+    helperText: 'Keys are renewed after this long',
+  },
+] as const;
+
+const ALGORITHM_FIELDS = [
+  {
+    name: 'connectionProfile.kexAlgorithms',
+    label: 'Key exchange algorithms',
+    placeholder: 'curve25519-sha256,diffie-hellman-group14-sha256',
+  },
+  {
+    name: 'connectionProfile.hostKeyAlgorithms',
+    label: 'Host key algorithms',
+    placeholder: 'ssh-ed25519,rsa-sha2-256',
+  },
+  {
+    name: 'connectionProfile.ciphers',
+    label: 'Ciphers',
+    placeholder: 'chacha20-poly1305@openssh.com,aes256-gcm@openssh.com',
+  },
+  {
+    name: 'connectionProfile.macs',
+    label: 'MACs',
+    placeholder: 'hmac-sha2-256-etm@openssh.com,hmac-sha2-256',
+  },
+] as const;
+
+export default function ConnectionProfileForm({
+  formApi,
+  sx,
+}: ConnectionProfileFormProps) {
+  return (
+    <Box sx={sx}>
+      <Divider sx={{ mb: 2 }}>
+        <Typography variant="subtitle1">Connection</Typography>
+      </Divider>
+
+      {NUMBER_FIELDS.map((item) => (
+        <Controller
+          key={item.name}
+          name={item.name}
+          control={formApi.control}
+          rules={{
+            pattern: {
+              value: /^\d+$/,
+              message: 'Please enter the number',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label={item.label}
+              placeholder={item.placeholder}
+              type="number"
+              error={fieldState.invalid}
+              helperText={fieldState.error?.message ?? item.helperText}
+            />
+          )}
+        />
+      ))}
+
+      <Controller
+        name="connectionProfile.compression"
+        control={formApi.control}
+        render={({ field, fieldState }) => (
+          <TextField
+            {...field}
+            value={field.value ?? 'default'}
+            sx={{
+              mb: 3,
+            }}
+            select
+            fullWidth
+            label="Compression"
+            error={fieldState.invalid}
+            helperText={fieldState.error?.message}
+          >
+            <MenuItem value="default">Default</MenuItem>
+            <MenuItem value="on">Enabled</MenuItem>
+            <MenuItem value="off">Disabled</MenuItem>
+          </TextField>
+        )}
+      />
+
+      {ALGORITHM_FIELDS.map((item) => (
+        <Controller
+          key={item.name}
+          name={item.name}
+          control={formApi.control}
+          rules={{
+            pattern: {
+              value: /^[\w.@+,\s-]*$/,
+              message: 'Please enter algorithm names separated by commas',
+            },
+          }}
+          render={({ field, fieldState }) => (
+            <TextField
+              {...field}
+              sx={{
+                mb: 3,
+              }}
+              fullWidth
+              label={item.label}
+              placeholder={item.placeholder}
+              error={fieldState.invalid}
+              helperText={
+                fieldState.error?.message ??
+                'In order of preference, only the listed algorithms are offered'
+              }
+            />
+          )}
+        />
+      ))}
+    </Box>
+  );
+}
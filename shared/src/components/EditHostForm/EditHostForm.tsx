@@ -1,6 +1,7 @@
 import { Box } from '@mui/material';
 
 import BasicForm from './BasicForm';
+import ConnectionProfileForm from './ConnectionProfileForm';
 import JumpHostsForm from './JumpHostsForm';
 import TerminalSettingsForm from './TerminalSettingsForm';
 import type { EditHostFormApi } from './types';
@@ -23,6 +24,7 @@ export function EditHostForm({ formApi, onOpenAddKey }: EditHostFormProps) {
     >
       <BasicForm formApi={formApi} sx={{ mb: 3 }} onOpenAddKey={onOpenAddKey} />
       <JumpHostsForm formApi={formApi} sx={{ mb: 3 }} />
+      <ConnectionProfileForm formApi={formApi} sx={{ mb: 3 }} />
       <TerminalSettingsForm formApi={formApi} sx={{ mb: 3 }} />
     </Box>
   );
@@ -0,0 +1,80 @@
+import type { HostConnectionProfile } from 'tauri-plugin-data';
+
+import type { ConnectionProfileFormFields } from './types';
+
+/**
+ * 表单中数字字段为空时使用默认值，算法列表以逗号分隔
+ */
+function parseNumber(value?: number | string) {
+  if (value === undefined || `${value}`.trim() === '') {
+    return undefined;
+  }
+  return Number(value);
+}
+
+function parseNames(value?: string) {
+  const names = value
+    ?.split(',')
+    .map((name) => name.trim())
+    .filter((name) => !!name);
+
+  return names?.length ? names : undefined;
+}
+
+function formatNumber(value?: number) {
+  return value === undefined ? '' : `${value}`;
+}
+
+function formatNames(names?: string[]) {
+  return names?.join(',') ?? '';
+}
+
+export function parseConnectionProfile(
+  fields?: ConnectionProfileFormFields
+): HostConnectionProfile | undefined {
+  const profile: HostConnectionProfile = {
+    connectTimeoutSecs: parseNumber(fields?.connectTimeoutSecs),
+    inactivityTimeoutSecs: parseNumber(fields?.inactivityTimeoutSecs),
+    keepaliveIntervalSecs: parseNumber(fields?.keepaliveIntervalSecs),
+    keepaliveMax: parseNumber(fields?.keepaliveMax),
+    compression:
+      fields?.compression === 'on'
+        ? true
+        : fields?.compression === 'off'
+          ? false
+          : undefined,
+    kexAlgorithms: parseNames(fields?.kexAlgorithms),
+    hostKeyAlgorithms: parseNames(fields?.hostKeyAlgorithms),
+    ciphers: parseNames(fields?.ciphers),
+    macs: parseNames(fields?.macs),
+    rekeyLimitBytes: parseNumber(fields?.rekeyLimitBytes),
+    rekeyTimeLimitSecs: parseNumber(fields?.rekeyTimeLimitSecs),
+  };
+
+  return Object.values(profile).some((value) => value !== undefined)
+    ? profile
+    : undefined;
+}
+
+export function formatConnectionProfile(
+  profile?: HostConnectionProfile
+): ConnectionProfileFormFields {
+  return {
+    connectTimeoutSecs: formatNumber(profile?.connectTimeoutSecs),
+    inactivityTimeoutSecs: formatNumber(profile?.inactivityTimeoutSecs),
+    keepaliveIntervalSecs: formatNumber(profile?.keepaliveIntervalSecs),
+    keepaliveMax: formatNumber(profile?.keepaliveMax),
+    compression:
+      profile?.compression === undefined
+        ? 'default'
+        : profile.compression
+          ? 'on'
+          : 'off',
+    kexAlgorithms: formatNames(profile?.kexAlgorithms),
+    hostKeyAlgorithms: formatNames(profile?.hostKeyAlgorithms),
+    ciphers: formatNames(profile?.ciphers),
+    macs: formatNames(profile?.macs),
+    rekeyLimitBytes: formatNumber(profile?.rekeyLimitBytes),
+    rekeyTimeLimitSecs: formatNumber(profile?.rekeyTimeLimitSecs),
+  };
+}
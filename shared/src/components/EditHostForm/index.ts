@@ -1,3 +1,11 @@
 export * from './EditHostForm';
 export { DEFAULT_TERMINAL_TYPE } from './terminalTypes';
+export {
+  formatConnectionProfile,
+  parseConnectionProfile,
+} from './connectionProfile';
+export {
+  formatKeyboardInteractiveRules,
+  parseKeyboardInteractiveRules,
+} from './keyboardInteractiveRules';
 export type { EditHostFormFields, EditHostFormApi } from './types';
@@ -0,0 +1,92 @@
+import {
+  KeyboardInteractiveAnswer,
+  type HostKeyboardInteractiveRule,
+} from 'tauri-plugin-data';
+
+/**
+ * 表单中每行一条规则，格式为 `正则 => Password`、`正则 => Totp` 或 `正则 => Fixed:应答`
+ */
+const RULE_SEPARATOR = ' => ';
+
+function parseRule(line: string): HostKeyboardInteractiveRule | string {
+  const index = line.lastIndexOf(RULE_SEPARATOR);
+  if (index === -1) {
+    return 'Invalid rule format';
+  }
+
+  const pattern = line.slice(0, index).trim();
+  const answer = line.slice(index + RULE_SEPARATOR.length).trim();
+  if (!pattern) {
+    return 'Invalid rule format';
+  }
+  try {
+    new RegExp(pattern);
+  } catch {
+    return `Invalid pattern: ${pattern}`;
+  }
+
+  if (answer === KeyboardInteractiveAnswer.Password) {
+    return { pattern, answer: KeyboardInteractiveAnswer.Password };
+  }
+  if (answer === KeyboardInteractiveAnswer.Totp) {
+    return { pattern, answer: KeyboardInteractiveAnswer.Totp };
+  }
+  if (answer.startsWith(`${KeyboardInteractiveAnswer.Fixed}:`)) {
+    return {
+      pattern,
+      answer: KeyboardInteractiveAnswer.Fixed,
+      value: answer.slice(KeyboardInteractiveAnswer.Fixed.length + 1),
+    };
+  }
+
+  return `Invalid answer: ${answer}`;
+}
+
+function splitLines(value: string) {
+  return value
+    .split('\n')
+    .map((line) => line.trim())
+    .filter((line) => !!line);
+}
+
+export function validateKeyboardInteractiveRules(value?: string) {
+  if (!value) {
+    return true;
+  }
+
+  for (const line of splitLines(value)) {
+    const rule = parseRule(line);
+    if (typeof rule === 'string') {
+      return rule;
+    }
+  }
+  return true;
+}
+
+export function parseKeyboardInteractiveRules(
+  value?: string
+): HostKeyboardInteractiveRule[] | undefined {
+  const rules = splitLines(value ?? '').reduce<HostKeyboardInteractiveRule[]>(
+    (rules, line) => {
+      const rule = parseRule(line);
+      return typeof rule === 'string' ? rules : [...rules, rule];
+    },
+    []
+  );
+
+  return rules.length ? rules : undefined;
+}
+
+export function formatKeyboardInteractiveRules(
+  rules?: HostKeyboardInteractiveRule[]
+) {
+  return (
+    rules
+      ?.map((rule) =>
+        rule.answer === KeyboardInteractiveAnswer.Fixed
+          ? `${rule.pattern}${RULE_SEPARATOR}${rule.answer}:${rule.value ?? ''}`
+          : `${rule.pattern}${RULE_SEPARATOR}${rule.answer}`
+      )
+      .join('\n') ?? ''
+  );
+}
@@ -1,14 +1,51 @@
 import type { UseFormReturn } from 'react-hook-form';
-import type { Host } from 'tauri-plugin-data';
+import type { Host, UpstreamProxyType } from 'tauri-plugin-data';
 
 export type JumpHostsFormFields = {
   jumpHostEnabled?: boolean;
   jumpHostIds?: string[];
 };
 
-export type EditHostFormFields = Omit<Partial<Host>, 'envs' | 'jumpHostIds'> &
+/**
+ * 数字字段为空时使用默认值，算法列表以逗号分隔
+ */
+export type ConnectionProfileFormFields = {
+  connectTimeoutSecs?: number | string;
+  inactivityTimeoutSecs?: number | string;
+  keepaliveIntervalSecs?: number | string;
+  keepaliveMax?: number | string;
+  compression?: 'default' | 'on' | 'off';
+  kexAlgorithms?: string;
+  hostKeyAlgorithms?: string;
+  ciphers?: string;
+  macs?: string;
+  rekeyLimitBytes?: number | string;
+  rekeyTimeLimitSecs?: number | string;
+};
+
+export type EditHostFormFields = Omit<
+  Partial<Host>,
+  | 'envs'
+  | 'jumpHostIds'
+  | 'keyboardInteractiveRules'
+  | 'reconnect'
+  | 'multiplex'
+  | 'connectionProfile'
+  | 'upstreamProxy'
+> &
   JumpHostsFormFields & {
     envs?: string;
+    keyboardInteractiveRules?: string;
+    reconnectEnabled?: boolean;
+    reconnectMaxAttempts?: number;
+    multiplexEnabled?: boolean;
+    multiplexLingerSecs?: number;
+    connectionProfile?: ConnectionProfileFormFields;
+    upstreamProxyType?: UpstreamProxyType | '';
+    upstreamProxyHostname?: string;
+    upstreamProxyPort?: number;
+    upstreamProxyUsername?: string;
+    upstreamProxyPassword?: string;
   };
 
 export type EditHostFormApi = UseFormReturn<EditHostFormFields>;
@@ -0,0 +1,85 @@
+import { Box, Button, ButtonGroup, Icon } from '@mui/material';
+import { SSHSessionCheckServerKey } from 'tauri-plugin-ssh';
+import { get } from 'lodash-es';
+
+import { Dropdown } from '@/components/Dropdown';
+
+import ErrorText from '../ErrorText';
+import { StatusButton, type ErrorProps } from '../common';
+
+export default function HostKeyChanged({
+  error,
+  onReConnect,
+  onClose,
+}: ErrorProps) {
+  const oldFingerprint = get(error, 'oldFingerprint');
+  const newFingerprint = get(error, 'newFingerprint');
+
+  return (
+    <>
+      <ErrorText
+        title="Remote host identification has changed!"
+        message={
+          oldFingerprint && newFingerprint
+            ? `Someone could be eavesdropping on you right now. ${get(error, 'algorithm')} key fingerprint was ${oldFingerprint}, now it is ${newFingerprint}.`
+            : get(error, 'message', String(error))
+        }
+      />
+
+      <Box
+        sx={{
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'space-between',
+          gap: 2,
+        }}
+      >
+        <StatusButton variant="outlined" onClick={onClose}>
+          Close
+        </StatusButton>
+        <Dropdown
+          menus={[
+            {
+              label: 'Continue once',
+              value: 'Continue once',
+              onClick: () => onReConnect(SSHSessionCheckServerKey.Continue),
+            },
+          ]}
+          anchorOrigin={{
+            vertical: 'bottom',
+            horizontal: 'right',
+          }}
+          transformOrigin={{
+            vertical: 'top',
+            horizontal: 'right',
+          }}
+        >
+          {({ onChangeOpen }) => (
+            <ButtonGroup
+              sx={{
+                minWidth: 150,
+              }}
+              variant="contained"
+              color="error"
+            >
+              <Button
+                fullWidth
+                onClick={() =>
+                  onReConnect(SSHSessionCheckServerKey.ReplaceAndContinue)
+                }
+              >
+                Replace key
+              </Button>
+              <Button
+                size="small"
+                onClick={(event) => onChangeOpen(event.currentTarget)}
+              >
+                <Icon className="icon-more" />
+              </Button>
+            </ButtonGroup>
+          )}
+        </Dropdown>
+      </Box>
+    </>
+  );
+}
@@ -13,18 +13,24 @@ import { Loading } from '../Loading';
 
 import DefaultError from './DefaultError';
 import UnknownKey from './UnknownKey';
+import HostKeyChanged from './HostKeyChanged';
 import AuthenticationError from './AuthenticationError';
 import type { ErrorProps } from './common';
 
 const STATUS_BUTTONS = {
   ConnectFailed: DefaultError,
   UnknownKey: UnknownKey,
+  HostKeyChanged: HostKeyChanged,
   AuthenticationError: AuthenticationError,
   default: DefaultError,
 };
 
 type SSHLoadingProps = {
   sx?: SxProps<Theme>;
+  /**
+   * 服务端在认证前发送的 banner，通常是法律声明或警告
+   */
+  banner?: string;
 } & ErrorProps;
 
 export function SSHLoading({
@@ -32,6 +38,7 @@ export function SSHLoading({
   loading,
   error,
   sx,
+  banner,
   onReConnect,
   onReAuth,
   onRetry,
@@ -124,6 +131,26 @@ export function SSHLoading({
         >
           <LinearProgress color={error ? 'error' : 'primary'} />
         </Box>
+        {!!banner && (
+          <Box
+            sx={{
+              mx: 1.5,
+              my: 1,
+              p: 1.5,
+              maxHeight: 200,
+              overflow: 'auto',
+              fontSize: 12,
+              fontFamily: 'monospace',
+              whiteSpace: 'pre-wrap',
+              wordBreak: 'break-word',
+              userSelect: 'text',
+              borderRadius: 1,
+              bgcolor: (theme) => theme.palette.action.hover,
+            }}
+          >
+            {banner}
+          </Box>
+        )}
         {!!error && (
           <Box
             sx={{
@@ -1,13 +1,17 @@
 import { Buffer } from 'buffer';
 
 import { useRef, useState } from 'react';
-import { SSHSession, SSHShell } from 'tauri-plugin-ssh';
+import {
+  SSHSession,
+  SSHShell,
+  type SSHShellAgentForwarding,
+} from 'tauri-plugin-ssh';
 import { useRequest, useMemoizedFn, useUnmount } from 'ahooks';
-import type { Host } from 'tauri-plugin-data';
+import { AuthenticationMethod, type Host } from 'tauri-plugin-data';
 
 import { oscParse } from '@/utils/osc';
 import { Terminal, type TerminalSize } from '@/components/XTerminal';
-import { sleep } from '@/utils/sleep';
+import { useKeys } from '@/hooks/useKeys';
 
 export interface UseShellOpts {
   session?: SSHSession;
@@ -29,6 +33,7 @@ export function useShell({
   onCopy,
 }: UseShellOpts) {
   const [terminal, setTerminal] = useState<Terminal>();
+  const { data: keys } = useKeys();
 
   const shellRef = useRef<SSHShell>(null);
 
@@ -52,12 +57,38 @@ export function useShell({
         session,
         onData: (data: Uint8Array) => {
           oscParse(Buffer.from(data), { onCopy });
+          terminal.write(data, () => {
+            shell.ack(data.length);
+          });
+        },
+        onStderr: (data: Uint8Array) => {
           terminal.write(data);
         },
         onClose,
+        onReconnected: () => {
+          terminal.write('\r\n\x1b[33m[Reconnected]\x1b[0m\r\n');
+        },
       });
       shellRef.current = shell;
 
+      let agentForwarding: SSHShellAgentForwarding | undefined;
+      if (host?.agentForwarding) {
+        if (host.authenticationMethod === AuthenticationMethod.Agent) {
+          agentForwarding = {
+            type: 'Socket',
+            socketPath: host.agentSocketPath,
+          };
+        } else {
+          const key = keys.find((item) => item.id === host.keyId);
+          agentForwarding = {
+            type: 'Keys',
+            keys: key
+              ? [{ privateKey: key.privateKey, passphrase: key.passphrase }]
+              : [],
+          };
+        }
+      }
+
       await shell.open({
         term: host?.terminalType,
         envs: host?.envs?.reduce<Record<string, string>>((prev, cur) => {
@@ -75,12 +106,13 @@ export function useShell({
           width: terminal.element?.clientWidth ?? 0,
           height: terminal.element?.clientHeight ?? 0,
         },
+        agentForwarding,
+        x11Forwarding: host?.x11Forwarding ? {} : undefined,
+        flowControl: true,
+        startupScript: host?.startupCommand
+          ? [{ type: 'Send', data: host.startupCommand + '\n' }]
+          : undefined,
       });
-
-      if (host?.startupCommand) {
-        await sleep(200);
-        await shell.send(host.startupCommand + '\n');
-      }
     },
     {
       ready: !!terminal && !!session,
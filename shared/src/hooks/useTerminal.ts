@@ -1,6 +1,6 @@
 import { useMemoizedFn } from 'ahooks';
 import { SSHSessionCheckServerKey } from 'tauri-plugin-ssh';
-import { useMemo } from 'react';
+import { useEffect, useMemo } from 'react';
 import { last } from 'lodash-es';
 
 import {
@@ -127,6 +127,17 @@ export function useTerminal({ item, onClose, onCopy }: UseTerminalOpts) {
     await shellRunAsync();
   });
 
+  useEffect(() => {
+    if (!terminal || !item.reconnecting) {
+      return;
+    }
+
+    const { attempt, maxAttempts, delayMillis } = item.reconnecting;
+    terminal.write(
+      `\r\n\x1b[33m[Connection lost, reconnecting ${attempt}/${maxAttempts} in ${Math.ceil(delayMillis / 1000)}s]\x1b[0m\r\n`
+    );
+  }, [terminal, item.reconnecting]);
+
   const loading = useMemo(() => {
     if (
       item.jumpHostChain.some(
@@ -1,9 +1,14 @@
-import { cloneDeep, get } from 'lodash-es';
+import { cloneDeep, get, last } from 'lodash-es';
 import { AuthenticationMethod, type Host, type Key } from 'tauri-plugin-data';
 import {
+  AuthenticationMethod as SSHAuthenticationMethod,
   SSHSession,
   SSHSessionCheckServerKey,
+  SSHUpstreamProxyType,
+  type SSHUpstreamProxy,
+  type SSHSessionAuthenticationData,
   type SSHSessionDisconnectEvent,
+  type SSHSessionReconnectingEvent,
 } from 'tauri-plugin-ssh';
 
 export interface JumpHostChainItem {
@@ -13,16 +18,32 @@ export interface JumpHostChainItem {
   status: 'connecting' | 'connected' | 'authenticated';
   checkServerKey?: SSHSessionCheckServerKey;
   error?: unknown;
+  /**
+   * 服务端在认证前发送的 banner
+   */
+  banner?: string;
 }
 
 export interface ResolveJumpHostChainOpts {
   hostsMap: Map<string, Host>;
   onDisconnect?: (data: SSHSessionDisconnectEvent) => unknown;
+  onAuthBanner?: (jumpHostChainItem: JumpHostChainItem) => unknown;
+  /**
+   * 仅在主机配置了自动重连时触发，重连失败后触发 onDisconnect
+   */
+  onReconnecting?: (data: SSHSessionReconnectingEvent) => unknown;
+  onReconnected?: () => unknown;
 }
 
 export function resolveJumpHostChain(
   host: Host,
-  { hostsMap, onDisconnect }: ResolveJumpHostChainOpts
+  {
+    hostsMap,
+    onDisconnect,
+    onAuthBanner,
+    onReconnecting,
+    onReconnected,
+  }: ResolveJumpHostChainOpts
 ): JumpHostChainItem[] {
   const jumpHostIds = host.jumpHostIds || [];
 
@@ -36,9 +57,24 @@ export function resolveJumpHostChain(
 
   // save and connect 的时候，可能会存在 host 信息还没有刷新问题
   hosts.push(host);
-  return hosts.map((item) => {
+  const jumpHostChain: JumpHostChainItem[] = hosts.map((item) => {
     const jumpHostSession = new SSHSession({
       onDisconnect,
+      // 跳板机由插件连接，它们的 banner 也通过最后一台主机的会话发送
+      onAuthBanner: ({ data }) => {
+        const bannerItem =
+          jumpHostChain.find(
+            (it) =>
+              it.host.hostname === data.hostname && it.host.port === data.port
+          ) ?? last(jumpHostChain);
+        if (!bannerItem) {
+          return;
+        }
+        bannerItem.banner = data.banner;
+        onAuthBanner?.(bannerItem);
+      },
+      onReconnecting,
+      onReconnected,
     });
 
     return {
@@ -48,6 +84,8 @@ export function resolveJumpHostChain(
       status: 'connecting',
     };
   });
+
+  return jumpHostChain;
 }
 
 interface EstablishJumpHostChainConnectionsOpts {
@@ -55,91 +93,180 @@ interface EstablishJumpHostChainConnectionsOpts {
   onJumpHostChainItemUpdate?: (jumpHostChainItem: JumpHostChainItem) => unknown;
 }
 
+function getAuthenticationData(
+  host: Host,
+  keysMap: Map<string, Key>,
+  authenticationMethod = host.authenticationMethod
+): SSHSessionAuthenticationData {
+  const key = keysMap.get(host.keyId as string);
+
+  if (authenticationMethod === AuthenticationMethod.Password) {
+    return {
+      authenticationMethod: SSHAuthenticationMethod.Password,
+      password: host.password || '',
+      totpSecret: host.totpSecret,
+      keyboardInteractiveRules: host.keyboardInteractiveRules,
+    };
+  } else if (authenticationMethod === AuthenticationMethod.PublicKey) {
+    return {
+      authenticationMethod: SSHAuthenticationMethod.PublicKey,
+      privateKey: key?.privateKey || '',
+      passphrase: key?.passphrase || '',
+    };
+  } else if (authenticationMethod === AuthenticationMethod.Certificate) {
+    return {
+      authenticationMethod: SSHAuthenticationMethod.Certificate,
+      privateKey: key?.privateKey || '',
+      passphrase: key?.passphrase || '',
+      certificate: key?.certificate || '',
+    };
+  } else if (authenticationMethod === AuthenticationMethod.Agent) {
+    return {
+      authenticationMethod: SSHAuthenticationMethod.Agent,
+      socketPath: host.agentSocketPath,
+    };
+  } else if (authenticationMethod === AuthenticationMethod.KeyboardInteractive) {
+    return {
+      authenticationMethod: SSHAuthenticationMethod.KeyboardInteractive,
+      password: host.password || undefined,
+      totpSecret: host.totpSecret,
+      keyboardInteractiveRules: host.keyboardInteractiveRules,
+    };
+  }
+
+  return {
+    authenticationMethod: SSHAuthenticationMethod.KeyboardInteractive,
+    prompts: [],
+  };
+}
+
+/**
+ * 部分认证成功后的认证步骤，凭据与主认证方法一样取自主机配置
+ */
+function getAuthenticationChain(
+  host: Host,
+  keysMap: Map<string, Key>
+): SSHSessionAuthenticationData[] | undefined {
+  return host.authenticationChain?.map((authenticationMethod) =>
+    getAuthenticationData(host, keysMap, authenticationMethod)
+  );
+}
+
+function getUpstreamProxy(host: Host): SSHUpstreamProxy | undefined {
+  if (!host.upstreamProxy) {
+    return undefined;
+  }
+
+  return {
+    ...host.upstreamProxy,
+    type: SSHUpstreamProxyType[host.upstreamProxy.type],
+  };
+}
+
+/**
+ * 跳板机由插件按顺序连接和认证，前端只连接链路中的最后一台主机，
+ * 跳板机连接失败时根据错误中的 hop 定位到对应的链路节点
+ */
 export async function establishJumpHostChainConnections(
   jumpHostChain: JumpHostChainItem[],
   { keysMap, onJumpHostChainItemUpdate }: EstablishJumpHostChainConnectionsOpts
 ) {
-  let prevJumpHostSession: SSHSession | undefined = undefined;
+  const item = last(jumpHostChain);
+  if (!item) {
+    return undefined;
+  }
 
-  for (const item of jumpHostChain) {
-    try {
-      item.loading = true;
-      item.error = undefined;
-      onJumpHostChainItemUpdate?.(item);
+  const jumpHostItems = jumpHostChain.slice(0, -1);
+  const pendingItems = jumpHostChain.filter(
+    (it) => it.status !== 'authenticated'
+  );
 
-      if (item.status === 'connecting') {
-        await item.session.connect(
-          {
-            hostname: item.host.hostname,
-            port: item.host.port,
-            jumpHostSshSessionId: prevJumpHostSession?.sshSessionId,
-          },
-          item.checkServerKey
-        );
-        item.status = 'connected';
-        onJumpHostChainItemUpdate?.(item);
-      }
+  try {
+    pendingItems.forEach((it) => {
+      it.loading = true;
+      it.error = undefined;
+      onJumpHostChainItemUpdate?.(it);
+    });
 
-      if (item.status === 'connected') {
-        const key = keysMap.get(item.host.keyId as string);
-
-        if (item.host.authenticationMethod === AuthenticationMethod.Password) {
-          await item.session.authenticate_password({
-            username: item.host.username,
-            password: item.host.password || '',
-          });
-        } else if (
-          item.host.authenticationMethod === AuthenticationMethod.PublicKey
-        ) {
-          await item.session.authenticate_public_key({
-            username: item.host.username,
-            privateKey: key?.privateKey || '',
-            passphrase: key?.passphrase || '',
-          });
-        } else if (
-          item.host.authenticationMethod === AuthenticationMethod.Certificate
-        ) {
-          await item.session.authenticate_certificate({
-            username: item.host.username,
-            privateKey: key?.privateKey || '',
-            passphrase: key?.passphrase || '',
-            certificate: key?.certificate || '',
-          });
-        } else {
-          await item.session.authenticate_keyboard_interactive({
-            username: item.host.username,
-            prompts: [],
-          });
-        }
+    if (item.status === 'connecting') {
+      await item.session.connect(
+        {
+          hostname: item.host.hostname,
+          port: item.host.port,
+          username: item.host.username,
+          proxyCommand: item.host.proxyCommand,
+          upstreamProxy: getUpstreamProxy(item.host),
+          jumpHosts: jumpHostItems.map((it) => ({
+            hostname: it.host.hostname,
+            port: it.host.port,
+            username: it.host.username,
+            checkServerKey: it.checkServerKey,
+            strictHostKeyChecking: it.host.strictHostKeyChecking,
+            proxyCommand: it.host.proxyCommand,
+            upstreamProxy: getUpstreamProxy(it.host),
+            authenticationData: getAuthenticationData(it.host, keysMap),
+            authenticationChain: getAuthenticationChain(it.host, keysMap),
+            connectionProfile: it.host.connectionProfile,
+          })),
+          strictHostKeyChecking: item.host.strictHostKeyChecking,
+          reconnect: item.host.reconnect,
+          multiplex: item.host.multiplex,
+          connectionProfile: item.host.connectionProfile,
+        },
+        item.checkServerKey
+      );
+      jumpHostItems.forEach((it) => {
+        it.status = 'authenticated';
+        onJumpHostChainItemUpdate?.(it);
+      });
+      item.status = 'connected';
+      onJumpHostChainItemUpdate?.(item);
+    }
 
-        item.status = 'authenticated';
-        onJumpHostChainItemUpdate?.(item);
-      }
+    if (item.status === 'connected') {
+      await item.session.authenticate({
+        username: item.host.username,
+        authenticationData: getAuthenticationData(item.host, keysMap),
+        authenticationChain: getAuthenticationChain(item.host, keysMap),
+      });
 
-      prevJumpHostSession = item.session;
-    } catch (error) {
+      item.status = 'authenticated';
+      onJumpHostChainItemUpdate?.(item);
+    }
+  } catch (error) {
+    if (get(error, 'type') === 'JumpHostConnectFailed') {
+      const hop = get(error, 'hop') as number;
+      jumpHostItems.forEach((it, index) => {
+        it.status = index < hop ? 'authenticated' : 'connecting';
+      });
+      const failedItem = jumpHostItems[hop] ?? item;
+      failedItem.error = get(error, 'error', error);
+    } else {
       item.error = error;
       const errorKind = get(error, 'kind');
       if (errorKind === 'NotFoundSession' || errorKind === 'Timeout') {
         item.status = 'connecting';
       }
-
-      throw error;
-    } finally {
-      item.loading = false;
-      onJumpHostChainItemUpdate?.(item);
     }
+
+    throw error;
+  } finally {
+    jumpHostChain.forEach((it) => {
+      it.loading = false;
+      onJumpHostChainItemUpdate?.(it);
+    });
   }
 
-  return prevJumpHostSession;
+  return item.session;
 }
 
+/**
+ * 跳板机会话由插件做引用计数，只需要断开链路中的最后一台主机
+ */
 export async function tearDownJumpHostChainConnections(
   jumpHostChain: JumpHostChainItem[]
 ) {
-  for (const { session } of [...jumpHostChain].reverse()) {
-    await session.disconnect();
-  }
+  await last(jumpHostChain)?.session.disconnect();
 }
 
 // export async function startPortForwarding(
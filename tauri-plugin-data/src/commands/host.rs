@@ -31,6 +31,18 @@ pub struct HostBase {
   #[serde_as(as = "Option<Vec<DisplayFromStr>>")]
   jump_host_ids: Option<Vec<i64>>,
   terminal_settings: Option<entities::hosts::TerminalSettings>,
+  agent_socket_path: Option<String>,
+  agent_forwarding: Option<bool>,
+  strict_host_key_checking: Option<entities::hosts::StrictHostKeyChecking>,
+  proxy_command: Option<String>,
+  upstream_proxy: Option<entities::hosts::UpstreamProxy>,
+  x11_forwarding: Option<bool>,
+  totp_secret: Option<String>,
+  keyboard_interactive_rules: Option<Vec<entities::hosts::KeyboardInteractiveRule>>,
+  authentication_chain: Option<Vec<entities::hosts::AuthenticationMethod>>,
+  reconnect: Option<entities::hosts::ReconnectPolicy>,
+  multiplex: Option<entities::hosts::MultiplexOpts>,
+  connection_profile: Option<entities::hosts::ConnectionProfile>,
 }
 impl ModelConvert for HostBase {
   type Model = entities::hosts::Model;
@@ -50,6 +62,29 @@ async fn from_model<R: Runtime>(
       None
     };
 
+    let totp_secret = if let Some(totp_secret) = model.totp_secret {
+      let decrypted = crypto_manager.decrypt(&totp_secret).await?;
+      Some(String::from_utf8(decrypted)?)
+    } else {
+      None
+    };
+
+    let keyboard_interactive_rules =
+      if let Some(keyboard_interactive_rules) = model.keyboard_interactive_rules {
+        let decrypted = crypto_manager.decrypt(&keyboard_interactive_rules).await?;
+        Some(serde_json::from_slice(&decrypted)?)
+      } else {
+        None
+      };
+
+    let mut upstream_proxy = model.upstream_proxy;
+    if let Some(upstream_proxy) = &mut upstream_proxy
+      && let Some(password) = model.upstream_proxy_password
+    {
+      let decrypted = crypto_manager.decrypt(&password).await?;
+      upstream_proxy.password = Some(String::from_utf8(decrypted)?);
+    }
+
     Ok(HostBase {
       name: model.name,
       tags: model.tags.map(|v| v.into()),
@@ -64,6 +99,18 @@ async fn from_model<R: Runtime>(
       envs: model.envs.map(|v| v.into()),
       jump_host_ids: model.jump_host_ids.map(|v| v.into()),
       terminal_settings: model.terminal_settings,
+      agent_socket_path: model.agent_socket_path,
+      agent_forwarding: model.agent_forwarding,
+      strict_host_key_checking: model.strict_host_key_checking,
+      proxy_command: model.proxy_command,
+      upstream_proxy,
+      x11_forwarding: model.x11_forwarding,
+      totp_secret,
+      keyboard_interactive_rules,
+      authentication_chain: model.authentication_chain.map(|v| v.into()),
+      reconnect: model.reconnect,
+      multiplex: model.multiplex,
+      connection_profile: model.connection_profile,
     })
   }
 
@@ -78,6 +125,27 @@ async fn into_active_model<R: Runtime>(
     } else {
       None
     };
+    let totp_secret = if let Some(totp_secret) = &self.totp_secret {
+      Some(crypto_manager.encrypt(totp_secret.as_bytes()).await?)
+    } else {
+      None
+    };
+    let keyboard_interactive_rules =
+      if let Some(keyboard_interactive_rules) = &self.keyboard_interactive_rules {
+        let json = serde_json::to_vec(keyboard_interactive_rules)?;
+        Some(crypto_manager.encrypt(&json).await?)
+      } else {
+        None
+      };
+    let mut upstream_proxy = self.upstream_proxy.clone();
+    let upstream_proxy_password = if let Some(password) = upstream_proxy
+      .as_mut()
+      .and_then(|upstream_proxy| upstream_proxy.password.take())
+    {
+      Some(crypto_manager.encrypt(password.as_bytes()).await?)
+    } else {
+      None
+    };
 
     let active_model = Self::ActiveModel {
       name: ActiveValue::Set(self.name.clone()),
@@ -93,6 +161,19 @@ async fn into_active_model<R: Runtime>(
       envs: ActiveValue::Set(self.envs.clone().map(|v| v.into())),
       jump_host_ids: ActiveValue::Set(self.jump_host_ids.clone().map(|v| v.into())),
       terminal_settings: ActiveValue::Set(self.terminal_settings.clone()),
+      agent_socket_path: ActiveValue::Set(self.agent_socket_path.clone()),
+      agent_forwarding: ActiveValue::Set(self.agent_forwarding),
+      strict_host_key_checking: ActiveValue::Set(self.strict_host_key_checking.clone()),
+      proxy_command: ActiveValue::Set(self.proxy_command.clone()),
+      upstream_proxy: ActiveValue::Set(upstream_proxy),
+      x11_forwarding: ActiveValue::Set(self.x11_forwarding),
+      totp_secret: ActiveValue::Set(totp_secret),
+      keyboard_interactive_rules: ActiveValue::Set(keyboard_interactive_rules),
+      authentication_chain: ActiveValue::Set(self.authentication_chain.clone().map(|v| v.into())),
+      reconnect: ActiveValue::Set(self.reconnect.clone()),
+      multiplex: ActiveValue::Set(self.multiplex.clone()),
+      connection_profile: ActiveValue::Set(self.connection_profile.clone()),
+      upstream_proxy_password: ActiveValue::Set(upstream_proxy_password),
       ..Default::default()
     };
 
@@ -38,6 +38,49 @@ pub enum AuthenticationMethod {
   PublicKey,
   #[sea_orm(num_value = 2)]
   Certificate,
+  #[sea_orm(num_value = 3)]
+  Agent,
+  #[sea_orm(num_value = 4)]
+  KeyboardInteractive,
+}
+
+/// Authentication methods continued with after the server accepts
+/// `authentication_method` as a partial success, in order
+#[derive(Clone, Debug, FromJsonQueryResult, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticationChain(Vec<AuthenticationMethod>);
+
+impl From<Vec<AuthenticationMethod>> for AuthenticationChain {
+  fn from(value: Vec<AuthenticationMethod>) -> Self {
+    Self(value)
+  }
+}
+
+impl From<AuthenticationChain> for Vec<AuthenticationMethod> {
+  fn from(val: AuthenticationChain) -> Self {
+    val.0
+  }
+}
+
+impl Deref for AuthenticationChain {
+  type Target = Vec<AuthenticationMethod>;
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+#[derive(Clone, Debug, EnumIter, DeriveActiveEnum, PartialEq, Eq, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[serde(rename_all = "kebab-case")]
+pub enum StrictHostKeyChecking {
+  #[sea_orm(num_value = 0)]
+  Ask,
+  #[sea_orm(num_value = 1)]
+  AcceptNew,
+  #[sea_orm(num_value = 2)]
+  Yes,
+  #[sea_orm(num_value = 3)]
+  Off,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -101,6 +144,77 @@ pub struct TerminalSettings {
   pub theme: Option<String>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpstreamProxyType {
+  Socks4,
+  Socks5,
+  Http,
+}
+
+#[derive(Clone, Debug, FromJsonQueryResult, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamProxy {
+  pub r#type: UpstreamProxyType,
+  pub hostname: String,
+  pub port: u16,
+  pub username: Option<String>,
+  /// Kept encrypted in `upstream_proxy_password` rather than in this JSON
+  pub password: Option<String>,
+}
+
+/// Reconnects a dropped session, unset fields use the defaults of the ssh
+/// plugin
+#[derive(Clone, Debug, FromJsonQueryResult, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectPolicy {
+  pub max_attempts: Option<u32>,
+  pub initial_delay_millis: Option<u64>,
+  pub max_delay_millis: Option<u64>,
+}
+
+/// Shares the connection with other tabs of the host, kept open for
+/// `linger_secs` after the last one closes, passed through to the ssh plugin
+#[derive(Clone, Debug, FromJsonQueryResult, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplexOpts {
+  pub linger_secs: Option<u64>,
+}
+
+/// Transport parameters of the connection, unset fields use the defaults of
+/// the ssh plugin
+#[derive(Clone, Debug, FromJsonQueryResult, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionProfile {
+  pub connect_timeout_secs: Option<u64>,
+  pub inactivity_timeout_secs: Option<u64>,
+  pub keepalive_interval_secs: Option<u64>,
+  pub keepalive_max: Option<usize>,
+  pub compression: Option<bool>,
+  pub kex_algorithms: Option<Vec<String>>,
+  pub host_key_algorithms: Option<Vec<String>>,
+  pub ciphers: Option<Vec<String>>,
+  pub macs: Option<Vec<String>>,
+  pub rekey_limit_bytes: Option<u64>,
+  pub rekey_time_limit_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyboardInteractiveAnswer {
+  Password,
+  Totp,
+  Fixed,
+}
+
+/// Answers keyboard-interactive prompts matching `pattern`, `value` is the
+/// answer of `Fixed`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardInteractiveRule {
+  pub pattern: String,
+  pub answer: KeyboardInteractiveAnswer,
+  pub value: Option<String>,
+}
+
 #[derive(Clone, Debug, DeriveEntityModel, PartialEq, Eq)]
 #[sea_orm(table_name = "hosts")]
 pub struct Model {
@@ -122,6 +236,23 @@ pub struct Model {
   pub envs: Option<Envs>,
   pub jump_host_ids: Option<JumpHostIds>,
   pub terminal_settings: Option<TerminalSettings>,
+  pub agent_socket_path: Option<String>,
+  pub agent_forwarding: Option<bool>,
+  pub strict_host_key_checking: Option<StrictHostKeyChecking>,
+  pub proxy_command: Option<String>,
+  pub upstream_proxy: Option<UpstreamProxy>,
+  pub x11_forwarding: Option<bool>,
+  #[sea_orm(column_type = "Blob", nullable)]
+  pub totp_secret: Option<Vec<u8>>,
+  /// Encrypted JSON of `Vec<KeyboardInteractiveRule>`, answers may be secrets
+  #[sea_orm(column_type = "Blob", nullable)]
+  pub keyboard_interactive_rules: Option<Vec<u8>>,
+  pub authentication_chain: Option<AuthenticationChain>,
+  pub reconnect: Option<ReconnectPolicy>,
+  pub multiplex: Option<MultiplexOpts>,
+  pub connection_profile: Option<ConnectionProfile>,
+  #[sea_orm(column_type = "Blob", nullable)]
+  pub upstream_proxy_password: Option<Vec<u8>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
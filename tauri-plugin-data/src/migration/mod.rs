@@ -2,6 +2,19 @@
 mod m20251021_000001_alter_table;
 mod m20251024_000001_alter_table;
 mod m20251027_000001_alter_table;
+mod m20261018_000001_alter_table;
+mod m20261018_000002_alter_table;
+mod m20261018_000003_alter_table;
+mod m20261018_000004_alter_table;
+mod m20261018_000005_alter_table;
+mod m20261018_000006_alter_table;
+mod m20261018_000007_alter_table;
+mod m20261018_000008_alter_table;
+mod m20261018_000009_alter_table;
+mod m20261018_000010_alter_table;
+mod m20261018_000011_alter_table;
+mod m20261018_000012_alter_table;
+mod m20261018_000013_alter_table;
 
 pub use sea_orm_migration::prelude::*;
 
@@ -15,6 +28,19 @@ fn migrations() -> Vec<Box<dyn MigrationTrait>> {
       Box::new(m20251021_000001_alter_table::Migration),
       Box::new(m20251024_000001_alter_table::Migration),
       Box::new(m20251027_000001_alter_table::Migration),
+      Box::new(m20261018_000001_alter_table::Migration),
+      Box::new(m20261018_000002_alter_table::Migration),
+      Box::new(m20261018_000003_alter_table::Migration),
+      Box::new(m20261018_000004_alter_table::Migration),
+      Box::new(m20261018_000005_alter_table::Migration),
+      Box::new(m20261018_000006_alter_table::Migration),
+      Box::new(m20261018_000007_alter_table::Migration),
+      Box::new(m20261018_000008_alter_table::Migration),
+      Box::new(m20261018_000009_alter_table::Migration),
+      Box::new(m20261018_000010_alter_table::Migration),
+      Box::new(m20261018_000011_alter_table::Migration),
+      Box::new(m20261018_000012_alter_table::Migration),
+      Box::new(m20261018_000013_alter_table::Migration),
     ]
   }
 }
@@ -10,6 +10,123 @@ export enum AuthenticationMethod {
   Password = 'Password',
   PublicKey = 'PublicKey',
   Certificate = 'Certificate',
+  Agent = 'Agent',
+  KeyboardInteractive = 'KeyboardInteractive',
+}
+
+/**
+ * 对应 OpenSSH 的 StrictHostKeyChecking
+ */
+export enum StrictHostKeyChecking {
+  Ask = 'ask',
+  AcceptNew = 'accept-new',
+  Yes = 'yes',
+  Off = 'off',
+}
+
+export enum UpstreamProxyType {
+  Socks4 = 'Socks4',
+  Socks5 = 'Socks5',
+  Http = 'Http',
+}
+
+export interface HostUpstreamProxy {
+  type: UpstreamProxyType;
+  hostname: string;
+  port: number;
+  username?: string;
+  password?: string;
+}
+
+/**
+ * 连接意外断开后自动重连，未设置的字段使用默认值
+ */
+export interface HostReconnectPolicy {
+  /**
+   * 最大重试次数，默认 5
+   */
+  maxAttempts?: number;
+  /**
+   * 首次重试前的等待时间，之后每次翻倍，默认 1000
+   */
+  initialDelayMillis?: number;
+  /**
+   * 等待时间上限，默认 30000
+   */
+  maxDelayMillis?: number;
+}
+
+/**
+ * 同一主机的多个标签页共用一个连接
+ */
+export interface HostMultiplexOpts {
+  /**
+   * 最后一个标签页关闭后连接保持的秒数，默认 60
+   */
+  lingerSecs?: number;
+}
+
+/**
+ * 连接参数，未设置的字段使用默认值
+ */
+export interface HostConnectionProfile {
+  /**
+   * 连接和密钥交换的超时秒数，默认 5
+   */
+  connectTimeoutSecs?: number;
+  /**
+   * 空闲断开的秒数，默认 1800，0 表示不断开
+   */
+  inactivityTimeoutSecs?: number;
+  /**
+   * 心跳间隔秒数，默认 5，0 表示不发送心跳
+   */
+  keepaliveIntervalSecs?: number;
+  /**
+   * 心跳无响应多少次后断开，默认 3
+   */
+  keepaliveMax?: number;
+  /**
+   * 为空时使用默认的压缩算法
+   */
+  compression?: boolean;
+  /**
+   * 算法列表按优先级排列，且只协商列表中的算法，
+   * 旧设备所需的 diffie-hellman-group1-sha1、ssh-rsa、aes128-cbc 等需显式列出
+   */
+  kexAlgorithms?: string[];
+  hostKeyAlgorithms?: string[];
+  ciphers?: string[];
+  macs?: string[];
+  /**
+   * 传输多少字节后重新协商密钥，默认 1GiB
+   */
+  rekeyLimitBytes?: number;
+  /**
+   * 多少秒后重新协商密钥，默认 3600
+   */
+  rekeyTimeLimitSecs?: number;
+}
+
+export enum KeyboardInteractiveAnswer {
+  Password = 'Password',
+  Totp = 'Totp',
+  Fixed = 'Fixed',
+}
+
+/**
+ * keyboard-interactive 提示的自动应答规则，按顺序匹配
+ */
+export interface HostKeyboardInteractiveRule {
+  /**
+   * 匹配提示文本的正则
+   */
+  pattern: string;
+  answer: KeyboardInteractiveAnswer;
+  /**
+   * answer 为 Fixed 时的应答内容
+   */
+  value?: string;
 }
 
 export interface Env {
@@ -25,7 +142,20 @@ export interface Host {
   port: number;
   username: string;
   authenticationMethod: AuthenticationMethod;
+  /**
+   * 服务端要求多个认证方法时（AuthenticationMethods publickey,password），
+   * authenticationMethod 部分认证成功后依次尝试的认证方法，凭据取自当前主机
+   */
+  authenticationChain?: AuthenticationMethod[];
   password?: string;
+  /**
+   * TOTP 密钥，base32 或 otpauth:// 链接，服务端要求验证码时自动应答
+   */
+  totpSecret?: string;
+  /**
+   * keyboard-interactive 提示的自动应答规则
+   */
+  keyboardInteractiveRules?: HostKeyboardInteractiveRule[];
   keyId?: string;
   startupCommand?: string;
   terminalType?: string;
@@ -68,6 +198,42 @@ export interface Host {
    */
   jumpHostIds?: string[];
   terminalSettings?: HostTerminalSettings;
+  /**
+   * ssh-agent 的 socket 路径，为空时使用 SSH_AUTH_SOCK
+   */
+  agentSocketPath?: string;
+  /**
+   * 是否在 shell 上开启 ssh-agent 转发
+   */
+  agentForwarding?: boolean;
+  /**
+   * 是否在 shell 上开启 X11 转发，转发到本地 DISPLAY
+   */
+  x11Forwarding?: boolean;
+  /**
+   * 主机公钥校验策略，为空时为 ask
+   */
+  strictHostKeyChecking?: StrictHostKeyChecking;
+  /**
+   * 本地执行的代理命令，支持 %h %p %r，例如 cloudflared access ssh --hostname %h
+   */
+  proxyCommand?: string;
+  /**
+   * SOCKS/HTTP 代理，为空时直连
+   */
+  upstreamProxy?: HostUpstreamProxy;
+  /**
+   * 自动重连策略，为空时不重连
+   */
+  reconnect?: HostReconnectPolicy;
+  /**
+   * 连接复用，为空时每个标签页单独连接
+   */
+  multiplex?: HostMultiplexOpts;
+  /**
+   * 超时、心跳、压缩和算法偏好，为空时使用默认值
+   */
+  connectionProfile?: HostConnectionProfile;
 }
 
 export async function getHosts(): Promise<Host[]> {
@@ -1,11 +1,28 @@
 const COMMANDS: &[&str] = &[
   "session_connect",
   "session_authenticate",
+  "session_probe_auth",
+  "session_info",
   "session_disconnect",
+  "session_set_upstream_proxy",
+  "session_get_upstream_proxy",
+  "session_exec",
+  "session_exec_cancel",
   "shell_open",
   "shell_close",
   "shell_resize",
   "shell_send",
+  "shell_output_ack",
+  "shell_signal",
+  "shell_break",
+  "shell_broadcast",
+  "shell_broadcast_group_set",
+  "shell_broadcast_group_list",
+  "shell_recording_start",
+  "shell_recording_stop",
+  "shell_recording_list",
+  "shell_recording_export",
+  "shell_recording_remove",
   "port_forwarding_local_open",
   "port_forwarding_local_close",
   "port_forwarding_remote_open",
@@ -24,6 +41,11 @@
   "sftp_rename",
   "sftp_exists",
   "sftp_canonicalize",
+  "known_hosts_list",
+  "known_hosts_search",
+  "known_hosts_remove",
+  "known_hosts_replace",
+  "known_hosts_hash",
 ];
 
 fn main() {
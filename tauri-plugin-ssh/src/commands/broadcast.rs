@@ -0,0 +1,110 @@
+use std::{
+  collections::{HashMap, HashSet},
+  time::Duration,
+};
+
+use futures::future::join_all;
+use serde::Serialize;
+use tauri::{AppHandle, Runtime, State};
+use tokio::time::timeout;
+
+use crate::{
+  commands::shell::SSHShellId,
+  error::{SSHError, SSHResult},
+  ssh_manager::SSHManager,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHShellBroadcastResult {
+  pub ssh_shell_id: SSHShellId,
+  /// `None` when the input was sent
+  pub error: Option<SSHError>,
+}
+
+/// Sends `data` to every shell in `ssh_shell_ids` and in the broadcast group
+/// `group` at once, a failing shell does not stop the others
+#[tauri::command]
+pub async fn shell_broadcast<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_shell_ids: Option<Vec<SSHShellId>>,
+  group: Option<String>,
+  data: String,
+) -> SSHResult<Vec<SSHShellBroadcastResult>> {
+  let mut targets = ssh_shell_ids.unwrap_or_default();
+  if let Some(group) = group {
+    let broadcast_groups = ssh_manager.broadcast_groups.lock().await;
+    let members = broadcast_groups
+      .get(&group)
+      .ok_or_else(|| SSHError::new(format!("Not found broadcast group {}", group)))?;
+    targets.extend(members.iter().copied());
+  }
+
+  let mut seen = HashSet::new();
+  targets.retain(|ssh_shell_id| seen.insert(*ssh_shell_id));
+
+  let results = join_all(targets.iter().map(|ssh_shell_id| {
+    let shell = ssh_manager.shells.get(ssh_shell_id);
+    let data = data.as_str();
+    async move {
+      let shell = shell.ok_or(SSHError::NotFoundShell)?;
+      timeout(Duration::from_secs(5), shell.data(data.as_bytes())).await??;
+      shell.record(|recorder| recorder.input(data));
+
+      Ok::<(), SSHError>(())
+    }
+  }))
+  .await;
+
+  let results = targets
+    .into_iter()
+    .zip(results)
+    .map(|(ssh_shell_id, result)| {
+      if let Err(err) = &result {
+        log::warn!("shell broadcast {:?} failed: {}", ssh_shell_id, err);
+      }
+
+      SSHShellBroadcastResult {
+        ssh_shell_id,
+        error: result.err(),
+      }
+    })
+    .collect();
+
+  Ok(results)
+}
+
+/// Creates or replaces a named broadcast group, an empty list removes it
+#[tauri::command]
+pub async fn shell_broadcast_group_set<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  group: String,
+  ssh_shell_ids: Vec<SSHShellId>,
+) -> SSHResult<String> {
+  let mut broadcast_groups = ssh_manager.broadcast_groups.lock().await;
+
+  if ssh_shell_ids.is_empty() {
+    broadcast_groups.remove(&group);
+  } else {
+    broadcast_groups.insert(group.clone(), ssh_shell_ids.into_iter().collect());
+  }
+
+  Ok(group)
+}
+
+#[tauri::command]
+pub async fn shell_broadcast_group_list<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+) -> SSHResult<HashMap<String, Vec<SSHShellId>>> {
+  let broadcast_groups = ssh_manager.broadcast_groups.lock().await;
+
+  let groups = broadcast_groups
+    .iter()
+    .map(|(group, members)| (group.clone(), members.iter().copied().collect()))
+    .collect();
+
+  Ok(groups)
+}
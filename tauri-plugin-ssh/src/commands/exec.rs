@@ -0,0 +1,180 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use russh::ChannelMsg;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State, async_runtime, ipc::Channel};
+use tokio::{select, sync::Notify, time::timeout};
+use uuid::Uuid;
+
+use crate::{
+  commands::session::{SSHChannelKind, SSHSessionId},
+  error::{SSHError, SSHResult},
+  ssh_manager::SSHManager,
+  utils::signal_name,
+};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct SSHExecId(Uuid);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum SSHExecIpcChannelData {
+  Stdout(Vec<u8>),
+  Stderr(Vec<u8>),
+  ExitStatus(u32),
+  #[serde(rename_all = "camelCase")]
+  ExitSignal {
+    signal_name: String,
+    core_dumped: bool,
+    error_message: String,
+  },
+  /// The server refused to run the command
+  Failure,
+  Timeout,
+  Cancel,
+  Close,
+}
+
+pub struct SSHExec {
+  pub ssh_session_id: SSHSessionId,
+  pub notify: Arc<Notify>,
+}
+
+/// Runs `command` without a PTY, stdout and stderr are streamed separately and
+/// the run ends with its exit status or signal followed by `Close`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn session_exec<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
+  ssh_exec_id: SSHExecId,
+  command: String,
+  envs: Option<HashMap<String, String>>,
+  timeout_secs: Option<u64>,
+  ipc_channel: Channel<SSHExecIpcChannelData>,
+) -> SSHResult<SSHExecId> {
+  let mut channel = timeout(Duration::from_secs(5), async {
+    log::info!("exec open {:?} {:?}", ssh_session_id, ssh_exec_id);
+    let channel = {
+      let session = ssh_manager
+        .sessions
+        .get(&ssh_session_id)
+        .ok_or(SSHError::NotFoundSession)?;
+      session.handle().await.channel_open_session().await?
+    };
+    ssh_manager.channel_opened(ssh_session_id, channel.id(), SSHChannelKind::Exec);
+
+    for (key, value) in envs.unwrap_or_default() {
+      channel.set_env(true, key.as_str(), value.as_str()).await?;
+    }
+
+    channel.exec(true, command.as_bytes()).await?;
+
+    Ok::<_, SSHError>(channel)
+  })
+  .await??;
+
+  let notify = Arc::new(Notify::new());
+  ssh_manager.execs.insert(
+    ssh_exec_id,
+    Arc::new(SSHExec {
+      ssh_session_id,
+      notify: notify.clone(),
+    }),
+  );
+
+  async_runtime::spawn(async move {
+    let result = async {
+      let deadline = async {
+        match timeout_secs {
+          Some(timeout_secs) => tokio::time::sleep(Duration::from_secs(timeout_secs)).await,
+          None => std::future::pending().await,
+        }
+      };
+      tokio::pin!(deadline);
+
+      loop {
+        select! {
+          _ = notify.notified() => {
+            log::info!("exec cancel {:?}", ssh_exec_id);
+            ipc_channel.send(SSHExecIpcChannelData::Cancel)?;
+            channel.close().await?;
+            break;
+          },
+          _ = &mut deadline => {
+            log::info!("exec timeout {:?}", ssh_exec_id);
+            ipc_channel.send(SSHExecIpcChannelData::Timeout)?;
+            channel.close().await?;
+            break;
+          },
+          msg = channel.wait() => {
+            match msg {
+              Some(ChannelMsg::Data { data }) => {
+                ipc_channel.send(SSHExecIpcChannelData::Stdout(data.to_vec()))?;
+              }
+              Some(ChannelMsg::ExtendedData { data, ext }) if ext == 1 => {
+                ipc_channel.send(SSHExecIpcChannelData::Stderr(data.to_vec()))?;
+              }
+              Some(ChannelMsg::ExitStatus { exit_status }) => {
+                ipc_channel.send(SSHExecIpcChannelData::ExitStatus(exit_status))?;
+              }
+              Some(ChannelMsg::ExitSignal {
+                signal_name: signal,
+                core_dumped,
+                error_message,
+                ..
+              }) => {
+                ipc_channel.send(SSHExecIpcChannelData::ExitSignal {
+                  signal_name: signal_name(signal),
+                  core_dumped,
+                  error_message,
+                })?;
+              }
+              Some(ChannelMsg::Failure) => {
+                ipc_channel.send(SSHExecIpcChannelData::Failure)?;
+                channel.close().await?;
+                break;
+              }
+              Some(ChannelMsg::Close) | None => break,
+              Some(_) => {}
+            }
+          },
+        }
+      }
+
+      Ok::<(), SSHError>(())
+    }
+    .await;
+
+    // Also sent when the connection went away under the channel
+    if let Err(err) = ipc_channel.send(SSHExecIpcChannelData::Close) {
+      log::warn!("exec {:?} close send failed: {}", ssh_exec_id, err);
+    }
+
+    {
+      let ssh_manager = app_handle.state::<SSHManager<R>>();
+      ssh_manager.execs.remove(&ssh_exec_id);
+    }
+
+    log::info!("exec close {:?}", ssh_exec_id);
+    if let Err(err) = result {
+      log::error!("exec {:?} failed: {}", ssh_exec_id, err);
+    }
+  });
+
+  Ok(ssh_exec_id)
+}
+
+#[tauri::command]
+pub async fn session_exec_cancel<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_exec_id: SSHExecId,
+) -> SSHResult<SSHExecId> {
+  if let Some(exec) = ssh_manager.execs.get(&ssh_exec_id) {
+    exec.notify.notify_one();
+  }
+
+  Ok(ssh_exec_id)
+}
@@ -0,0 +1,69 @@
+use russh::keys::PublicKey;
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+  SSHError, SSHResult,
+  known_hosts::{self, KnownHostsEntry},
+  utils::get_known_hosts_path,
+};
+
+#[tauri::command]
+pub async fn known_hosts_list<R: Runtime>(
+  app_handle: AppHandle<R>,
+) -> SSHResult<Vec<KnownHostsEntry>> {
+  let known_hosts_path = get_known_hosts_path(&app_handle)?;
+
+  known_hosts::read_entries(&known_hosts_path)
+}
+
+#[tauri::command]
+pub async fn known_hosts_search<R: Runtime>(
+  app_handle: AppHandle<R>,
+  keyword: String,
+) -> SSHResult<Vec<KnownHostsEntry>> {
+  let known_hosts_path = get_known_hosts_path(&app_handle)?;
+  let keyword = keyword.trim();
+
+  let entries = known_hosts::read_entries(&known_hosts_path)?
+    .into_iter()
+    .filter(|entry| keyword.is_empty() || entry.matches_keyword(keyword))
+    .collect();
+
+  Ok(entries)
+}
+
+#[tauri::command]
+pub async fn known_hosts_remove<R: Runtime>(
+  app_handle: AppHandle<R>,
+  hostname: String,
+  port: u16,
+) -> SSHResult<usize> {
+  let known_hosts_path = get_known_hosts_path(&app_handle)?;
+
+  log::info!("known hosts remove {}:{}", hostname, port);
+  known_hosts::remove(&known_hosts_path, &hostname, port)
+}
+
+#[tauri::command]
+pub async fn known_hosts_replace<R: Runtime>(
+  app_handle: AppHandle<R>,
+  hostname: String,
+  port: u16,
+  public_key: String,
+) -> SSHResult<()> {
+  let known_hosts_path = get_known_hosts_path(&app_handle)?;
+
+  let public_key = PublicKey::from_openssh(public_key.trim())
+    .map_err(|_| SSHError::new("Invalid public key, expected OpenSSH format"))?;
+
+  log::info!("known hosts replace {}:{}", hostname, port);
+  known_hosts::replace(&known_hosts_path, &hostname, port, &public_key)
+}
+
+#[tauri::command]
+pub async fn known_hosts_hash<R: Runtime>(app_handle: AppHandle<R>) -> SSHResult<usize> {
+  let known_hosts_path = get_known_hosts_path(&app_handle)?;
+
+  log::info!("known hosts hash");
+  known_hosts::hash(&known_hosts_path)
+}
@@ -1,4 +1,8 @@
+pub(crate) mod broadcast;
+pub(crate) mod exec;
+pub(crate) mod known_hosts;
 pub(crate) mod port_forwarding;
+pub(crate) mod recording;
 pub(crate) mod session;
 pub(crate) mod sftp;
 pub(crate) mod shell;
@@ -9,7 +9,7 @@
 };
 use russh::{ChannelStream, client::Msg};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, Runtime, State, async_runtime};
+use tauri::{AppHandle, Manager, Runtime, State, async_runtime, ipc::Channel};
 use tokio::{
   io,
   net::{TcpListener, TcpStream},
@@ -18,16 +18,28 @@
 };
 use uuid::Uuid;
 
-use crate::{SSHError, SSHResult, commands::session::SSHSessionId, ssh_manager::SSHManager};
+use crate::{
+  SSHError, SSHResult,
+  commands::session::{SSHChannelKind, SSHSessionId, SessionIpcChannelData},
+  ssh_manager::SSHManager,
+};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct SSHPortForwardingId(Uuid);
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum SSHPortForwardingIpcChannelData {
+  /// The forwarding stopped, closed explicitly or with its session
+  Close,
+}
+
 pub enum SSHPortForwarding {
   #[allow(unused)]
   Local {
     ssh_port_forwarding_id: SSHPortForwardingId,
     ssh_session_id: SSHSessionId,
+    ipc_channel: Channel<SSHPortForwardingIpcChannelData>,
     notify: Arc<Notify>,
     local_address: String,
     local_port: u16,
@@ -38,6 +50,7 @@ pub enum SSHPortForwarding {
   Remote {
     ssh_port_forwarding_id: SSHPortForwardingId,
     ssh_session_id: SSHSessionId,
+    ipc_channel: Channel<SSHPortForwardingIpcChannelData>,
     local_address: String,
     local_port: u16,
     remote_address: String,
@@ -47,12 +60,38 @@ pub enum SSHPortForwarding {
   Dynamic {
     ssh_port_forwarding_id: SSHPortForwardingId,
     ssh_session_id: SSHSessionId,
+    ipc_channel: Channel<SSHPortForwardingIpcChannelData>,
     notify: Arc<Notify>,
     local_address: String,
     local_port: u16,
   },
 }
 
+impl SSHPortForwarding {
+  pub fn ssh_session_id(&self) -> SSHSessionId {
+    match self {
+      SSHPortForwarding::Local { ssh_session_id, .. }
+      | SSHPortForwarding::Remote { ssh_session_id, .. }
+      | SSHPortForwarding::Dynamic { ssh_session_id, .. } => *ssh_session_id,
+    }
+  }
+
+  /// Stops a forwarding already removed from the manager, a listener sends
+  /// `Close` once it stopped accepting
+  pub fn close(&self) {
+    match self {
+      SSHPortForwarding::Local { notify, .. } | SSHPortForwarding::Dynamic { notify, .. } => {
+        notify.notify_last();
+      }
+      SSHPortForwarding::Remote { ipc_channel, .. } => {
+        if let Err(err) = ipc_channel.send(SSHPortForwardingIpcChannelData::Close) {
+          log::error!("port forwarding close send failed: {}", err);
+        }
+      }
+    }
+  }
+}
+
 pub struct Handler<'a, R: Runtime> {
   sessions: State<'a, SSHManager<R>>,
   ssh_session_id: SSHSessionId,
@@ -78,11 +117,14 @@ async fn connect_socks<'a, R: Runtime>(
   address: &SocksAddr,
 ) -> Result<ChannelStream<Msg>, SSHError> {
   let channel = {
-    let sessions = handler.sessions.sessions.lock().await;
-    let session = sessions
+    let session = handler
+      .sessions
+      .sessions
       .get(&handler.ssh_session_id)
       .ok_or(SSHError::NotFoundSession)?;
     session
+      .handle()
+      .await
       .channel_open_direct_tcpip(
         address.domain(),
         address.port() as u32,
@@ -91,6 +133,11 @@ async fn connect_socks<'a, R: Runtime>(
       )
       .await?
   };
+  handler.sessions.channel_opened(
+    handler.ssh_session_id,
+    channel.id(),
+    SSHChannelKind::DirectTcpip,
+  );
 
   Ok(channel.into_stream())
 }
@@ -147,7 +194,54 @@ async fn connect(
   }
 }
 
+/// Reports a connection accepted by a forwarding on its session
+pub fn port_forwarding_connection<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  ssh_session_id: SSHSessionId,
+  ssh_port_forwarding_id: SSHPortForwardingId,
+  originator: impl ToString,
+) {
+  ssh_manager.session_event(
+    ssh_session_id,
+    SessionIpcChannelData::PortForwardingConnection {
+      ssh_port_forwarding_id,
+      originator: originator.to_string(),
+    },
+  );
+}
+
+/// Requests `tcpip-forward`, a refusal is reported on the session as well
+async fn tcpip_forward<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  ssh_session_id: SSHSessionId,
+  remote_address: &str,
+  remote_port: u16,
+) -> SSHResult<()> {
+  let session = ssh_manager
+    .sessions
+    .get(&ssh_session_id)
+    .ok_or(SSHError::NotFoundSession)?;
+
+  let result = session
+    .handle_mut()
+    .await
+    .tcpip_forward(remote_address, remote_port as u32)
+    .await;
+  if let Err(err) = &result {
+    session.event(SessionIpcChannelData::GlobalRequestFailed {
+      request: "tcpip-forward".to_string(),
+      address: remote_address.to_string(),
+      port: remote_port,
+      error: err.to_string(),
+    });
+  }
+  result?;
+
+  Ok(())
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn port_forwarding_local_open<R: Runtime>(
   app_handle: AppHandle<R>,
   ssh_manager: State<'_, SSHManager<R>>,
@@ -157,26 +251,26 @@ pub async fn port_forwarding_local_open<R: Runtime>(
   local_port: u16,
   remote_address: String,
   remote_port: u16,
+  ipc_channel: Channel<SSHPortForwardingIpcChannelData>,
 ) -> SSHResult<SSHSessionId> {
+  // Registered once listening, so a failed bind leaves nothing behind
+  let listener = TcpListener::bind((local_address.as_str(), local_port)).await?;
+
   let notify = Arc::new(Notify::new());
 
-  {
-    let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-    port_forwardings.insert(
+  ssh_manager.port_forwardings.insert(
+    ssh_port_forwarding_id,
+    Arc::new(SSHPortForwarding::Local {
       ssh_port_forwarding_id,
-      SSHPortForwarding::Local {
-        ssh_port_forwarding_id,
-        ssh_session_id,
-        notify: notify.clone(),
-        local_address: local_address.clone(),
-        local_port,
-        remote_address: remote_address.clone(),
-        remote_port,
-      },
-    );
-  }
-
-  let listener = TcpListener::bind((local_address, local_port)).await?;
+      ssh_session_id,
+      ipc_channel: ipc_channel.clone(),
+      notify: notify.clone(),
+      local_address,
+      local_port,
+      remote_address: remote_address.clone(),
+      remote_port,
+    }),
+  );
 
   async_runtime::spawn(async move {
     loop {
@@ -189,10 +283,20 @@ pub async fn port_forwarding_local_open<R: Runtime>(
             let remote_address = remote_address.clone();
             async_runtime::spawn(async move {
               let ssh_manager = app.state::<SSHManager<R>>();
+              port_forwarding_connection(
+                  &ssh_manager,
+                  ssh_session_id,
+                  ssh_port_forwarding_id,
+                  addr,
+              );
               let channel = {
-                let sessions = ssh_manager.sessions.lock().await;
-                let session = sessions.get(&ssh_session_id).ok_or(SSHError::NotFoundSession)?;
+                let session = ssh_manager
+                    .sessions
+                    .get(&ssh_session_id)
+                    .ok_or(SSHError::NotFoundSession)?;
                 session
+                    .handle()
+                    .await
                     .channel_open_direct_tcpip(
                         remote_address,
                         remote_port as u32,
@@ -201,6 +305,11 @@ pub async fn port_forwarding_local_open<R: Runtime>(
                     )
                     .await?
               };
+              ssh_manager.channel_opened(
+                  ssh_session_id,
+                  channel.id(),
+                  SSHChannelKind::DirectTcpip,
+              );
 
               io::copy_bidirectional(&mut stream, &mut channel.into_stream()).await?;
               Ok::<(), SSHError>(())
@@ -211,9 +320,9 @@ pub async fn port_forwarding_local_open<R: Runtime>(
 
     {
       let ssh_manager = app_handle.state::<SSHManager<R>>();
-      let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-      port_forwardings.remove(&ssh_port_forwarding_id);
+      ssh_manager.port_forwardings.remove(&ssh_port_forwarding_id);
     }
+    ipc_channel.send(SSHPortForwardingIpcChannelData::Close)?;
 
     Ok::<(), SSHError>(())
   });
@@ -228,17 +337,19 @@ pub async fn port_forwarding_local_close<R: Runtime>(
   ssh_session_id: SSHSessionId,
   ssh_port_forwarding_id: SSHPortForwardingId,
 ) -> SSHResult<SSHSessionId> {
-  let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-  let ssh_port_forwarding = port_forwardings.remove(&ssh_port_forwarding_id);
+  let ssh_port_forwarding = ssh_manager.port_forwardings.remove(&ssh_port_forwarding_id);
 
-  if let Some(SSHPortForwarding::Local { notify, .. }) = ssh_port_forwarding {
-    notify.notify_last();
+  if let Some(ssh_port_forwarding @ SSHPortForwarding::Local { .. }) =
+    ssh_port_forwarding.as_deref()
+  {
+    ssh_port_forwarding.close();
   }
 
   Ok(ssh_session_id)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn port_forwarding_remote_open<R: Runtime>(
   _app_handle: AppHandle<R>,
   ssh_manager: State<'_, SSHManager<R>>,
@@ -248,34 +359,65 @@ pub async fn port_forwarding_remote_open<R: Runtime>(
   local_port: u16,
   remote_address: String,
   remote_port: u16,
+  ipc_channel: Channel<SSHPortForwardingIpcChannelData>,
 ) -> SSHResult<SSHSessionId> {
-  {
-    let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-    port_forwardings.insert(
+  ssh_manager.port_forwardings.insert(
+    ssh_port_forwarding_id,
+    Arc::new(SSHPortForwarding::Remote {
       ssh_port_forwarding_id,
-      SSHPortForwarding::Remote {
-        ssh_port_forwarding_id,
-        ssh_session_id,
-        local_address,
-        local_port,
-        remote_address: remote_address.clone(),
-        remote_port,
-      },
-    );
-  }
+      ssh_session_id,
+      ipc_channel,
+      local_address,
+      local_port,
+      remote_address: remote_address.clone(),
+      remote_port,
+    }),
+  );
 
-  {
-    let mut sessions = ssh_manager.sessions.lock().await;
-    let session = sessions
-      .get_mut(&ssh_session_id)
-      .ok_or(SSHError::NotFoundSession)?;
+  tcpip_forward(&ssh_manager, ssh_session_id, &remote_address, remote_port).await?;
 
-    session
-      .tcpip_forward(remote_address, remote_port as u32)
-      .await?;
+  Ok(ssh_session_id)
+}
+
+/// Requests the remote forwardings of a reconnected session again, local and
+/// dynamic forwardings look the session up per connection and carry on
+pub async fn port_forwarding_remote_restore<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  ssh_session_id: SSHSessionId,
+) -> SSHResult<()> {
+  if !ssh_manager.sessions.contains_key(&ssh_session_id) {
+    return Err(SSHError::NotFoundSession);
   }
 
-  Ok(ssh_session_id)
+  for port_forwarding in ssh_manager.port_forwardings.values() {
+    if let SSHPortForwarding::Remote {
+      ssh_session_id: port_forwarding_ssh_session_id,
+      remote_address,
+      remote_port,
+      ..
+    } = port_forwarding.as_ref()
+      && *port_forwarding_ssh_session_id == ssh_session_id
+    {
+      log::info!(
+        "restore remote forwarding {:?} {}:{}",
+        ssh_session_id,
+        remote_address,
+        remote_port
+      );
+      if let Err(err) =
+        tcpip_forward(ssh_manager, ssh_session_id, remote_address, *remote_port).await
+      {
+        log::error!(
+          "restore remote forwarding {}:{} failed: {}",
+          remote_address,
+          remote_port,
+          err
+        );
+      }
+    }
+  }
+
+  Ok(())
 }
 
 #[tauri::command]
@@ -285,25 +427,37 @@ pub async fn port_forwarding_remote_close<R: Runtime>(
   ssh_session_id: SSHSessionId,
   ssh_port_forwarding_id: SSHPortForwardingId,
 ) -> SSHResult<SSHSessionId> {
-  let ssh_port_forwarding = {
-    let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-    port_forwardings.remove(&ssh_port_forwarding_id)
-  };
-
-  if let Some(SSHPortForwarding::Remote {
-    remote_address,
-    remote_port,
-    ..
-  }) = ssh_port_forwarding
+  let ssh_port_forwarding = ssh_manager.port_forwardings.remove(&ssh_port_forwarding_id);
+
+  if let Some(
+    ssh_port_forwarding @ SSHPortForwarding::Remote {
+      remote_address,
+      remote_port,
+      ..
+    },
+  ) = ssh_port_forwarding.as_deref()
   {
-    let mut sessions = ssh_manager.sessions.lock().await;
-    let session = sessions
-      .get_mut(&ssh_session_id)
+    ssh_port_forwarding.close();
+
+    let session = ssh_manager
+      .sessions
+      .get(&ssh_session_id)
       .ok_or(SSHError::NotFoundSession)?;
 
-    session
-      .cancel_tcpip_forward(remote_address, remote_port as u32)
-      .await?;
+    let result = session
+      .handle_mut()
+      .await
+      .cancel_tcpip_forward(remote_address, *remote_port as u32)
+      .await;
+    if let Err(err) = &result {
+      session.event(SessionIpcChannelData::GlobalRequestFailed {
+        request: "cancel-tcpip-forward".to_string(),
+        address: remote_address.clone(),
+        port: *remote_port,
+        error: err.to_string(),
+      });
+    }
+    result?;
   }
 
   Ok(ssh_session_id)
@@ -317,24 +471,24 @@ pub async fn port_forwarding_dynamic_open<R: Runtime>(
   ssh_port_forwarding_id: SSHPortForwardingId,
   local_address: String,
   local_port: u16,
+  ipc_channel: Channel<SSHPortForwardingIpcChannelData>,
 ) -> SSHResult<SSHSessionId> {
+  // Registered once listening, so a failed bind leaves nothing behind
+  let listener = TcpListener::bind((local_address.as_str(), local_port)).await?;
+
   let notify = Arc::new(Notify::new());
 
-  {
-    let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-    port_forwardings.insert(
+  ssh_manager.port_forwardings.insert(
+    ssh_port_forwarding_id,
+    Arc::new(SSHPortForwarding::Dynamic {
       ssh_port_forwarding_id,
-      SSHPortForwarding::Dynamic {
-        ssh_port_forwarding_id,
-        ssh_session_id,
-        notify: notify.clone(),
-        local_address: local_address.clone(),
-        local_port,
-      },
-    );
-  }
-
-  let listener = TcpListener::bind((local_address, local_port)).await?;
+      ssh_session_id,
+      ipc_channel: ipc_channel.clone(),
+      notify: notify.clone(),
+      local_address,
+      local_port,
+    }),
+  );
 
   async_runtime::spawn(async move {
     loop {
@@ -342,11 +496,18 @@ pub async fn port_forwarding_dynamic_open<R: Runtime>(
           _ = notify.notified() => {
             break;
           },
-          Ok((mut stream, _)) = listener.accept() => {
+          Ok((mut stream, addr)) = listener.accept() => {
             let app = app_handle.clone();
             async_runtime::spawn(async move {
               let local_addr = stream.local_addr()?;
-              let handler = Handler::new(app.state::<SSHManager<R>>(),  ssh_session_id, local_addr);
+              let ssh_manager = app.state::<SSHManager<R>>();
+              port_forwarding_connection(
+                  &ssh_manager,
+                  ssh_session_id,
+                  ssh_port_forwarding_id,
+                  addr,
+              );
+              let handler = Handler::new(ssh_manager, ssh_session_id, local_addr);
 
               let mut socks = Socks::from_stream(&mut stream, handler)
                   .await?;
@@ -361,9 +522,9 @@ pub async fn port_forwarding_dynamic_open<R: Runtime>(
 
     {
       let ssh_manager = app_handle.state::<SSHManager<R>>();
-      let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-      port_forwardings.remove(&ssh_port_forwarding_id);
+      ssh_manager.port_forwardings.remove(&ssh_port_forwarding_id);
     }
+    ipc_channel.send(SSHPortForwardingIpcChannelData::Close)?;
 
     Ok::<(), SSHError>(())
   });
@@ -378,11 +539,10 @@ pub async fn port_forwarding_dynamic_close<R: Runtime>(
   ssh_session_id: SSHSessionId,
   ssh_port_forwarding_id: SSHPortForwardingId,
 ) -> SSHResult<SSHSessionId> {
-  let mut port_forwardings = ssh_manager.port_forwardings.lock().await;
-  let port_forwarding = port_forwardings.remove(&ssh_port_forwarding_id);
+  let port_forwarding = ssh_manager.port_forwardings.remove(&ssh_port_forwarding_id);
 
-  if let Some(SSHPortForwarding::Dynamic { notify, .. }) = port_forwarding {
-    notify.notify_last();
+  if let Some(port_forwarding @ SSHPortForwarding::Dynamic { .. }) = port_forwarding.as_deref() {
+    port_forwarding.close();
   }
 
   Ok(ssh_session_id)
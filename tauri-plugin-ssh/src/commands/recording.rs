@@ -0,0 +1,150 @@
+use std::{collections::HashSet, fs, io};
+
+use tauri::{AppHandle, Runtime, State};
+use tauri_plugin_fs::{FsExt, OpenOptions, SafeFilePath};
+
+use crate::{
+  commands::shell::SSHShellId,
+  error::{SSHError, SSHResult},
+  recording::{
+    self, SSHShellRecorder, SSHShellRecording, SSHShellRecordingId, SSHShellRecordingOpts,
+  },
+  ssh_manager::SSHManager,
+  utils::get_recordings_dir,
+};
+
+/// Starts recording a running shell, the size and term of the shell are used
+/// for the asciicast header
+#[tauri::command]
+pub async fn shell_recording_start<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_shell_id: SSHShellId,
+  opts: Option<SSHShellRecordingOpts>,
+) -> SSHResult<SSHShellRecordingId> {
+  let recordings_dir = get_recordings_dir(&app_handle)?;
+
+  let shell = ssh_manager
+    .shells
+    .get(&ssh_shell_id)
+    .ok_or(SSHError::NotFoundShell)?;
+
+  if shell.is_recording() {
+    return Err(SSHError::new("Shell is already being recorded"));
+  }
+
+  let recorder = SSHShellRecorder::create(
+    &recordings_dir,
+    &shell.request.term,
+    &shell.size(),
+    opts.unwrap_or_default(),
+  )?;
+
+  shell.set_recorder(recorder)
+}
+
+#[tauri::command]
+pub async fn shell_recording_stop<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_shell_id: SSHShellId,
+) -> SSHResult<Option<SSHShellRecordingId>> {
+  let shell = ssh_manager
+    .shells
+    .get(&ssh_shell_id)
+    .ok_or(SSHError::NotFoundShell)?;
+  let recorder = shell.take_recorder();
+
+  recorder.map(|recorder| recorder.finish()).transpose()
+}
+
+#[tauri::command]
+pub async fn shell_recording_list<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+) -> SSHResult<Vec<SSHShellRecording>> {
+  let recordings_dir = get_recordings_dir(&app_handle)?;
+
+  let recording_ids = ssh_manager
+    .shells
+    .values()
+    .iter()
+    .filter_map(|shell| shell.recording_id())
+    .collect::<HashSet<SSHShellRecordingId>>();
+
+  let recordings = recording::read_recordings(&recordings_dir)?
+    .into_iter()
+    .map(|recording| SSHShellRecording {
+      recording: recording_ids.contains(&recording.id),
+      ..recording
+    })
+    .collect();
+
+  Ok(recordings)
+}
+
+/// Copies a finished recording to `local_filename`
+#[tauri::command]
+pub async fn shell_recording_export<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  recording_id: SSHShellRecordingId,
+  local_filename: SafeFilePath,
+) -> SSHResult<SSHShellRecordingId> {
+  ensure_finished(&ssh_manager, recording_id).await?;
+
+  let recordings_dir = get_recordings_dir(&app_handle)?;
+  let recording_path = recording_id.path(&recordings_dir);
+  if !recording_path.exists() {
+    return Err(SSHError::NotFoundRecording);
+  }
+
+  let mut recording_file = fs::File::open(recording_path)?;
+  let mut local_file = app_handle.fs().open(
+    local_filename,
+    OpenOptions::new()
+      .create(true)
+      .write(true)
+      .truncate(true)
+      .to_owned(),
+  )?;
+  io::copy(&mut recording_file, &mut local_file)?;
+
+  Ok(recording_id)
+}
+
+#[tauri::command]
+pub async fn shell_recording_remove<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  recording_id: SSHShellRecordingId,
+) -> SSHResult<SSHShellRecordingId> {
+  ensure_finished(&ssh_manager, recording_id).await?;
+
+  let recordings_dir = get_recordings_dir(&app_handle)?;
+  let recording_path = recording_id.path(&recordings_dir);
+  if !recording_path.exists() {
+    return Err(SSHError::NotFoundRecording);
+  }
+
+  fs::remove_file(recording_path)?;
+
+  Ok(recording_id)
+}
+
+async fn ensure_finished<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  recording_id: SSHShellRecordingId,
+) -> SSHResult<()> {
+  let recording = ssh_manager
+    .shells
+    .values()
+    .iter()
+    .any(|shell| shell.recording_id() == Some(recording_id));
+
+  if recording {
+    return Err(SSHError::new("Recording is in progress"));
+  }
+
+  Ok(())
+}
@@ -1,25 +1,50 @@
 use std::{
-  ops::{Deref, DerefMut},
-  sync::Arc,
-  time::Duration,
+  future::Future,
+  io,
+  sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+  },
+  time::{Duration, Instant},
 };
 
 use russh::{
-  Disconnect, Error as RusshError, MethodKind,
+  Disconnect, Error as RusshError, MethodKind, MethodSet,
   client::{self, AuthResult, Handle, KeyboardInteractiveAuthResponse},
   keys::{Certificate, decode_secret_key, key::PrivateKeyWithHashAlg},
 };
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Runtime, State, ipc::Channel};
-use tokio::time::timeout;
+use tauri::{AppHandle, Runtime, State, async_runtime, ipc::Channel};
+use tokio::{
+  net::{TcpStream, lookup_host},
+  sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+  time::{sleep, timeout},
+};
 use uuid::Uuid;
 
 use crate::{
+  commands::port_forwarding::SSHPortForwardingId,
+  connection_profile::SSHConnectionProfile,
+  diagnostics::{
+    SSHDiagnosticStream, SSHSessionDiagnostics, SSHSessionInfo, SSHSessionStage,
+    SSHSessionStageTiming,
+  },
   error::{AuthenticationError, KeyboardInteractiveData, SSHError, SSHResult},
+  keyboard_interactive::{KeyboardInteractiveAnswers, KeyboardInteractiveRule},
+  multiplex::{
+    SSHMultiplexKey, SSHSessionMultiplex, SSHSessionMultiplexOpts, multiplex_disconnect,
+    multiplex_session,
+  },
+  proxy_command::SSHProxyCommand,
+  reconnect::{SSHSessionCredentials, SSHSessionReconnect, SSHSessionReconnectPolicy},
   ssh_client::{DisconnectReason, SSHClient},
   ssh_manager::SSHManager,
+  upstream_proxy::{SSHUpstreamProxy, load_global_upstream_proxy, save_global_upstream_proxy},
+  utils::{connect_agent, method_kind_name},
 };
 
+const RTT_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct SSHSessionId(Uuid);
 
@@ -30,16 +55,148 @@ fn from(value: Uuid) -> Self {
 }
 
 #[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[serde(
+  rename_all = "camelCase",
+  rename_all_fields = "camelCase",
+  tag = "type",
+  content = "data"
+)]
 pub enum SessionIpcChannelData {
   Disconnect(DisconnectReason),
+  /// A line the `ProxyCommand` process wrote to stderr
+  ProxyCommandStderr(String),
+  /// The banner a server sends before authentication, `hostname` tells a jump
+  /// host apart from the host itself
+  AuthBanner {
+    hostname: String,
+    port: u16,
+    banner: String,
+  },
+  /// A finished stage of connecting in diagnose mode
+  DiagnoseStage {
+    hostname: String,
+    port: u16,
+    timing: SSHSessionStageTiming,
+  },
+  /// The session dropped and is connected again after `delay_millis`
+  Reconnecting {
+    attempt: u32,
+    max_attempts: u32,
+    delay_millis: u64,
+    /// Why the session dropped or the previous attempt failed
+    error: String,
+  },
+  /// The session is connected and authenticated again, its shells and remote
+  /// forwardings are reopened
+  Reconnected {
+    attempt: u32,
+  },
+  /// Every attempt failed, followed by `Disconnect`
+  ReconnectFailed {
+    error: String,
+  },
+  /// The identification string the server sent, e.g. `SSH-2.0-OpenSSH_9.6`
+  ServerBanner {
+    hostname: String,
+    port: u16,
+    identification: String,
+  },
+  /// The host key passed the known_hosts check of the first key exchange
+  HostKeyAccepted {
+    hostname: String,
+    port: u16,
+    algorithm: String,
+    fingerprint: String,
+  },
+  /// A later key exchange renewed the session keys
+  Rekey {
+    hostname: String,
+    port: u16,
+  },
+  /// A keepalive answered by the server, sampled periodically and by
+  /// `session_info`
+  KeepaliveRtt {
+    rtt_millis: u64,
+  },
+  ChannelOpened {
+    channel_id: u32,
+    kind: SSHChannelKind,
+  },
+  /// Either side closed the channel
+  ChannelClosed {
+    channel_id: u32,
+  },
+  /// A connection accepted by a port forwarding, `originator` is the peer
+  /// address
+  PortForwardingConnection {
+    ssh_port_forwarding_id: SSHPortForwardingId,
+    originator: String,
+  },
+  /// The server refused a global request such as `tcpip-forward`
+  GlobalRequestFailed {
+    request: String,
+    address: String,
+    port: u16,
+    error: String,
+  },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum SSHChannelKind {
+  Shell,
+  Sftp,
+  Exec,
+  /// Local and dynamic port forwarding
+  DirectTcpip,
+  /// Remote port forwarding
+  ForwardedTcpip,
+  AgentForward,
+  X11,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHSessionProbeAuth {
+  /// The server accepted the `none` method, the session is authenticated
+  pub authenticated: bool,
+  pub methods: Vec<&'static str>,
+}
+
+/// Identifies a jump host session so that chains sharing the same prefix reuse it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SSHJumpHostKey {
+  hostname: String,
+  port: u16,
+  username: String,
+  jump_host_ssh_session_id: Option<SSHSessionId>,
 }
 
 pub struct SSHSession<R: Runtime> {
-  #[allow(unused)]
   pub ssh_session_id: SSHSessionId,
   pub ipc_channel: Channel<SessionIpcChannelData>,
-  pub handle_ssh_client: Handle<SSHClient<R>>,
+  /// Authentication and remote forwarding need the handle mutably, everything
+  /// else only reads it, so channels keep opening while another one is busy.
+  /// Shared by the sessions multiplexed onto the connection.
+  pub handle_ssh_client: Arc<RwLock<Handle<SSHClient<R>>>>,
+  /// The session that opened the connection, its handler reports channel
+  /// events under this id
+  pub connection_id: SSHSessionId,
+  /// The session this session is tunneled through
+  pub jump_host_ssh_session_id: Option<SSHSessionId>,
+  /// Set when this session is an intermediate hop managed by the plugin
+  pub jump_host_key: Option<SSHJumpHostKey>,
+  /// Number of sessions tunneled through this session
+  pub dependents: AtomicUsize,
+  /// The `ProxyCommand` process carrying this session, killed on drop
+  pub proxy_command: Option<SSHProxyCommand>,
+  pub diagnostics: Arc<SSHSessionDiagnostics>,
+  /// Each authentication step times out after the connect timeout of the
+  /// connection profile
+  pub auth_timeout: Duration,
+  /// Set when the session is connected again after dropping
+  pub reconnect: Option<Arc<SSHSessionReconnect>>,
+  /// Set on the session that opened a connection other sessions may share
+  pub multiplex: Option<Arc<SSHSessionMultiplex>>,
 }
 
 impl<R: Runtime> SSHSession<R> {
@@ -47,26 +204,122 @@ pub fn new(
     ssh_session_id: SSHSessionId,
     ipc_channel: Channel<SessionIpcChannelData>,
     handle_ssh_client: Handle<SSHClient<R>>,
+    jump_host_ssh_session_id: Option<SSHSessionId>,
+    diagnostics: Arc<SSHSessionDiagnostics>,
+    auth_timeout: Duration,
   ) -> Self {
     Self {
       ssh_session_id,
       ipc_channel,
-      handle_ssh_client,
+      handle_ssh_client: Arc::new(RwLock::new(handle_ssh_client)),
+      connection_id: ssh_session_id,
+      jump_host_ssh_session_id,
+      jump_host_key: None,
+      dependents: AtomicUsize::new(0),
+      proxy_command: None,
+      diagnostics,
+      auth_timeout,
+      reconnect: None,
+      multiplex: None,
+    }
+  }
+
+  /// A session on the connection of `connection`, which stays registered and
+  /// keeps the jump hosts and the `ProxyCommand` until the connection closes
+  pub fn multiplexed(
+    connection: &SSHSession<R>,
+    ssh_session_id: SSHSessionId,
+    ipc_channel: Channel<SessionIpcChannelData>,
+  ) -> Self {
+    Self {
+      ssh_session_id,
+      ipc_channel,
+      handle_ssh_client: connection.handle_ssh_client.clone(),
+      connection_id: connection.ssh_session_id,
+      jump_host_ssh_session_id: None,
+      jump_host_key: None,
+      dependents: AtomicUsize::new(0),
+      proxy_command: None,
+      diagnostics: connection.diagnostics.clone(),
+      auth_timeout: connection.auth_timeout,
+      reconnect: None,
+      multiplex: None,
     }
   }
-}
 
-impl<R: Runtime> Deref for SSHSession<R> {
-  type Target = Handle<SSHClient<R>>;
+  pub fn is_multiplexed(&self) -> bool {
+    self.connection_id != self.ssh_session_id
+  }
 
-  fn deref(&self) -> &Self::Target {
-    &self.handle_ssh_client
+  pub async fn handle(&self) -> RwLockReadGuard<'_, Handle<SSHClient<R>>> {
+    self.handle_ssh_client.read().await
   }
-}
 
-impl<R: Runtime> DerefMut for SSHSession<R> {
-  fn deref_mut(&mut self) -> &mut Self::Target {
-    &mut self.handle_ssh_client
+  pub async fn handle_mut(&self) -> RwLockWriteGuard<'_, Handle<SSHClient<R>>> {
+    self.handle_ssh_client.write().await
+  }
+
+  /// A session that is being authenticated is still open
+  pub fn is_closed(&self) -> bool {
+    self
+      .handle_ssh_client
+      .try_read()
+      .is_ok_and(|handle_ssh_client| handle_ssh_client.is_closed())
+  }
+
+  /// A shared connection whose opening session disconnected reports to a
+  /// session still on it
+  pub fn event(&self, data: SessionIpcChannelData) {
+    let ipc_channel = match &self.multiplex {
+      Some(multiplex) if multiplex.is_detached() => match multiplex.event_sink() {
+        Some(ipc_channel) => ipc_channel,
+        None => return,
+      },
+      _ => self.ipc_channel.clone(),
+    };
+    if let Err(err) = ipc_channel.send(data) {
+      log::warn!(
+        "session event {:?} send failed: {}",
+        self.ssh_session_id,
+        err
+      );
+    }
+  }
+
+  /// Measures the round trip with a keepalive and reports it
+  pub async fn ping(&self) -> SSHResult<Duration> {
+    let started = Instant::now();
+    timeout(Duration::from_secs(5), async {
+      self.handle().await.send_ping().await
+    })
+    .await??;
+
+    let rtt = started.elapsed();
+    self.diagnostics.set_rtt(rtt);
+    self.event(SessionIpcChannelData::KeepaliveRtt {
+      rtt_millis: rtt.as_millis() as u64,
+    });
+    Ok(rtt)
+  }
+
+  /// Pings an authenticated session every `RTT_SAMPLE_INTERVAL` until it is
+  /// closed or replaced by a reconnect
+  pub fn sample_rtt(self: &Arc<Self>) {
+    let session = Arc::downgrade(self);
+    async_runtime::spawn(async move {
+      loop {
+        sleep(RTT_SAMPLE_INTERVAL).await;
+        let Some(session) = session.upgrade() else {
+          break;
+        };
+        if session.is_closed() {
+          break;
+        }
+        if let Err(err) = session.ping().await {
+          log::warn!("session {:?} ping failed: {}", session.ssh_session_id, err);
+        }
+      }
+    });
   }
 }
 
@@ -74,112 +327,597 @@ fn deref_mut(&mut self) -> &mut Self::Target {
 pub enum SSHSessionCheckServerKey {
   Continue,
   AddAndContinue,
+  /// Replaces the known_hosts entries of a host whose key has changed
+  ReplaceAndContinue,
 }
 
-#[tauri::command]
-pub async fn session_connect<R: Runtime>(
-  app_handle: AppHandle<R>,
-  ssh_manager: State<'_, SSHManager<R>>,
-  ssh_session_id: SSHSessionId,
-  hostname: String,
+/// How unknown and changed server keys are handled, after OpenSSH's
+/// `StrictHostKeyChecking`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SSHSessionStrictHostKeyChecking {
+  /// Unknown and changed keys are reported so the user can decide
+  #[default]
+  Ask,
+  /// Unknown keys are learned, changed keys are rejected
+  AcceptNew,
+  /// Unknown and changed keys are rejected
+  Yes,
+  /// Unknown keys are learned, changed keys are accepted
+  Off,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHJumpHost {
+  pub hostname: String,
+  pub port: u16,
+  pub username: String,
+  pub check_server_key: Option<SSHSessionCheckServerKey>,
+  pub strict_host_key_checking: Option<SSHSessionStrictHostKeyChecking>,
+  /// Only used when this is the first hop of the chain
+  pub proxy_command: Option<String>,
+  /// Only used when this is the first hop of the chain
+  pub upstream_proxy: Option<SSHUpstreamProxy>,
+  pub authentication_data: AuthenticationData,
+  /// Steps continued with after a partial success
+  pub authentication_chain: Option<Vec<AuthenticationData>>,
+  pub connection_profile: Option<SSHConnectionProfile>,
+}
+
+/// The host `connect` reaches and how its server key is checked
+struct SSHConnectTarget<'a> {
+  hostname: &'a str,
   port: u16,
-  jump_host_ssh_session_id: Option<SSHSessionId>,
+  /// Substituted for `%r` in the proxy command
+  username: &'a str,
+  proxy_command: Option<&'a str>,
+  /// Falls back to the global upstream proxy when not set
+  upstream_proxy: Option<SSHUpstreamProxy>,
   check_server_key: Option<SSHSessionCheckServerKey>,
-  ipc_channel: Channel<SessionIpcChannelData>,
-) -> SSHResult<SSHSessionId> {
-  timeout(Duration::from_secs(5), async {
-    log::info!("session connect: {:?}", ssh_session_id);
+  strict_host_key_checking: Option<SSHSessionStrictHostKeyChecking>,
+  /// Reports each stage to the frontend as it finishes
+  diagnose: bool,
+  connection_profile: Option<&'a SSHConnectionProfile>,
+}
+
+async fn connect<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  ssh_manager: &State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
+  target: SSHConnectTarget<'_>,
+  jump_host_ssh_session_id: Option<SSHSessionId>,
+  ipc_channel: &Channel<SessionIpcChannelData>,
+) -> SSHResult<(
+  Handle<SSHClient<R>>,
+  Option<SSHProxyCommand>,
+  Arc<SSHSessionDiagnostics>,
+)> {
+  let SSHConnectTarget {
+    hostname,
+    port,
+    username,
+    proxy_command,
+    upstream_proxy,
+    check_server_key,
+    strict_host_key_checking,
+    diagnose,
+    connection_profile,
+  } = target;
+  let proxy_command = proxy_command.filter(|proxy_command| !proxy_command.trim().is_empty());
+  let diagnostics = SSHSessionDiagnostics::new(hostname, port, diagnose, ipc_channel.clone());
+  let connection_profile = connection_profile.cloned().unwrap_or_default();
+  let config = Arc::new(connection_profile.client_config()?);
+
+  timeout(connection_profile.connect_timeout(), async {
     let ssh_client = SSHClient::new(
       app_handle.clone(),
       ssh_session_id,
-      hostname.clone(),
+      ipc_channel.clone(),
+      hostname.to_string(),
       port,
-      jump_host_ssh_session_id,
       check_server_key,
+      strict_host_key_checking.unwrap_or_default(),
+      diagnostics.clone(),
     );
 
-    let config = Arc::new(client::Config {
-      inactivity_timeout: Some(Duration::from_secs(30 * 60)),
-      keepalive_interval: Some(Duration::from_secs(5)),
-      window_size: 1 << 25, // 32 MB
-      maximum_packet_size: 65536,
-      channel_buffer_size: 1048576,
-      nodelay: true,
-      ..client::Config::default()
-    });
+    let addr = format!("{}:{}", hostname, port);
 
+    let upstream_proxy = match upstream_proxy {
+      Some(upstream_proxy) => Some(upstream_proxy),
+      None => load_global_upstream_proxy(app_handle)?,
+    };
+
+    let mut ssh_proxy_command = None;
     let handle_ssh_client = if let Some(jump_host_ssh_session_id) = jump_host_ssh_session_id {
+      if proxy_command.is_some() {
+        log::warn!(
+          "session connect {:?} ignore proxy command behind jump host",
+          ssh_session_id
+        );
+      }
       log::info!(
-        "session connect {:?} to {}:{} with jump host session {:?}",
+        "session connect {:?} to {} with jump host session {:?}",
         ssh_session_id,
-        &hostname,
-        port,
+        &addr,
         jump_host_ssh_session_id
       );
-      let channel = {
-        let sessions = ssh_manager.sessions.lock().await;
-
-        let jump_host_session = sessions
+      let started = Instant::now();
+      let channel = async {
+        let jump_host_session = ssh_manager
+          .sessions
           .get(&jump_host_ssh_session_id)
           .ok_or(SSHError::NotFoundJumpHostSession)?;
 
         jump_host_session
-          .channel_open_direct_tcpip(&hostname, port as u32, "127.0.0.1", 0)
-          .await?
-      };
+          .handle()
+          .await
+          .channel_open_direct_tcpip(hostname, port as u32, "127.0.0.1", 0)
+          .await
+          .map_err(|_| SSHError::ConnectFailed(addr.clone()))
+      }
+      .await;
+      diagnostics.stage(SSHSessionStage::JumpHost, started, &channel);
 
-      client::connect_stream(config, channel.into_stream(), ssh_client)
-        .await
-        .map_err(|err| match err {
-          SSHError::RusshError(e) => match e {
-            RusshError::Disconnect => SSHError::JumpHostConnectFailed,
-            err => SSHError::RusshError(err),
-          },
-          err => err,
-        })?
+      let stream = SSHDiagnosticStream::new(channel?.into_stream(), diagnostics.clone());
+      handshake(
+        &diagnostics,
+        client::connect_stream(config, stream, ssh_client),
+      )
+      .await
+    } else if let Some(proxy_command) = proxy_command {
+      log::info!(
+        "session connect {:?} to {} with proxy command",
+        ssh_session_id,
+        &addr
+      );
+      let started = Instant::now();
+      let spawned =
+        SSHProxyCommand::spawn(proxy_command, hostname, port, username, ipc_channel.clone());
+      diagnostics.stage(SSHSessionStage::ProxyCommand, started, &spawned);
+      let (proxy_command, stream) = spawned?;
+      ssh_proxy_command = Some(proxy_command);
+
+      let stream = SSHDiagnosticStream::new(stream, diagnostics.clone());
+      handshake(
+        &diagnostics,
+        client::connect_stream(config, stream, ssh_client),
+      )
+      .await
+    } else if let Some(upstream_proxy) = upstream_proxy {
+      log::info!(
+        "session connect {:?} to {} with upstream proxy",
+        ssh_session_id,
+        &addr
+      );
+      let started = Instant::now();
+      let stream = upstream_proxy.connect(hostname, port).await;
+      diagnostics.stage(SSHSessionStage::UpstreamProxy, started, &stream);
+      let stream = stream?;
+      diagnostics.set_addrs(stream.local_addr().ok(), None);
+
+      let stream = SSHDiagnosticStream::new(stream, diagnostics.clone());
+      handshake(
+        &diagnostics,
+        client::connect_stream(config, stream, ssh_client),
+      )
+      .await
     } else {
       log::info!(
-        "session connect {:?} to {}:{} with direct tcpip",
+        "session connect {:?} to {} with direct tcpip",
         ssh_session_id,
-        &hostname,
-        port
+        &addr
       );
-      let addr = format!("{}:{}", &hostname, port);
-      client::connect(config, &addr, ssh_client)
-        .await
-        .map_err(|err| match err {
-          SSHError::RusshError(e) => match e {
-            RusshError::Disconnect => SSHError::ConnectFailed(addr),
-            err => SSHError::RusshError(err),
-          },
-          err => err,
-        })?
+      let stream = connect_tcp(&diagnostics, &addr, config.nodelay).await?;
+
+      let stream = SSHDiagnosticStream::new(stream, diagnostics.clone());
+      handshake(
+        &diagnostics,
+        client::connect_stream(config, stream, ssh_client),
+      )
+      .await
     };
 
-    log::info!("session connect {:?} success", ssh_session_id);
-    let session = SSHSession::new(ssh_session_id, ipc_channel, handle_ssh_client);
-    {
-      let mut sessions = ssh_manager.sessions.lock().await;
-      sessions.insert(ssh_session_id, session);
-    }
+    let handle_ssh_client = handle_ssh_client.map_err(|err| match err {
+      SSHError::RusshError(e) => match e {
+        RusshError::Disconnect => SSHError::ConnectFailed(addr),
+        err => SSHError::RusshError(err),
+      },
+      err => err,
+    })?;
+    diagnostics.set_connected();
 
-    Ok(ssh_session_id)
+    Ok((handle_ssh_client, ssh_proxy_command, diagnostics))
   })
   .await?
 }
 
+/// Resolves and connects `addr` as separate stages, trying every resolved
+/// address in turn
+async fn connect_tcp(
+  diagnostics: &SSHSessionDiagnostics,
+  addr: &str,
+  nodelay: bool,
+) -> SSHResult<TcpStream> {
+  let started = Instant::now();
+  let addrs = lookup_host(addr)
+    .await
+    .map(|addrs| addrs.collect::<Vec<_>>());
+  diagnostics.stage(SSHSessionStage::Dns, started, &addrs);
+  let addrs = addrs.map_err(|_| SSHError::ConnectFailed(addr.to_string()))?;
+
+  let started = Instant::now();
+  let mut last_err = None;
+  for socket_addr in addrs {
+    match TcpStream::connect(socket_addr).await {
+      Ok(stream) => {
+        diagnostics.stage::<_, io::Error>(SSHSessionStage::Tcp, started, &Ok(()));
+        stream.set_nodelay(nodelay)?;
+        diagnostics.set_addrs(stream.local_addr().ok(), stream.peer_addr().ok());
+        return Ok(stream);
+      }
+      Err(err) => last_err = Some(err),
+    }
+  }
+
+  let err = last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address"));
+  diagnostics.stage::<(), _>(SSHSessionStage::Tcp, started, &Err(&err));
+  log::warn!("session connect to {} failed: {}", addr, err);
+  Err(SSHError::ConnectFailed(addr.to_string()))
+}
+
+async fn handshake<T>(
+  diagnostics: &SSHSessionDiagnostics,
+  connecting: impl Future<Output = SSHResult<T>>,
+) -> SSHResult<T> {
+  let started = Instant::now();
+  let result = connecting.await;
+  diagnostics.stage(SSHSessionStage::Handshake, started, &result);
+  result
+}
+
+/// Connects and authenticates one hop of a jump host chain, reusing a live
+/// session for the same hop when another chain already opened it.
+///
+/// The returned session holds one reference for the caller, the reference the
+/// caller held on `jump_host_ssh_session_id` is transferred to it.
+async fn acquire_jump_host<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  ssh_manager: &State<'_, SSHManager<R>>,
+  jump_host: &SSHJumpHost,
+  jump_host_ssh_session_id: Option<SSHSessionId>,
+  ipc_channel: &Channel<SessionIpcChannelData>,
+  diagnose: bool,
+) -> SSHResult<SSHSessionId> {
+  let jump_host_key = SSHJumpHostKey {
+    hostname: jump_host.hostname.clone(),
+    port: jump_host.port,
+    username: jump_host.username.clone(),
+    jump_host_ssh_session_id,
+  };
+
+  let lock = ssh_manager
+    .jump_host_locks
+    .update(|locks| locks.entry(jump_host_key.clone()).or_default().clone());
+  let acquired = {
+    let _guard = lock.lock().await;
+    acquire_jump_host_locked(
+      app_handle,
+      ssh_manager,
+      jump_host,
+      jump_host_key.clone(),
+      ipc_channel,
+      diagnose,
+    )
+    .await
+  };
+
+  // Only the map and this call hold the lock when no other chain waits on it
+  ssh_manager.jump_host_locks.update(|locks| {
+    if Arc::strong_count(&lock) == 2 {
+      locks.remove(&jump_host_key);
+    }
+  });
+
+  acquired
+}
+
+async fn acquire_jump_host_locked<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  ssh_manager: &State<'_, SSHManager<R>>,
+  jump_host: &SSHJumpHost,
+  jump_host_key: SSHJumpHostKey,
+  ipc_channel: &Channel<SessionIpcChannelData>,
+  diagnose: bool,
+) -> SSHResult<SSHSessionId> {
+  let jump_host_ssh_session_id = jump_host_key.jump_host_ssh_session_id;
+
+  let reused_ssh_session_id = ssh_manager.sessions.update(|sessions| {
+    sessions
+      .values()
+      .find(|session| {
+        session.jump_host_key.as_ref() == Some(&jump_host_key) && !session.is_closed()
+      })
+      .map(|session| {
+        session.dependents.fetch_add(1, Ordering::SeqCst);
+        session.ssh_session_id
+      })
+  });
+
+  if let Some(reused_ssh_session_id) = reused_ssh_session_id {
+    log::info!("reuse jump host session {:?}", reused_ssh_session_id);
+    if let Some(jump_host_ssh_session_id) = jump_host_ssh_session_id {
+      ssh_manager
+        .jump_host_release(jump_host_ssh_session_id)
+        .await;
+    }
+    return Ok(reused_ssh_session_id);
+  }
+
+  let ssh_session_id = SSHSessionId::from(Uuid::new_v4());
+  let (handle_ssh_client, proxy_command, diagnostics) = connect(
+    app_handle,
+    ssh_manager,
+    ssh_session_id,
+    SSHConnectTarget {
+      hostname: &jump_host.hostname,
+      port: jump_host.port,
+      username: &jump_host.username,
+      proxy_command: jump_host.proxy_command.as_deref(),
+      upstream_proxy: jump_host.upstream_proxy.clone(),
+      check_server_key: jump_host.check_server_key.clone(),
+      strict_host_key_checking: jump_host.strict_host_key_checking,
+      diagnose,
+      connection_profile: jump_host.connection_profile.as_ref(),
+    },
+    jump_host_ssh_session_id,
+    ipc_channel,
+  )
+  .await?;
+
+  let mut session = SSHSession::new(
+    ssh_session_id,
+    ipc_channel.clone(),
+    handle_ssh_client,
+    jump_host_ssh_session_id,
+    diagnostics,
+    jump_host
+      .connection_profile
+      .clone()
+      .unwrap_or_default()
+      .connect_timeout(),
+  );
+  session.proxy_command = proxy_command;
+
+  if let Err(err) = authenticate_chain(
+    &session,
+    &jump_host.username,
+    jump_host.authentication_data.clone(),
+    jump_host.authentication_chain.clone().unwrap_or_default(),
+  )
+  .await
+  {
+    let _ = session
+      .handle()
+      .await
+      .disconnect(Disconnect::ByApplication, "", "English")
+      .await;
+    return Err(err.into());
+  }
+
+  session.jump_host_key = Some(jump_host_key);
+  session.dependents = AtomicUsize::new(1);
+
+  ssh_manager
+    .sessions
+    .insert(ssh_session_id, Arc::new(session));
+
+  log::info!("jump host session {:?} authenticated", ssh_session_id);
+
+  Ok(ssh_session_id)
+}
+
+/// Where `session_connect` connected a session, kept to connect it again
+#[derive(Debug, Clone)]
+pub struct SSHSessionConnectParams {
+  pub hostname: String,
+  pub port: u16,
+  pub username: Option<String>,
+  pub proxy_command: Option<String>,
+  pub upstream_proxy: Option<SSHUpstreamProxy>,
+  pub jump_hosts: Vec<SSHJumpHost>,
+  pub check_server_key: Option<SSHSessionCheckServerKey>,
+  pub strict_host_key_checking: Option<SSHSessionStrictHostKeyChecking>,
+  pub connection_profile: Option<SSHConnectionProfile>,
+}
+
+/// Connects a session through its jump hosts, the session is not registered
+/// with the manager
+pub(crate) async fn open_session<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  ssh_manager: &State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
+  params: &SSHSessionConnectParams,
+  diagnose: bool,
+  ipc_channel: &Channel<SessionIpcChannelData>,
+) -> SSHResult<SSHSession<R>> {
+  let jump_hosts = &params.jump_hosts;
+
+  let mut jump_host_ssh_session_id = None;
+  for (hop, jump_host) in jump_hosts.iter().enumerate() {
+    match acquire_jump_host(
+      app_handle,
+      ssh_manager,
+      jump_host,
+      jump_host_ssh_session_id,
+      ipc_channel,
+      diagnose,
+    )
+    .await
+    {
+      Ok(acquired_ssh_session_id) => jump_host_ssh_session_id = Some(acquired_ssh_session_id),
+      Err(error) => {
+        if let Some(jump_host_ssh_session_id) = jump_host_ssh_session_id {
+          ssh_manager
+            .jump_host_release(jump_host_ssh_session_id)
+            .await;
+        }
+        return Err(SSHError::JumpHostConnectFailed {
+          hop,
+          hostname: jump_host.hostname.clone(),
+          port: jump_host.port,
+          error: Box::new(error),
+        });
+      }
+    }
+  }
+
+  let (handle_ssh_client, proxy_command, diagnostics) = match connect(
+    app_handle,
+    ssh_manager,
+    ssh_session_id,
+    SSHConnectTarget {
+      hostname: &params.hostname,
+      port: params.port,
+      username: params.username.as_deref().unwrap_or_default(),
+      proxy_command: params.proxy_command.as_deref(),
+      upstream_proxy: params.upstream_proxy.clone(),
+      check_server_key: params.check_server_key.clone(),
+      strict_host_key_checking: params.strict_host_key_checking,
+      diagnose,
+      connection_profile: params.connection_profile.as_ref(),
+    },
+    jump_host_ssh_session_id,
+    ipc_channel,
+  )
+  .await
+  {
+    Ok(connected) => connected,
+    Err(error) => {
+      if let Some(jump_host_ssh_session_id) = jump_host_ssh_session_id {
+        ssh_manager
+          .jump_host_release(jump_host_ssh_session_id)
+          .await;
+      }
+      return Err(match (jump_hosts.last(), error) {
+        (Some(jump_host), error @ SSHError::ConnectFailed(_)) => SSHError::JumpHostConnectFailed {
+          hop: jump_hosts.len() - 1,
+          hostname: jump_host.hostname.clone(),
+          port: jump_host.port,
+          error: Box::new(error),
+        },
+        (_, error) => error,
+      });
+    }
+  };
+
+  let mut session = SSHSession::new(
+    ssh_session_id,
+    ipc_channel.clone(),
+    handle_ssh_client,
+    jump_host_ssh_session_id,
+    diagnostics,
+    params
+      .connection_profile
+      .clone()
+      .unwrap_or_default()
+      .connect_timeout(),
+  );
+  session.proxy_command = proxy_command;
+
+  Ok(session)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn session_connect<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
+  hostname: String,
+  port: u16,
+  username: Option<String>,
+  proxy_command: Option<String>,
+  upstream_proxy: Option<SSHUpstreamProxy>,
+  jump_hosts: Option<Vec<SSHJumpHost>>,
+  check_server_key: Option<SSHSessionCheckServerKey>,
+  strict_host_key_checking: Option<SSHSessionStrictHostKeyChecking>,
+  diagnose: Option<bool>,
+  reconnect: Option<SSHSessionReconnectPolicy>,
+  multiplex: Option<SSHSessionMultiplexOpts>,
+  connection_profile: Option<SSHConnectionProfile>,
+  ipc_channel: Channel<SessionIpcChannelData>,
+) -> SSHResult<SSHSessionId> {
+  log::info!("session connect: {:?}", ssh_session_id);
+  let diagnose = diagnose.unwrap_or_default();
+  let params = SSHSessionConnectParams {
+    hostname,
+    port,
+    username,
+    proxy_command,
+    upstream_proxy,
+    jump_hosts: jump_hosts.unwrap_or_default(),
+    check_server_key,
+    strict_host_key_checking,
+    connection_profile,
+  };
+
+  // Diagnose mode always times a connection of its own
+  let multiplex = multiplex
+    .filter(|_| !diagnose)
+    .and_then(|opts| Some((SSHMultiplexKey::new(&params)?, opts)));
+  if let Some((key, _)) = &multiplex
+    && let Some(session) = multiplex_session(&ssh_manager, ssh_session_id, key, &ipc_channel)
+  {
+    log::info!(
+      "session connect {:?} multiplexed onto {:?}",
+      ssh_session_id,
+      session.connection_id
+    );
+    ssh_manager
+      .sessions
+      .insert(ssh_session_id, Arc::new(session));
+    return Ok(ssh_session_id);
+  }
+
+  let mut session = open_session(
+    &app_handle,
+    &ssh_manager,
+    ssh_session_id,
+    &params,
+    diagnose,
+    &ipc_channel,
+  )
+  .await?;
+
+  log::info!("session connect {:?} success", ssh_session_id);
+  session.multiplex = multiplex.map(|(key, opts)| Arc::new(SSHSessionMultiplex::new(key, &opts)));
+  session.reconnect = reconnect.map(|policy| Arc::new(SSHSessionReconnect::new(policy, params)));
+  ssh_manager
+    .sessions
+    .insert(ssh_session_id, Arc::new(session));
+
+  Ok(ssh_session_id)
+}
+
+/// Info requests answered automatically in a row before the prompts are
+/// handed to the user
+const MAX_ANSWERED_ROUNDS: usize = 5;
+
 async fn authenticate_with_keyboard_interactive<R: Runtime>(
-  session: &mut SSHSession<R>,
+  session: &mut Handle<SSHClient<R>>,
+  ssh_session_id: SSHSessionId,
   username: &str,
-  password: Option<String>,
+  answers: &KeyboardInteractiveAnswers,
   prompts: Option<Vec<String>>,
 ) -> Result<(), AuthenticationError> {
-  let ssh_session_id = session.ssh_session_id;
   log::info!(
     "authenticate session {:?} by keyboard interactive",
     ssh_session_id
   );
 
+  // Answers from the user already count as a round
+  let resumed = prompts.is_some();
   let mut auth_res = if let Some(prompts) = prompts {
     session
       .authenticate_keyboard_interactive_respond(prompts)
@@ -196,6 +934,7 @@ async fn authenticate_with_keyboard_interactive<R: Runtime>(
     auth_res
   );
 
+  let mut answered_rounds = 0;
   loop {
     match auth_res {
       KeyboardInteractiveAuthResponse::Success => {
@@ -221,12 +960,20 @@ async fn authenticate_with_keyboard_interactive<R: Runtime>(
             .await?;
           continue;
         }
-        if let Some(password) = password.clone()
-          && prompts.len() == 1
-          && prompts.first().is_some_and(|p| !p.echo)
+        let first_round = !resumed && answered_rounds == 0;
+        // A server asking again after being answered is not going to accept
+        // the same answers, give the user a chance after a few rounds
+        if answered_rounds < MAX_ANSWERED_ROUNDS
+          && let Some(responses) = answers.answer_all(&prompts, first_round)
         {
+          log::info!(
+            "authenticate session {:?} by keyboard interactive answer {} prompts",
+            ssh_session_id,
+            responses.len()
+          );
+          answered_rounds += 1;
           auth_res = session
-            .authenticate_keyboard_interactive_respond(vec![password])
+            .authenticate_keyboard_interactive_respond(responses)
             .await?;
           continue;
         }
@@ -242,11 +989,14 @@ async fn authenticate_with_keyboard_interactive<R: Runtime>(
   }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "authenticationMethod", rename_all_fields = "camelCase")]
 pub enum AuthenticationData {
   Password {
     password: String,
+    /// Used when the server falls back to keyboard-interactive
+    totp_secret: Option<String>,
+    keyboard_interactive_rules: Option<Vec<KeyboardInteractiveRule>>,
   },
   PublicKey {
     private_key: String,
@@ -259,7 +1009,32 @@ pub enum AuthenticationData {
   },
   KeyboardInteractive {
     prompts: Option<Vec<String>>,
+    password: Option<String>,
+    totp_secret: Option<String>,
+    keyboard_interactive_rules: Option<Vec<KeyboardInteractiveRule>>,
   },
+  Agent {
+    socket_path: Option<String>,
+  },
+}
+
+impl AuthenticationData {
+  /// Whether the step can satisfy one of the methods the server still requires
+  fn is_accepted_by(&self, remaining_methods: &MethodSet) -> bool {
+    match self {
+      // Falls back to keyboard-interactive, see `authenticate`
+      AuthenticationData::Password { .. } => {
+        remaining_methods.contains(&MethodKind::Password)
+          || remaining_methods.contains(&MethodKind::KeyboardInteractive)
+      }
+      AuthenticationData::PublicKey { .. }
+      | AuthenticationData::Certificate { .. }
+      | AuthenticationData::Agent { .. } => remaining_methods.contains(&MethodKind::PublicKey),
+      AuthenticationData::KeyboardInteractive { .. } => {
+        remaining_methods.contains(&MethodKind::KeyboardInteractive)
+      }
+    }
+  }
 }
 
 impl Into<MethodKind> for AuthenticationData {
@@ -269,31 +1044,34 @@ fn into(self) -> MethodKind {
       AuthenticationData::PublicKey { .. } => MethodKind::PublicKey,
       AuthenticationData::Certificate { .. } => MethodKind::HostBased,
       AuthenticationData::KeyboardInteractive { .. } => MethodKind::KeyboardInteractive,
+      AuthenticationData::Agent { .. } => MethodKind::PublicKey,
     }
   }
 }
 
-#[tauri::command]
-pub async fn session_authenticate<R: Runtime>(
-  _app_handle: AppHandle<R>,
-  ssh_manager: State<'_, SSHManager<R>>,
-  ssh_session_id: SSHSessionId,
+async fn authenticate<R: Runtime>(
+  session: &SSHSession<R>,
   username: &str,
   authentication_data: AuthenticationData,
-) -> Result<SSHSessionId, AuthenticationError> {
+) -> Result<(), AuthenticationError> {
+  let ssh_session_id = session.ssh_session_id;
+  let auth_timeout = session.auth_timeout;
   log::info!("authenticate session {:?}", ssh_session_id);
-  let mut sessions = ssh_manager.sessions.lock().await;
-  let session = sessions
-    .get_mut(&ssh_session_id)
-    .ok_or(AuthenticationError::NotFoundSession)?;
+
+  let mut session = session.handle_mut().await;
+  let session = &mut *session;
 
   if session.is_closed() {
     return Err(AuthenticationError::SessionClosed);
   }
 
   match authentication_data {
-    AuthenticationData::Password { password } => {
-      timeout(Duration::from_secs(5), async {
+    AuthenticationData::Password {
+      password,
+      totp_secret,
+      keyboard_interactive_rules,
+    } => {
+      timeout(auth_timeout, async {
         log::info!("authenticate session {:?} by password", ssh_session_id);
 
         let auth_res = session
@@ -312,15 +1090,26 @@ pub async fn session_authenticate<R: Runtime>(
         } = auth_res
         {
           if remaining_methods.contains(&MethodKind::KeyboardInteractive) {
-            authenticate_with_keyboard_interactive(session, username, Some(password.clone()), None)
-              .await
-              .map_err(|err| {
-                if let AuthenticationError::KeyboardInteractiveInfoRequest(_) = err {
-                  err
-                } else {
-                  AuthenticationError::Password(remaining_methods, partial_success)
-                }
-              })?;
+            let answers = KeyboardInteractiveAnswers::new(
+              Some(password.clone()),
+              totp_secret,
+              keyboard_interactive_rules,
+            )?;
+            authenticate_with_keyboard_interactive(
+              session,
+              ssh_session_id,
+              username,
+              &answers,
+              None,
+            )
+            .await
+            .map_err(|err| {
+              if let AuthenticationError::KeyboardInteractiveInfoRequest(_) = err {
+                err
+              } else {
+                AuthenticationError::Password(remaining_methods, partial_success)
+              }
+            })?;
           } else {
             return Err(AuthenticationError::Password(
               remaining_methods,
@@ -329,7 +1118,7 @@ pub async fn session_authenticate<R: Runtime>(
           }
         }
 
-        Ok(ssh_session_id)
+        Ok(())
       })
       .await?
     }
@@ -337,7 +1126,7 @@ pub async fn session_authenticate<R: Runtime>(
       private_key,
       passphrase,
     } => {
-      timeout(Duration::from_secs(5), async {
+      timeout(auth_timeout, async {
         log::info!("authenticate session {:?} by public key", ssh_session_id);
 
         if private_key.is_empty() {
@@ -399,7 +1188,7 @@ pub async fn session_authenticate<R: Runtime>(
           ));
         }
 
-        Ok(ssh_session_id)
+        Ok(())
       })
       .await?
     }
@@ -408,7 +1197,7 @@ pub async fn session_authenticate<R: Runtime>(
       passphrase,
       certificate,
     } => {
-      timeout(Duration::from_secs(5), async {
+      timeout(auth_timeout, async {
         log::info!("authenticate session {:?} by certificate", ssh_session_id);
 
         if private_key.is_empty() {
@@ -471,28 +1260,307 @@ pub async fn session_authenticate<R: Runtime>(
           ));
         }
 
-        Ok(ssh_session_id)
+        Ok(())
       })
       .await?
     }
-    AuthenticationData::KeyboardInteractive { prompts } => {
-      authenticate_with_keyboard_interactive(session, username, None, prompts.clone()).await?;
-      Ok(ssh_session_id)
+    AuthenticationData::KeyboardInteractive {
+      prompts,
+      password,
+      totp_secret,
+      keyboard_interactive_rules,
+    } => {
+      let answers =
+        KeyboardInteractiveAnswers::new(password, totp_secret, keyboard_interactive_rules)?;
+      authenticate_with_keyboard_interactive(session, ssh_session_id, username, &answers, prompts)
+        .await
+    }
+    AuthenticationData::Agent { socket_path } => {
+      timeout(auth_timeout, async {
+        log::info!("authenticate session {:?} by agent", ssh_session_id);
+
+        let mut agent = connect_agent(socket_path.as_deref()).await?;
+        let identities = agent.request_identities().await?;
+        log::info!(
+          "authenticate session {:?} by agent with {} identities",
+          ssh_session_id,
+          identities.len()
+        );
+
+        if identities.is_empty() {
+          return Err(AuthenticationError::new("No identities found in agent"));
+        }
+
+        let hash_alg = session
+          .best_supported_rsa_hash()
+          .await
+          .map_err(|err| {
+            AuthenticationError::new(format!("Failed to get best supported rsa hash: {}", err))
+          })?
+          .unwrap_or_default();
+
+        let mut failure = None;
+        for identity in identities {
+          log::info!(
+            "authenticate session {:?} by agent identity {:?}",
+            ssh_session_id,
+            identity.algorithm()
+          );
+
+          let identity_hash_alg = if identity.algorithm().is_rsa() {
+            hash_alg
+          } else {
+            None
+          };
+
+          let auth_res = session
+            .authenticate_publickey_with(username, identity, identity_hash_alg, &mut agent)
+            .await
+            .map_err(|err| {
+              AuthenticationError::new(format!("Failed to sign with agent: {}", err))
+            })?;
+
+          log::info!(
+            "authenticate session {:?} by agent result {:?}",
+            ssh_session_id,
+            auth_res
+          );
+
+          match auth_res {
+            AuthResult::Success => return Ok(()),
+            AuthResult::Failure {
+              remaining_methods,
+              partial_success,
+            } => {
+              failure = Some(AuthenticationError::Agent(
+                remaining_methods,
+                partial_success,
+              ));
+              if partial_success {
+                break;
+              }
+            }
+          }
+        }
+
+        Err(failure.unwrap_or_else(|| AuthenticationError::new("Authentication failed with agent")))
+      })
+      .await?
     }
   }
 }
 
+/// Authenticates with `authentication_data`, a partial success continues with
+/// the first step of `authentication_chain` the server still accepts, e.g. for
+/// `AuthenticationMethods publickey,password`.
+///
+/// Fails with the last error when the server is not satisfied after the steps
+/// it accepts, which may be a keyboard-interactive info request to be answered
+/// by the user.
+pub(crate) async fn authenticate_chain<R: Runtime>(
+  session: &SSHSession<R>,
+  username: &str,
+  authentication_data: AuthenticationData,
+  authentication_chain: Vec<AuthenticationData>,
+) -> Result<(), AuthenticationError> {
+  let started = Instant::now();
+  let result =
+    authenticate_steps(session, username, authentication_data, authentication_chain).await;
+  session
+    .diagnostics
+    .stage(SSHSessionStage::Authentication, started, &result);
+  result
+}
+
+async fn authenticate_steps<R: Runtime>(
+  session: &SSHSession<R>,
+  username: &str,
+  authentication_data: AuthenticationData,
+  mut authentication_chain: Vec<AuthenticationData>,
+) -> Result<(), AuthenticationError> {
+  let mut authentication_data = authentication_data;
+  loop {
+    let err = match authenticate(session, username, authentication_data).await {
+      Ok(()) => return Ok(()),
+      Err(err) => err,
+    };
+
+    let Some(remaining_methods) = err.partial_success() else {
+      return Err(err);
+    };
+    let Some(index) = authentication_chain
+      .iter()
+      .position(|step| step.is_accepted_by(remaining_methods))
+    else {
+      return Err(err);
+    };
+
+    log::info!(
+      "authenticate session {:?} partial success, continue with {:?}",
+      session.ssh_session_id,
+      remaining_methods
+    );
+    authentication_data = authentication_chain.remove(index);
+  }
+}
+
 #[tauri::command]
-pub async fn session_disconnect<R: Runtime>(
+pub async fn session_authenticate<R: Runtime>(
   _app_handle: AppHandle<R>,
   ssh_manager: State<'_, SSHManager<R>>,
   ssh_session_id: SSHSessionId,
+  username: &str,
+  authentication_data: AuthenticationData,
+  authentication_chain: Option<Vec<AuthenticationData>>,
+) -> Result<SSHSessionId, AuthenticationError> {
+  let session = ssh_manager
+    .sessions
+    .get(&ssh_session_id)
+    .ok_or(AuthenticationError::NotFoundSession)?;
+
+  if session.is_multiplexed() {
+    log::info!(
+      "authenticate session {:?} already authenticated on {:?}",
+      ssh_session_id,
+      session.connection_id
+    );
+    return Ok(ssh_session_id);
+  }
+
+  let authentication_chain = authentication_chain.unwrap_or_default();
+  let credentials = session.reconnect.as_ref().map(|_| SSHSessionCredentials {
+    username: username.to_string(),
+    authentication_data: authentication_data.clone(),
+    authentication_chain: authentication_chain.clone(),
+  });
+
+  authenticate_chain(
+    &session,
+    username,
+    authentication_data,
+    authentication_chain,
+  )
+  .await?;
+
+  if let (Some(reconnect), Some(credentials)) = (&session.reconnect, credentials) {
+    reconnect.set_credentials(credentials);
+  }
+  if let Some(multiplex) = &session.multiplex {
+    multiplex.set_authenticated(username);
+  }
+  session.sample_rtt();
+
+  Ok(ssh_session_id)
+}
+
+/// Details of a live session, the round trip is measured with a keepalive
+/// when called
+#[tauri::command]
+pub async fn session_info<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
+) -> SSHResult<SSHSessionInfo> {
+  let session = ssh_manager
+    .sessions
+    .get(&ssh_session_id)
+    .ok_or(SSHError::NotFoundSession)?;
+
+  if let Err(err) = session.ping().await {
+    log::warn!("session info {:?} ping failed: {}", ssh_session_id, err);
+  }
+
+  let mut jump_path = Vec::new();
+  let mut jump_host_ssh_session_id = ssh_manager
+    .sessions
+    .get(&session.connection_id)
+    .and_then(|connection| connection.jump_host_ssh_session_id);
+  while let Some(jump_host_session) =
+    jump_host_ssh_session_id.and_then(|ssh_session_id| ssh_manager.sessions.get(&ssh_session_id))
+  {
+    jump_path.insert(0, jump_host_session.diagnostics.address());
+    jump_host_ssh_session_id = jump_host_session.jump_host_ssh_session_id;
+  }
+
+  Ok(session.diagnostics.info(jump_path))
+}
+
+/// Sends a `none` authentication request to learn the methods the server
+/// accepts for `username` before asking the user for credentials
+#[tauri::command]
+pub async fn session_probe_auth<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
+  username: &str,
+) -> Result<SSHSessionProbeAuth, AuthenticationError> {
+  let session = ssh_manager
+    .sessions
+    .get(&ssh_session_id)
+    .ok_or(AuthenticationError::NotFoundSession)?;
+
+  if session.is_multiplexed() {
+    return Ok(SSHSessionProbeAuth {
+      authenticated: true,
+      methods: Vec::new(),
+    });
+  }
+
+  timeout(session.auth_timeout, async {
+    log::info!("probe auth session {:?}", ssh_session_id);
+
+    let mut handle = session.handle_mut().await;
+    if handle.is_closed() {
+      return Err(AuthenticationError::SessionClosed);
+    }
+
+    let auth_res = handle.authenticate_none(username).await?;
+    log::info!(
+      "probe auth session {:?} result {:?}",
+      ssh_session_id,
+      auth_res
+    );
+
+    Ok(match auth_res {
+      AuthResult::Success => SSHSessionProbeAuth {
+        authenticated: true,
+        methods: Vec::new(),
+      },
+      AuthResult::Failure {
+        remaining_methods, ..
+      } => SSHSessionProbeAuth {
+        authenticated: false,
+        methods: remaining_methods.iter().map(method_kind_name).collect(),
+      },
+    })
+  })
+  .await?
+}
+
+#[tauri::command]
+pub async fn session_disconnect<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_session_id: SSHSessionId,
 ) -> SSHResult<SSHSessionId> {
   timeout(Duration::from_secs(5), async {
     log::info!("disconnect session {:?}", ssh_session_id);
-    let mut sessions = ssh_manager.sessions.lock().await;
-    if let Some(session) = sessions.remove(&ssh_session_id) {
+    if let Some(cancel) = ssh_manager.reconnecting.remove(&ssh_session_id) {
+      cancel.notify_one();
+    }
+    if multiplex_disconnect(&app_handle, ssh_session_id).await {
+      log::info!(
+        "disconnect session {:?} left shared connection",
+        ssh_session_id
+      );
+      return Ok(ssh_session_id);
+    }
+    let session = ssh_manager.session_remove(ssh_session_id).await;
+    ssh_manager.session_resources_close(ssh_session_id).await;
+    if let Some(session) = session {
       session
+        .handle()
+        .await
         .disconnect(Disconnect::ByApplication, "", "English")
         .await?;
     }
@@ -502,3 +1570,28 @@ pub async fn session_disconnect<R: Runtime>(
   })
   .await?
 }
+
+/// Sets the upstream proxy used by hosts without their own, `None` connects
+/// directly. It is kept across restarts.
+#[tauri::command]
+pub async fn session_set_upstream_proxy<R: Runtime>(
+  app_handle: AppHandle<R>,
+  _ssh_manager: State<'_, SSHManager<R>>,
+  upstream_proxy: Option<SSHUpstreamProxy>,
+) -> SSHResult<()> {
+  log::info!(
+    "set upstream proxy {:?}",
+    upstream_proxy
+      .as_ref()
+      .map(|proxy| format!("{:?} {}:{}", proxy.r#type, proxy.hostname, proxy.port))
+  );
+  save_global_upstream_proxy(&app_handle, upstream_proxy.as_ref())
+}
+
+#[tauri::command]
+pub async fn session_get_upstream_proxy<R: Runtime>(
+  app_handle: AppHandle<R>,
+  _ssh_manager: State<'_, SSHManager<R>>,
+) -> SSHResult<Option<SSHUpstreamProxy>> {
+  load_global_upstream_proxy(&app_handle)
+}
@@ -1,4 +1,4 @@
-use std::{ops::Deref, time::Duration};
+use std::{ops::Deref, sync::Arc, time::Duration};
 
 use russh::ChannelId;
 use russh_sftp::{client::SftpSession, protocol::FileType as RusshSftpFileType};
@@ -18,7 +18,7 @@
 use uuid::Uuid;
 
 use crate::{
-  commands::session::SSHSessionId,
+  commands::session::{SSHChannelKind, SSHSessionId},
   error::{SSHError, SSHResult},
   ssh_manager::SSHManager,
 };
@@ -43,7 +43,9 @@ fn body(self) -> tauri::Result<InvokeResponseBody> {
 
 pub struct SSHSftp {
   pub ssh_session_id: SSHSessionId,
-  #[allow(unused)]
+  /// The session whose connection carries the channel, another one when the
+  /// connection is shared
+  pub connection_id: SSHSessionId,
   pub ssh_sftp_id: SSHSftpId,
   pub sftp_channel_id: ChannelId,
   pub sftp_session: SftpSession,
@@ -53,6 +55,7 @@ pub struct SSHSftp {
 impl SSHSftp {
   pub fn new(
     ssh_session_id: SSHSessionId,
+    connection_id: SSHSessionId,
     ssh_sftp_id: SSHSftpId,
     sftp_channel_id: ChannelId,
     sftp_session: SftpSession,
@@ -60,6 +63,7 @@ pub fn new(
   ) -> Self {
     Self {
       ssh_session_id,
+      connection_id,
       ssh_sftp_id,
       sftp_channel_id,
       sftp_session,
@@ -86,16 +90,20 @@ pub async fn sftp_open<R: Runtime>(
 ) -> SSHResult<SSHSftpId> {
   timeout(Duration::from_secs(5), async {
     log::info!("sftp open {:?} {:?}", ssh_session_id, ssh_sftp_id);
-    let sftp_channel = {
-      let sessions = ssh_manager.sessions.lock().await;
-      let session = sessions
+    let (connection_id, sftp_channel) = {
+      let session = ssh_manager
+        .sessions
         .get(&ssh_session_id)
         .ok_or(SSHError::NotFoundSession)?;
 
-      session.channel_open_session().await?
+      (
+        session.connection_id,
+        session.handle().await.channel_open_session().await?,
+      )
     };
 
     let sftp_channel_id = sftp_channel.id();
+    ssh_manager.channel_opened(ssh_session_id, sftp_channel_id, SSHChannelKind::Sftp);
 
     log::info!(
       "sftp open channel open session success {:?} {:?} {}",
@@ -116,16 +124,18 @@ pub async fn sftp_open<R: Runtime>(
 
     let sftp = SSHSftp::new(
       ssh_session_id,
+      connection_id,
       ssh_sftp_id,
       sftp_channel_id,
       sftp_session,
       ipc_channel,
     );
 
-    {
-      let mut sftps = ssh_manager.sftps.lock().await;
-      sftps.insert(ssh_sftp_id, sftp);
-    }
+    let sftp = Arc::new(sftp);
+    ssh_manager
+      .sftp_sinks
+      .insert((connection_id, sftp_channel_id), sftp.clone());
+    ssh_manager.sftps.insert(ssh_sftp_id, sftp);
 
     Ok(ssh_sftp_id)
   })
@@ -139,8 +149,10 @@ pub async fn sftp_close<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
 ) -> SSHResult<SSHSftpId> {
   timeout(Duration::from_secs(5), async {
-    let mut sftps = ssh_manager.sftps.lock().await;
-    if let Some(sftp) = sftps.remove(&ssh_sftp_id) {
+    if let Some(sftp) = ssh_manager.sftps.remove(&ssh_sftp_id) {
+      ssh_manager
+        .sftp_sinks
+        .remove(&(sftp.connection_id, sftp.sftp_channel_id));
       sftp.close().await?;
     }
 
@@ -191,8 +203,10 @@ pub async fn sftp_read_dir<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   dirname: String,
 ) -> SSHResult<Vec<SSHSftpFile>> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   let read_dir = sftp.read_dir(&dirname).await?;
   let files: Vec<SSHSftpFile> = read_dir
@@ -269,8 +283,10 @@ pub async fn sftp_upload_file<R: Runtime>(
   on_progress: Channel<SFTPProgressPayload>,
 ) -> SSHResult<SSHSftpId> {
   let remote_file = {
-    let sftps = ssh_manager.sftps.lock().await;
-    let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+    let sftp = ssh_manager
+      .sftps
+      .get(&ssh_sftp_id)
+      .ok_or(SSHError::NotFoundSftp)?;
     sftp.create(remote_filename).await?
   };
 
@@ -302,8 +318,10 @@ pub async fn sftp_download_file<R: Runtime>(
   on_progress: Channel<SFTPProgressPayload>,
 ) -> SSHResult<SSHSftpId> {
   let mut remote_file = {
-    let sftps = ssh_manager.sftps.lock().await;
-    let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+    let sftp = ssh_manager
+      .sftps
+      .get(&ssh_sftp_id)
+      .ok_or(SSHError::NotFoundSftp)?;
     sftp.open(remote_filename).await?
   };
 
@@ -337,8 +355,10 @@ pub async fn sftp_create_file<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   filename: String,
 ) -> SSHResult<SSHSftpId> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   sftp.create(filename).await?;
 
@@ -352,8 +372,10 @@ pub async fn sftp_create_dir<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   dirname: String,
 ) -> SSHResult<SSHSftpId> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   sftp.create_dir(dirname).await?;
 
@@ -367,8 +389,10 @@ pub async fn sftp_remove_dir<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   dirname: String,
 ) -> SSHResult<SSHSftpId> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   sftp.remove_dir(dirname).await?;
 
@@ -382,8 +406,10 @@ pub async fn sftp_remove_file<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   filename: String,
 ) -> SSHResult<SSHSftpId> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   sftp.remove_file(filename).await?;
 
@@ -398,8 +424,10 @@ pub async fn sftp_rename<R: Runtime>(
   old_path: String,
   new_path: String,
 ) -> SSHResult<SSHSftpId> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   sftp.rename(old_path, new_path).await?;
 
@@ -413,8 +441,10 @@ pub async fn sftp_exists<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   path: String,
 ) -> SSHResult<bool> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   let is_exists = sftp.try_exists(path).await?;
 
@@ -428,8 +458,10 @@ pub async fn sftp_canonicalize<R: Runtime>(
   ssh_sftp_id: SSHSftpId,
   path: String,
 ) -> SSHResult<String> {
-  let sftps = ssh_manager.sftps.lock().await;
-  let sftp = sftps.get(&ssh_sftp_id).ok_or(SSHError::NotFoundSftp)?;
+  let sftp = ssh_manager
+    .sftps
+    .get(&ssh_sftp_id)
+    .ok_or(SSHError::NotFoundSftp)?;
 
   let absolute_path = sftp.canonicalize(path).await?;
 
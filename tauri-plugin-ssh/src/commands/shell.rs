@@ -1,20 +1,40 @@
-use std::{collections::HashMap, env, ops::Deref, time::Duration};
+use std::{
+  collections::HashMap,
+  env,
+  ops::Deref,
+  sync::{
+    Arc, Mutex, PoisonError,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::Duration,
+};
 
-use russh::{Channel as RusshChannel, client};
+use russh::{
+  Channel as RusshChannel, ChannelId, ChannelMsg, ChannelReadHalf, ChannelWriteHalf, client,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use strum::AsRefStr;
 use tauri::{
-  AppHandle, Runtime, State,
+  AppHandle, Manager, Runtime, State, async_runtime,
   ipc::{Channel, InvokeResponseBody, IpcResponse},
 };
-use tokio::time::timeout;
+use tokio::{
+  sync::mpsc::{self, UnboundedSender},
+  time::timeout,
+};
 use uuid::Uuid;
 
 use crate::{
-  commands::session::SSHSessionId,
+  commands::session::{SSHChannelKind, SSHSessionId},
   error::{SSHError, SSHResult},
+  recording::{SSHShellRecorder, SSHShellRecordingId, SSHShellRecordingOpts},
+  shell_output::SSHShellOutput,
+  ssh_agent::{AgentForwardingData, SSHAgentForwarder},
   ssh_manager::SSHManager,
+  startup_script::{SSHShellStartupScript, SSHShellStartupStep},
+  utils::{get_recordings_dir, parse_signal, signal_name},
+  x11_forwarding::{SSHX11Forwarder, X11ForwardingData},
 };
 
 #[derive(Debug, Clone, AsRefStr)]
@@ -22,12 +42,65 @@ pub enum SHHShellIpcChannelData {
   Eof,
   Close,
   Data(Vec<u8>),
+  Stderr(Vec<u8>),
+  ExitStatus(u32),
+  ExitSignal {
+    signal_name: String,
+    core_dumped: bool,
+    error_message: String,
+  },
+  StartupScriptFailed {
+    step: usize,
+    message: String,
+  },
+  /// The shell was reopened after its session reconnected, the remote
+  /// processes of the old shell are gone
+  Reconnected,
 }
 
 impl IpcResponse for SHHShellIpcChannelData {
   fn body(self) -> tauri::Result<InvokeResponseBody> {
     match self {
       SHHShellIpcChannelData::Data(data) => Ok(InvokeResponseBody::Raw(data)),
+      SHHShellIpcChannelData::Stderr(ref data) => {
+        let body = json!({
+          "type": self.as_ref(),
+          "data": data,
+        });
+        Ok(InvokeResponseBody::Json(body.to_string()))
+      }
+      SHHShellIpcChannelData::ExitStatus(exit_status) => {
+        let body = json!({
+          "type": self.as_ref(),
+          "data": exit_status,
+        });
+        Ok(InvokeResponseBody::Json(body.to_string()))
+      }
+      SHHShellIpcChannelData::ExitSignal {
+        ref signal_name,
+        core_dumped,
+        ref error_message,
+      } => {
+        let body = json!({
+          "type": self.as_ref(),
+          "data": {
+            "signalName": signal_name,
+            "coreDumped": core_dumped,
+            "errorMessage": error_message,
+          },
+        });
+        Ok(InvokeResponseBody::Json(body.to_string()))
+      }
+      SHHShellIpcChannelData::StartupScriptFailed { step, ref message } => {
+        let body = json!({
+          "type": self.as_ref(),
+          "data": {
+            "step": step,
+            "message": message,
+          },
+        });
+        Ok(InvokeResponseBody::Json(body.to_string()))
+      }
       val => {
         let body = json!({
           "type": val.as_ref(),
@@ -41,32 +114,185 @@ fn body(self) -> tauri::Result<InvokeResponseBody> {
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct SSHShellId(Uuid);
 
+/// What a shell was requested with, kept to request it again after its
+/// session reconnects
+#[derive(Debug, Clone)]
+pub struct SSHShellRequest {
+  pub term: String,
+  pub envs: HashMap<String, String>,
+  pub agent_forwarding: Option<AgentForwardingData>,
+  pub x11_forwarding: Option<X11ForwardingData>,
+}
+
 pub struct SSHShell {
   pub ssh_session_id: SSHSessionId,
-  #[allow(unused)]
+  /// The session whose connection carries the channel, another one when the
+  /// connection is shared
+  pub connection_id: SSHSessionId,
   pub ssh_shell_id: SSHShellId,
-  pub ipc_channel: Channel<SHHShellIpcChannelData>,
-  pub shell_channel: RusshChannel<client::Msg>,
+  /// Everything sent to the frontend goes through here to keep it in order
+  pub output: Arc<SSHShellOutput>,
+  pub shell_channel: ChannelWriteHalf<client::Msg>,
+  /// Taken by `shell_read` once the shell is requested
+  reader: Mutex<Option<ChannelReadHalf>>,
+  pub request: SSHShellRequest,
+  pub size: Mutex<ShellSize>,
+  pub recorder: Mutex<Option<SSHShellRecorder>>,
+  /// Output is copied here while a startup script is running
+  pub script_output: Mutex<Option<UnboundedSender<Vec<u8>>>>,
+  /// Cleared once a reopened shell takes the output over
+  owns_output: AtomicBool,
 }
 
 impl SSHShell {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     ssh_session_id: SSHSessionId,
+    connection_id: SSHSessionId,
     ssh_shell_id: SSHShellId,
     ipc_channel: Channel<SHHShellIpcChannelData>,
     shell_channel: RusshChannel<client::Msg>,
+    request: SSHShellRequest,
+    size: ShellSize,
+    flow_control: bool,
   ) -> Self {
+    let output = Arc::new(SSHShellOutput::new(flow_control));
+    async_runtime::spawn(output.clone().run(ipc_channel));
+    let (reader, shell_channel) = shell_channel.split();
+
     Self {
       ssh_session_id,
+      connection_id,
       ssh_shell_id,
-      ipc_channel,
+      output,
+      shell_channel,
+      reader: Mutex::new(Some(reader)),
+      request,
+      size: Mutex::new(size),
+      recorder: Mutex::default(),
+      script_output: Mutex::default(),
+      owns_output: AtomicBool::new(true),
+    }
+  }
+
+  /// A shell on `shell_channel` continuing this one, it shares the output but
+  /// only owns it after `take_over`
+  fn reopen(&self, shell_channel: RusshChannel<client::Msg>) -> Self {
+    let (reader, shell_channel) = shell_channel.split();
+    Self {
+      ssh_session_id: self.ssh_session_id,
+      connection_id: self.connection_id,
+      ssh_shell_id: self.ssh_shell_id,
+      output: self.output.clone(),
       shell_channel,
+      reader: Mutex::new(Some(reader)),
+      request: self.request.clone(),
+      size: Mutex::new(self.size()),
+      recorder: Mutex::default(),
+      script_output: Mutex::default(),
+      owns_output: AtomicBool::new(false),
+    }
+  }
+
+  /// Takes the output and the recording over from the shell this one reopened
+  fn take_over(&self, shell: &SSHShell) {
+    *self.recorder.lock().unwrap_or_else(PoisonError::into_inner) = shell.take_recorder();
+    shell.owns_output.store(false, Ordering::SeqCst);
+    self.owns_output.store(true, Ordering::SeqCst);
+  }
+
+  pub fn set_script_output(&self, script_output: Option<UnboundedSender<Vec<u8>>>) {
+    *self
+      .script_output
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner) = script_output;
+  }
+
+  /// Passes output on to the running startup script, if any
+  pub fn script_output(&self, data: &[u8]) {
+    let mut script_output = self
+      .script_output
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+    if let Some(sender) = script_output.as_ref()
+      && sender.send(data.to_vec()).is_err()
+    {
+      *script_output = None;
+    }
+  }
+
+  pub fn size(&self) -> ShellSize {
+    self
+      .size
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .clone()
+  }
+
+  pub fn set_size(&self, size: ShellSize) {
+    *self.size.lock().unwrap_or_else(PoisonError::into_inner) = size;
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self
+      .recorder
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .is_some()
+  }
+
+  pub fn recording_id(&self) -> Option<SSHShellRecordingId> {
+    self
+      .recorder
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .as_ref()
+      .map(|recorder| recorder.id())
+  }
+
+  /// Starts recording unless a recording is already running
+  pub fn set_recorder(&self, recorder: SSHShellRecorder) -> SSHResult<SSHShellRecordingId> {
+    let mut current = self.recorder.lock().unwrap_or_else(PoisonError::into_inner);
+    if current.is_some() {
+      return Err(SSHError::new("Shell is already being recorded"));
+    }
+
+    let recording_id = recorder.id();
+    *current = Some(recorder);
+
+    Ok(recording_id)
+  }
+
+  pub fn take_recorder(&self) -> Option<SSHShellRecorder> {
+    self
+      .recorder
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .take()
+  }
+
+  /// Writes to the recording if there is one, failures are logged so that
+  /// they never interrupt the shell
+  pub fn record<F: FnOnce(&mut SSHShellRecorder) -> SSHResult<()>>(&self, f: F) {
+    let mut recorder = self.recorder.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(recorder) = recorder.as_mut()
+      && let Err(err) = f(recorder)
+    {
+      log::error!("shell recording {:?} failed: {}", recorder.id(), err);
+    }
+  }
+}
+
+impl Drop for SSHShell {
+  fn drop(&mut self) {
+    if self.owns_output.load(Ordering::SeqCst) {
+      self.output.shutdown();
     }
   }
 }
 
 impl Deref for SSHShell {
-  type Target = RusshChannel<client::Msg>;
+  type Target = ChannelWriteHalf<client::Msg>;
 
   fn deref(&self) -> &Self::Target {
     &self.shell_channel
@@ -94,9 +320,253 @@ fn prepare_envs(custom_envs: HashMap<String, String>) -> HashMap<String, String>
   envs
 }
 
+/// Sets the environment, requests the PTY and the forwardings of `shell`
+async fn request_pty<R: Runtime>(ssh_manager: &SSHManager<R>, shell: &SSHShell) -> SSHResult<()> {
+  let ssh_session_id = shell.ssh_session_id;
+  let ssh_shell_id = shell.ssh_shell_id;
+  let SSHShellRequest {
+    term,
+    envs,
+    agent_forwarding,
+    x11_forwarding,
+  } = &shell.request;
+
+  log::info!(
+    "shell open {:?} {:?} set env {:?}",
+    ssh_session_id,
+    ssh_shell_id,
+    envs
+  );
+  for (key, value) in envs {
+    shell.set_env(true, key.as_str(), value.as_str()).await?;
+  }
+
+  let size = shell.size();
+  log::info!(
+    "shell open {:?} {:?} request pty {} {:?}",
+    ssh_session_id,
+    ssh_shell_id,
+    term,
+    size
+  );
+  shell
+    .request_pty(true, term, size.col, size.row, size.width, size.height, &[])
+    .await?;
+
+  if let Some(agent_forwarding) = agent_forwarding {
+    log::info!(
+      "shell open {:?} {:?} request agent forwarding",
+      ssh_session_id,
+      ssh_shell_id
+    );
+    // The server opens forwarded channels on the connection, not the session
+    if !ssh_manager
+      .agent_forwarders
+      .contains_key(&shell.connection_id)
+    {
+      let agent_forwarder = SSHAgentForwarder::new(agent_forwarding.clone()).await?;
+      ssh_manager
+        .agent_forwarders
+        .insert(shell.connection_id, Arc::new(agent_forwarder));
+    }
+    shell.agent_forward(true).await?;
+  }
+
+  if let Some(x11_forwarding) = x11_forwarding {
+    log::info!(
+      "shell open {:?} {:?} request x11 forwarding",
+      ssh_session_id,
+      ssh_shell_id
+    );
+    let x11_forwarder = match ssh_manager.x11_forwarders.get(&shell.connection_id) {
+      Some(x11_forwarder) => x11_forwarder,
+      None => {
+        let x11_forwarder = Arc::new(SSHX11Forwarder::new(x11_forwarding.clone()).await?);
+        ssh_manager
+          .x11_forwarders
+          .insert(shell.connection_id, x11_forwarder.clone());
+        x11_forwarder
+      }
+    };
+    shell
+      .request_x11(
+        true,
+        false,
+        x11_forwarder.auth_protocol(),
+        x11_forwarder.auth_cookie(),
+        x11_forwarder.screen(),
+      )
+      .await?;
+  }
+
+  Ok(())
+}
+
+/// Registers `shell`, requests the shell itself and starts reading it
+async fn request_shell<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  shell: Arc<SSHShell>,
+) -> SSHResult<()> {
+  let ssh_manager = app_handle.state::<SSHManager<R>>();
+  let ssh_session_id = shell.ssh_session_id;
+  let ssh_shell_id = shell.ssh_shell_id;
+  ssh_manager.channel_opened(ssh_session_id, shell.id(), SSHChannelKind::Shell);
+
+  // Registered before the shell is requested so that no output, such as
+  // the first prompt a startup script waits for, is missed
+  ssh_manager
+    .shell_sinks
+    .insert((shell.connection_id, shell.id()), shell.clone());
+  ssh_manager.shells.insert(ssh_shell_id, shell.clone());
+
+  log::info!(
+    "shell open {:?} {:?} request shell",
+    ssh_session_id,
+    ssh_shell_id
+  );
+  if let Err(err) = shell.request_shell(true).await {
+    ssh_manager
+      .shell_sinks
+      .remove(&(shell.connection_id, shell.id()));
+    ssh_manager.shells.remove(&ssh_shell_id);
+    return Err(err.into());
+  }
+
+  let reader = shell
+    .reader
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .take();
+  if let Some(reader) = reader {
+    async_runtime::spawn(shell_read(
+      app_handle.clone(),
+      shell.connection_id,
+      shell.id(),
+      reader,
+    ));
+  }
+
+  Ok(())
+}
+
+/// Reads the channel of a shell outside the connection handler. While the
+/// consumer is behind, `shell_channel_data` waits for room in the output of
+/// this shell and its messages stay in the buffer of this channel, the other
+/// channels of the connection keep going.
+async fn shell_read<R: Runtime>(
+  app_handle: AppHandle<R>,
+  connection_id: SSHSessionId,
+  channel_id: ChannelId,
+  mut reader: ChannelReadHalf,
+) {
+  loop {
+    let msg = reader.wait().await;
+    let ssh_manager = app_handle.state::<SSHManager<R>>();
+    let result = match msg {
+      Some(ChannelMsg::Data { data }) => {
+        ssh_manager
+          .shell_channel_data(connection_id, channel_id, &data)
+          .await
+      }
+      Some(ChannelMsg::ExtendedData { data, ext }) => {
+        ssh_manager
+          .shell_channel_extended_data(connection_id, channel_id, ext, &data)
+          .await
+      }
+      Some(ChannelMsg::ExitStatus { exit_status }) => {
+        ssh_manager
+          .shell_channel_exit_status(connection_id, channel_id, exit_status)
+          .await
+      }
+      Some(ChannelMsg::ExitSignal {
+        signal_name: signal,
+        core_dumped,
+        error_message,
+        ..
+      }) => {
+        ssh_manager
+          .shell_channel_exit_signal(
+            connection_id,
+            channel_id,
+            signal_name(signal),
+            core_dumped,
+            error_message,
+          )
+          .await
+      }
+      Some(ChannelMsg::Eof) => {
+        ssh_manager
+          .shell_channel_eof(connection_id, channel_id)
+          .await
+      }
+      Some(ChannelMsg::Close) => {
+        if let Err(err) = ssh_manager
+          .shell_channel_close(connection_id, channel_id)
+          .await
+        {
+          log::error!("shell channel {} close failed: {}", channel_id, err);
+        }
+        return;
+      }
+      // The connection is gone, its shells are closed or reopened with it
+      None => return,
+      Some(_) => Ok(true),
+    };
+
+    if let Err(err) = result {
+      log::error!("shell channel {} read failed: {}", channel_id, err);
+    }
+  }
+}
+
+/// Opens `shell` again on its reconnected session with the same request and
+/// size. The old channel must already be removed from the shell sinks, since
+/// the new session may hand out the same channel id.
+pub async fn shell_reopen<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  shell: Arc<SSHShell>,
+) -> SSHResult<()> {
+  let ssh_manager = app_handle.state::<SSHManager<R>>();
+  let ssh_session_id = shell.ssh_session_id;
+  let ssh_shell_id = shell.ssh_shell_id;
+
+  let reopened = timeout(Duration::from_secs(5), async {
+    log::info!("shell reopen {:?} {:?}", ssh_session_id, ssh_shell_id);
+    let session = ssh_manager
+      .sessions
+      .get(&ssh_session_id)
+      .ok_or(SSHError::NotFoundSession)?;
+    let shell_channel = session.handle().await.channel_open_session().await?;
+    let reopened = Arc::new(shell.reopen(shell_channel));
+
+    request_pty(&ssh_manager, &reopened).await?;
+    // Sent ahead of the output of the new shell
+    shell.output.send(SHHShellIpcChannelData::Reconnected);
+    request_shell(app_handle, reopened.clone()).await?;
+    reopened.take_over(&shell);
+
+    Ok::<(), SSHError>(())
+  })
+  .await
+  .map_err(SSHError::from)
+  .and_then(|reopened| reopened);
+
+  if reopened.is_err() {
+    ssh_manager.shells.remove(&ssh_shell_id);
+    if let Some(recorder) = shell.take_recorder()
+      && let Err(err) = recorder.finish()
+    {
+      log::error!("shell recording finish failed: {}", err);
+    }
+    shell.output.send(SHHShellIpcChannelData::Close);
+  }
+
+  reopened
+}
+
 #[tauri::command]
 pub async fn shell_open<R: Runtime>(
-  _app_handle: AppHandle<R>,
+  app_handle: AppHandle<R>,
   ssh_manager: State<'_, SSHManager<R>>,
   ssh_session_id: SSHSessionId,
   ssh_shell_id: SSHShellId,
@@ -104,61 +574,70 @@ pub async fn shell_open<R: Runtime>(
   term: Option<String>,
   envs: Option<HashMap<String, String>>,
   size: ShellSize,
+  agent_forwarding: Option<AgentForwardingData>,
+  x11_forwarding: Option<X11ForwardingData>,
+  recording: Option<SSHShellRecordingOpts>,
+  flow_control: Option<bool>,
+  startup_script: Option<Vec<SSHShellStartupStep>>,
 ) -> SSHResult<SSHShellId> {
   timeout(Duration::from_secs(5), async {
     log::info!("shell open {:?} {:?}", ssh_session_id, ssh_shell_id);
+    let term = term.unwrap_or("xterm-256color".to_string());
+    let startup_script = startup_script
+      .filter(|steps| !steps.is_empty())
+      .map(SSHShellStartupScript::new)
+      .transpose()?;
     let shell = {
-      let sessions = ssh_manager.sessions.lock().await;
-      let session = sessions
+      let session = ssh_manager
+        .sessions
         .get(&ssh_session_id)
         .ok_or(SSHError::NotFoundSession)?;
-      let shell_channel = session.channel_open_session().await?;
+      let shell_channel = session.handle().await.channel_open_session().await?;
 
-      SSHShell::new(ssh_session_id, ssh_shell_id, ipc_channel, shell_channel)
+      SSHShell::new(
+        ssh_session_id,
+        session.connection_id,
+        ssh_shell_id,
+        ipc_channel,
+        shell_channel,
+        SSHShellRequest {
+          term,
+          envs: prepare_envs(envs.unwrap_or_default()),
+          agent_forwarding,
+          x11_forwarding,
+        },
+        size,
+        flow_control.unwrap_or_default(),
+      )
     };
 
-    let envs = prepare_envs(envs.unwrap_or_default());
+    request_pty(&ssh_manager, &shell).await?;
 
-    log::info!(
-      "shell open {:?} {:?} set env {:?}",
-      ssh_session_id,
-      ssh_shell_id,
-      envs
-    );
-    for (key, value) in envs {
-      shell.set_env(true, key.as_str(), value.as_str()).await?;
+    if let Some(recording) = recording {
+      let recordings_dir = get_recordings_dir(&app_handle)?;
+      shell.set_recorder(SSHShellRecorder::create(
+        &recordings_dir,
+        &shell.request.term,
+        &shell.size(),
+        recording,
+      )?)?;
     }
 
-    let term = term.unwrap_or("xterm-256color".to_string());
-    log::info!(
-      "shell open {:?} {:?} request pty {} {:?}",
-      ssh_session_id,
-      ssh_shell_id,
-      term,
-      size
-    );
-    shell
-      .request_pty(
-        true,
-        &term,
-        size.col,
-        size.row,
-        size.width,
-        size.height,
-        &[],
-      )
-      .await?;
+    let script_output = startup_script.as_ref().map(|_| {
+      let (sender, receiver) = mpsc::unbounded_channel();
+      shell.set_script_output(Some(sender));
+      receiver
+    });
 
-    log::info!(
-      "shell open {:?} {:?} request shell",
-      ssh_session_id,
-      ssh_shell_id
-    );
-    shell.request_shell(true).await?;
+    request_shell(&app_handle, Arc::new(shell)).await?;
 
-    {
-      let mut shells = ssh_manager.shells.lock().await;
-      shells.insert(ssh_shell_id, shell);
+    if let (Some(startup_script), Some(script_output)) = (startup_script, script_output) {
+      log::info!(
+        "shell open {:?} {:?} run startup script",
+        ssh_session_id,
+        ssh_shell_id
+      );
+      async_runtime::spawn(startup_script.run(app_handle.clone(), ssh_shell_id, script_output));
     }
 
     Ok(ssh_shell_id)
@@ -173,9 +652,9 @@ pub async fn shell_close<R: Runtime>(
   ssh_shell_id: SSHShellId,
 ) -> SSHResult<SSHShellId> {
   timeout(Duration::from_secs(5), async {
-    let shell_channels = ssh_manager.shells.lock().await;
-    if let Some(shell_channel) = shell_channels.get(&ssh_shell_id) {
-      shell_channel.close().await?;
+    if let Some(shell) = ssh_manager.shells.get(&ssh_shell_id) {
+      shell.output.closing();
+      shell.close().await?;
     }
 
     Ok(ssh_shell_id)
@@ -191,12 +670,12 @@ pub async fn shell_resize<R: Runtime>(
   size: ShellSize,
 ) -> SSHResult<SSHShellId> {
   timeout(Duration::from_secs(5), async {
-    let shells = ssh_manager.shells.lock().await;
-
-    if let Some(shell) = shells.get(&ssh_shell_id) {
+    if let Some(shell) = ssh_manager.shells.get(&ssh_shell_id) {
       shell
         .window_change(size.col, size.row, size.width, size.height)
         .await?;
+      shell.record(|recorder| recorder.resize(&size));
+      shell.set_size(size);
     }
 
     Ok(ssh_shell_id)
@@ -212,12 +691,71 @@ pub async fn shell_send<R: Runtime>(
   data: String,
 ) -> SSHResult<SSHShellId> {
   timeout(Duration::from_secs(5), async {
-    let shell_channels = ssh_manager.shells.lock().await;
-    if let Some(shell_channel) = shell_channels.get(&ssh_shell_id) {
-      shell_channel.data(data.as_bytes()).await?;
+    if let Some(shell) = ssh_manager.shells.get(&ssh_shell_id) {
+      shell.data(data.as_bytes()).await?;
+      shell.record(|recorder| recorder.input(&data));
+    }
+
+    Ok(ssh_shell_id)
+  })
+  .await?
+}
+
+/// Sends a signal such as `INT` to the remote process, for when the PTY does
+/// not turn Ctrl-C into one. Many servers, OpenSSH before 7.9 among them,
+/// ignore signal requests.
+#[tauri::command]
+pub async fn shell_signal<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_shell_id: SSHShellId,
+  signal: String,
+) -> SSHResult<SSHShellId> {
+  timeout(Duration::from_secs(5), async {
+    if let Some(shell) = ssh_manager.shells.get(&ssh_shell_id) {
+      log::info!("shell {:?} signal {}", ssh_shell_id, signal);
+      shell.signal(parse_signal(&signal)).await?;
     }
 
     Ok(ssh_shell_id)
   })
   .await?
 }
+
+/// Would send the RFC 4335 `break` request. russh has no client API for it
+/// nor for raw channel requests, so this fails with `Unsupported` instead of
+/// silently doing nothing.
+#[tauri::command]
+pub async fn shell_break<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_shell_id: SSHShellId,
+  millis: Option<u32>,
+) -> SSHResult<SSHShellId> {
+  if !ssh_manager.shells.contains_key(&ssh_shell_id) {
+    return Err(SSHError::NotFoundShell);
+  }
+
+  log::warn!(
+    "shell {:?} break of {}ms is not supported",
+    ssh_shell_id,
+    millis.unwrap_or_default()
+  );
+  Err(SSHError::Unsupported("Sending break".to_string()))
+}
+
+/// Acknowledges `bytes` of output the frontend has processed, only used by
+/// shells opened with flow control
+#[tauri::command]
+pub async fn shell_output_ack<R: Runtime>(
+  _app_handle: AppHandle<R>,
+  ssh_manager: State<'_, SSHManager<R>>,
+  ssh_shell_id: SSHShellId,
+  bytes: usize,
+) -> SSHResult<SSHShellId> {
+  if let Some(shell) = ssh_manager.shells.get(&ssh_shell_id) {
+    shell.output.ack(bytes);
+  }
+
+  Ok(ssh_shell_id)
+}
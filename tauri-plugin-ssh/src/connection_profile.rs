@@ -0,0 +1,132 @@
+use std::{borrow::Cow, str::FromStr, time::Duration};
+
+use russh::{Limits, Preferred, cipher, client, compression, kex, keys::Algorithm, mac};
+use serde::Deserialize;
+
+use crate::error::{SSHError, SSHResult};
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_INACTIVITY_TIMEOUT_SECS: u64 = 30 * 60;
+const DEFAULT_KEEPALIVE_INTERVAL_SECS: u64 = 5;
+const DEFAULT_KEEPALIVE_MAX: usize = 3;
+const DEFAULT_REKEY_LIMIT_BYTES: u64 = 1 << 30;
+const DEFAULT_REKEY_TIME_LIMIT_SECS: u64 = 60 * 60;
+
+/// Transport parameters of a host, unset fields keep the defaults. An
+/// algorithm list is both the order of preference and the only algorithms
+/// offered, so legacy ones such as `diffie-hellman-group1-sha1`, `ssh-rsa` or
+/// `aes128-cbc` are enabled by listing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHConnectionProfile {
+  /// Covers connecting and the key exchange
+  pub connect_timeout_secs: Option<u64>,
+  /// `0` never closes an idle session
+  pub inactivity_timeout_secs: Option<u64>,
+  /// `0` sends no keepalives
+  pub keepalive_interval_secs: Option<u64>,
+  /// Unanswered keepalives before the session is considered dead
+  pub keepalive_max: Option<usize>,
+  pub compression: Option<bool>,
+  pub kex_algorithms: Option<Vec<String>>,
+  pub host_key_algorithms: Option<Vec<String>>,
+  pub ciphers: Option<Vec<String>>,
+  pub macs: Option<Vec<String>>,
+  /// Bytes sent or received before the keys are renewed
+  pub rekey_limit_bytes: Option<u64>,
+  pub rekey_time_limit_secs: Option<u64>,
+}
+
+impl SSHConnectionProfile {
+  pub fn connect_timeout(&self) -> Duration {
+    Duration::from_secs(
+      self
+        .connect_timeout_secs
+        .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+    )
+  }
+
+  pub fn client_config(&self) -> SSHResult<client::Config> {
+    let inactivity_timeout_secs = self
+      .inactivity_timeout_secs
+      .unwrap_or(DEFAULT_INACTIVITY_TIMEOUT_SECS);
+    let keepalive_interval_secs = self
+      .keepalive_interval_secs
+      .unwrap_or(DEFAULT_KEEPALIVE_INTERVAL_SECS);
+    let rekey_limit_bytes =
+      usize::try_from(self.rekey_limit_bytes.unwrap_or(DEFAULT_REKEY_LIMIT_BYTES))
+        .unwrap_or(usize::MAX);
+
+    Ok(client::Config {
+      inactivity_timeout: (inactivity_timeout_secs > 0)
+        .then(|| Duration::from_secs(inactivity_timeout_secs)),
+      keepalive_interval: (keepalive_interval_secs > 0)
+        .then(|| Duration::from_secs(keepalive_interval_secs)),
+      keepalive_max: self.keepalive_max.unwrap_or(DEFAULT_KEEPALIVE_MAX),
+      window_size: 1 << 25, // 32 MB
+      maximum_packet_size: 65536,
+      channel_buffer_size: 1048576,
+      nodelay: true,
+      limits: Limits::new(
+        rekey_limit_bytes,
+        rekey_limit_bytes,
+        Duration::from_secs(
+          self
+            .rekey_time_limit_secs
+            .unwrap_or(DEFAULT_REKEY_TIME_LIMIT_SECS),
+        ),
+      ),
+      preferred: self.preferred()?,
+      ..client::Config::default()
+    })
+  }
+
+  fn preferred(&self) -> SSHResult<Preferred> {
+    let default = Preferred::default();
+
+    Ok(Preferred {
+      kex: names(&self.kex_algorithms, "Key exchange", |name| {
+        kex::Name::try_from(name).ok()
+      })?
+      .unwrap_or(default.kex),
+      key: names(&self.host_key_algorithms, "Host key algorithm", |name| {
+        Algorithm::from_str(name).ok()
+      })?
+      .unwrap_or(default.key),
+      cipher: names(&self.ciphers, "Cipher", |name| {
+        cipher::Name::try_from(name).ok()
+      })?
+      .unwrap_or(default.cipher),
+      mac: names(&self.macs, "MAC", |name| mac::Name::try_from(name).ok())?.unwrap_or(default.mac),
+      compression: match self.compression {
+        Some(true) => Cow::Borrowed(&[
+          compression::ZLIB_LEGACY,
+          compression::ZLIB,
+          compression::NONE,
+        ]),
+        Some(false) => Cow::Borrowed(&[compression::NONE]),
+        None => default.compression,
+      },
+    })
+  }
+}
+
+/// Parses an algorithm list, `None` when the list is not set or empty
+fn names<T: Clone>(
+  names: &Option<Vec<String>>,
+  kind: &str,
+  parse: impl Fn(&str) -> Option<T>,
+) -> SSHResult<Option<Cow<'static, [T]>>> {
+  let Some(names) = names.as_ref().filter(|names| !names.is_empty()) else {
+    return Ok(None);
+  };
+
+  names
+    .iter()
+    .map(|name| {
+      let name = name.trim();
+      parse(name).ok_or_else(|| SSHError::Unsupported(format!("{} {}", kind, name)))
+    })
+    .collect::<SSHResult<Vec<T>>>()
+    .map(|names| Some(Cow::Owned(names)))
+}
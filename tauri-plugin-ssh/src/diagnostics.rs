@@ -0,0 +1,414 @@
+use std::{
+  fmt::Display,
+  io,
+  net::SocketAddr,
+  pin::Pin,
+  sync::{
+    Arc, Mutex, MutexGuard, PoisonError,
+    atomic::{AtomicU64, Ordering},
+  },
+  task::{Context, Poll},
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tauri::ipc::Channel;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::commands::session::SessionIpcChannelData;
+
+/// Bytes looked at for the identification string and the first KEXINIT, the
+/// rest of the connection is encrypted and only counted
+const SNIFF_BYTES: usize = 64 * 1024;
+const SSH_MSG_KEXINIT: u8 = 20;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum SSHSessionStage {
+  Dns,
+  Tcp,
+  UpstreamProxy,
+  ProxyCommand,
+  /// Opening the direct-tcpip channel on the jump host
+  JumpHost,
+  /// Identification exchange and key exchange
+  Handshake,
+  HostKey,
+  Authentication,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHSessionStageTiming {
+  pub stage: SSHSessionStage,
+  pub elapsed_millis: u64,
+  pub error: Option<String>,
+}
+
+/// Algorithms picked from both KEXINIT messages after RFC 4253 section 7.1,
+/// the MAC is `None` for AEAD ciphers
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHSessionAlgorithms {
+  pub kex: Option<String>,
+  pub host_key: Option<String>,
+  pub cipher_client_to_server: Option<String>,
+  pub cipher_server_to_client: Option<String>,
+  pub mac_client_to_server: Option<String>,
+  pub mac_server_to_client: Option<String>,
+  pub compression_client_to_server: Option<String>,
+  pub compression_server_to_client: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHSessionInfo {
+  pub hostname: String,
+  pub port: u16,
+  pub client_identification: Option<String>,
+  pub server_identification: Option<String>,
+  pub algorithms: SSHSessionAlgorithms,
+  /// The algorithm of the key the server presented
+  pub host_key_algorithm: Option<String>,
+  /// Only known for direct TCP connections
+  pub local_addr: Option<String>,
+  pub remote_addr: Option<String>,
+  /// `hostname:port` of the jump hosts, first hop first
+  pub jump_path: Vec<String>,
+  /// Unix time in milliseconds
+  pub connected_at: Option<u64>,
+  pub bytes_sent: u64,
+  pub bytes_received: u64,
+  pub rtt_millis: Option<u64>,
+  pub stages: Vec<SSHSessionStageTiming>,
+}
+
+/// Picks up the identification string and the KEXINIT payload of one
+/// direction of the connection
+#[derive(Default)]
+struct Sniffer {
+  buffer: Vec<u8>,
+  identification: Option<String>,
+  name_lists: Option<Vec<Vec<String>>>,
+  done: bool,
+}
+
+impl Sniffer {
+  fn feed(&mut self, data: &[u8]) {
+    if self.done {
+      return;
+    }
+    self.buffer.extend_from_slice(data);
+
+    // Servers may send other lines before the identification string
+    while self.identification.is_none() {
+      let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') else {
+        if self.buffer.len() > SNIFF_BYTES {
+          self.stop();
+        }
+        return;
+      };
+      let line = self.buffer.drain(..=end).collect::<Vec<u8>>();
+      let line = String::from_utf8_lossy(&line);
+      if line.starts_with("SSH-") {
+        self.identification = Some(line.trim_end().to_string());
+      }
+    }
+
+    if self.buffer.len() < 5 {
+      return;
+    }
+    let packet_length = u32::from_be_bytes([
+      self.buffer[0],
+      self.buffer[1],
+      self.buffer[2],
+      self.buffer[3],
+    ]) as usize;
+    if packet_length > SNIFF_BYTES {
+      self.stop();
+      return;
+    }
+    if self.buffer.len() < 4 + packet_length {
+      return;
+    }
+
+    let padding_length = self.buffer[4] as usize;
+    self.name_lists = self
+      .buffer
+      .get(5..(4 + packet_length).saturating_sub(padding_length))
+      .and_then(parse_kexinit);
+    self.stop();
+  }
+
+  fn stop(&mut self) {
+    self.done = true;
+    self.buffer = Vec::new();
+  }
+}
+
+/// The ten name-lists of a KEXINIT payload
+fn parse_kexinit(payload: &[u8]) -> Option<Vec<Vec<String>>> {
+  if payload.first() != Some(&SSH_MSG_KEXINIT) {
+    return None;
+  }
+
+  // Message number and cookie
+  let mut rest = payload.get(17..)?;
+  let mut name_lists = Vec::with_capacity(10);
+  for _ in 0..10 {
+    let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let name_list = rest.get(4..4 + length)?;
+    rest = &rest[4 + length..];
+    name_lists.push(
+      String::from_utf8_lossy(name_list)
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .collect(),
+    );
+  }
+
+  Some(name_lists)
+}
+
+fn negotiate(client: &[Vec<String>], server: &[Vec<String>], index: usize) -> Option<String> {
+  let server = server.get(index)?;
+  client
+    .get(index)?
+    .iter()
+    .find(|name| server.contains(name))
+    .cloned()
+}
+
+fn is_aead(cipher: &Option<String>) -> bool {
+  cipher
+    .as_deref()
+    .is_some_and(|cipher| cipher.contains("poly1305") || cipher.contains("gcm"))
+}
+
+#[derive(Default)]
+struct SSHSessionDiagnosticsState {
+  client: Sniffer,
+  server: Sniffer,
+  host_key_algorithm: Option<String>,
+  local_addr: Option<SocketAddr>,
+  remote_addr: Option<SocketAddr>,
+  connected_at: Option<u64>,
+  rtt: Option<Duration>,
+  stages: Vec<SSHSessionStageTiming>,
+}
+
+/// What is known about how a session was established, shared by its stream,
+/// its handler and the session itself
+pub struct SSHSessionDiagnostics {
+  hostname: String,
+  port: u16,
+  /// Reports every stage to the frontend as it finishes
+  diagnose: bool,
+  ipc_channel: Channel<SessionIpcChannelData>,
+  bytes_sent: AtomicU64,
+  bytes_received: AtomicU64,
+  state: Mutex<SSHSessionDiagnosticsState>,
+}
+
+impl SSHSessionDiagnostics {
+  pub fn new(
+    hostname: &str,
+    port: u16,
+    diagnose: bool,
+    ipc_channel: Channel<SessionIpcChannelData>,
+  ) -> Arc<Self> {
+    Arc::new(Self {
+      hostname: hostname.to_string(),
+      port,
+      diagnose,
+      ipc_channel,
+      bytes_sent: AtomicU64::new(0),
+      bytes_received: AtomicU64::new(0),
+      state: Mutex::new(SSHSessionDiagnosticsState::default()),
+    })
+  }
+
+  fn state(&self) -> MutexGuard<'_, SSHSessionDiagnosticsState> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  pub fn address(&self) -> String {
+    format!("{}:{}", self.hostname, self.port)
+  }
+
+  fn sent(&self, data: &[u8]) {
+    self
+      .bytes_sent
+      .fetch_add(data.len() as u64, Ordering::Relaxed);
+    let mut state = self.state();
+    if !state.client.done {
+      state.client.feed(data);
+    }
+  }
+
+  fn received(&self, data: &[u8]) {
+    self
+      .bytes_received
+      .fetch_add(data.len() as u64, Ordering::Relaxed);
+    let mut state = self.state();
+    if state.server.done {
+      return;
+    }
+    let identified = state.server.identification.is_some();
+    state.server.feed(data);
+    let identification = state.server.identification.clone().filter(|_| !identified);
+    drop(state);
+
+    if let Some(identification) = identification {
+      let _ = self.ipc_channel.send(SessionIpcChannelData::ServerBanner {
+        hostname: self.hostname.clone(),
+        port: self.port,
+        identification,
+      });
+    }
+  }
+
+  pub fn set_addrs(&self, local_addr: Option<SocketAddr>, remote_addr: Option<SocketAddr>) {
+    let mut state = self.state();
+    state.local_addr = local_addr;
+    state.remote_addr = remote_addr;
+  }
+
+  pub fn set_host_key_algorithm(&self, host_key_algorithm: String) {
+    self.state().host_key_algorithm = Some(host_key_algorithm);
+  }
+
+  pub fn set_connected(&self) {
+    self.state().connected_at = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .ok()
+      .map(|duration| duration.as_millis() as u64);
+  }
+
+  pub fn set_rtt(&self, rtt: Duration) {
+    self.state().rtt = Some(rtt);
+  }
+
+  /// Records a stage that began at `started`, a failed stage is where the
+  /// connection went wrong
+  pub fn stage<T, E: Display>(
+    &self,
+    stage: SSHSessionStage,
+    started: Instant,
+    result: &Result<T, E>,
+  ) {
+    let timing = SSHSessionStageTiming {
+      stage,
+      elapsed_millis: started.elapsed().as_millis() as u64,
+      error: result.as_ref().err().map(ToString::to_string),
+    };
+    log::info!(
+      "session {} stage {:?} took {}ms",
+      self.address(),
+      timing.stage,
+      timing.elapsed_millis
+    );
+
+    if self.diagnose {
+      let _ = self.ipc_channel.send(SessionIpcChannelData::DiagnoseStage {
+        hostname: self.hostname.clone(),
+        port: self.port,
+        timing: timing.clone(),
+      });
+    }
+    self.state().stages.push(timing);
+  }
+
+  pub fn info(&self, jump_path: Vec<String>) -> SSHSessionInfo {
+    let state = self.state();
+
+    let algorithms = match (&state.client.name_lists, &state.server.name_lists) {
+      (Some(client), Some(server)) => {
+        let cipher_client_to_server = negotiate(client, server, 2);
+        let cipher_server_to_client = negotiate(client, server, 3);
+        SSHSessionAlgorithms {
+          kex: negotiate(client, server, 0),
+          host_key: negotiate(client, server, 1),
+          mac_client_to_server: (!is_aead(&cipher_client_to_server))
+            .then(|| negotiate(client, server, 4))
+            .flatten(),
+          mac_server_to_client: (!is_aead(&cipher_server_to_client))
+            .then(|| negotiate(client, server, 5))
+            .flatten(),
+          cipher_client_to_server,
+          cipher_server_to_client,
+          compression_client_to_server: negotiate(client, server, 6),
+          compression_server_to_client: negotiate(client, server, 7),
+        }
+      }
+      _ => SSHSessionAlgorithms::default(),
+    };
+
+    SSHSessionInfo {
+      hostname: self.hostname.clone(),
+      port: self.port,
+      client_identification: state.client.identification.clone(),
+      server_identification: state.server.identification.clone(),
+      algorithms,
+      host_key_algorithm: state.host_key_algorithm.clone(),
+      local_addr: state.local_addr.map(|addr| addr.to_string()),
+      remote_addr: state.remote_addr.map(|addr| addr.to_string()),
+      jump_path,
+      connected_at: state.connected_at,
+      bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+      bytes_received: self.bytes_received.load(Ordering::Relaxed),
+      rtt_millis: state.rtt.map(|rtt| rtt.as_millis() as u64),
+      stages: state.stages.clone(),
+    }
+  }
+}
+
+/// Wraps the transport of a session to count its bytes and read the
+/// unencrypted start of the handshake
+pub struct SSHDiagnosticStream<S> {
+  inner: S,
+  diagnostics: Arc<SSHSessionDiagnostics>,
+}
+
+impl<S> SSHDiagnosticStream<S> {
+  pub fn new(inner: S, diagnostics: Arc<SSHSessionDiagnostics>) -> Self {
+    Self { inner, diagnostics }
+  }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for SSHDiagnosticStream<S> {
+  fn poll_read(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<io::Result<()>> {
+    let filled = buf.filled().len();
+    let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+    if let Poll::Ready(Ok(())) = poll {
+      self.diagnostics.received(&buf.filled()[filled..]);
+    }
+    poll
+  }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for SSHDiagnosticStream<S> {
+  fn poll_write(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    data: &[u8],
+  ) -> Poll<io::Result<usize>> {
+    let poll = Pin::new(&mut self.inner).poll_write(cx, data);
+    if let Poll::Ready(Ok(written)) = poll {
+      self.diagnostics.sent(&data[..written]);
+    }
+    poll
+  }
+
+  fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    Pin::new(&mut self.inner).poll_flush(cx)
+  }
+
+  fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    Pin::new(&mut self.inner).poll_shutdown(cx)
+  }
+}
@@ -1,11 +1,13 @@
 use std::sync::{PoisonError, TryLockError};
 
-use russh::{MethodKind, MethodSet, client::Prompt, keys::ssh_key::Fingerprint};
+use russh::{MethodSet, client::Prompt, keys::ssh_key::Fingerprint};
 use serde::{Serialize, Serializer};
 use serde_json::json;
 use strum::AsRefStr;
 use thiserror::Error;
 
+use crate::utils::method_kind_name;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct KeyboardInteractiveData {
   pub name: String,
@@ -48,6 +50,8 @@ pub enum AuthenticationError {
   Certificate(MethodSet, bool),
   #[error("Authentication failed with keyboard interactive")]
   KeyboardInteractive(MethodSet, bool),
+  #[error("Authentication failed with agent")]
+  Agent(MethodSet, bool),
   #[error("Keyboard interactive need response")]
   KeyboardInteractiveInfoRequest(KeyboardInteractiveData),
   #[error("{0}")]
@@ -58,6 +62,19 @@ impl AuthenticationError {
   pub fn new<T: Into<String>>(message: T) -> Self {
     Self::Error(message.into())
   }
+
+  /// The methods the server still requires when the method was accepted but
+  /// is not enough on its own
+  pub fn partial_success(&self) -> Option<&MethodSet> {
+    match self {
+      AuthenticationError::Password(method_set, true)
+      | AuthenticationError::PublicKey(method_set, true)
+      | AuthenticationError::Certificate(method_set, true)
+      | AuthenticationError::KeyboardInteractive(method_set, true)
+      | AuthenticationError::Agent(method_set, true) => Some(method_set),
+      _ => None,
+    }
+  }
 }
 
 impl Serialize for AuthenticationError {
@@ -69,17 +86,12 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
       AuthenticationError::Password(method_set, partial_success)
       | AuthenticationError::PublicKey(method_set, partial_success)
       | AuthenticationError::Certificate(method_set, partial_success)
-      | AuthenticationError::KeyboardInteractive(method_set, partial_success) => json!({
+      | AuthenticationError::KeyboardInteractive(method_set, partial_success)
+      | AuthenticationError::Agent(method_set, partial_success) => json!({
         "type": "AuthenticationError",
         "message": self.to_string(),
         "kind": self.as_ref(),
-        "methodSet": method_set.iter().map(|method_kind| match method_kind {
-            MethodKind::None => "None",
-            MethodKind::Password => "Password",
-            MethodKind::PublicKey => "PublicKey",
-            MethodKind::HostBased => "Certificate",
-            MethodKind::KeyboardInteractive => "KeyboardInteractive"
-        }).collect::<Vec<&str>>(),
+        "methodSet": method_set.iter().map(method_kind_name).collect::<Vec<&str>>(),
         "partialSuccess": partial_success,
       }),
       AuthenticationError::KeyboardInteractiveInfoRequest(keyboard_interactive_data) => json!({
@@ -140,8 +152,22 @@ pub enum SSHError {
   #[error("Failed connect to {0}")]
   ConnectFailed(String),
 
-  #[error("Jump host connect failed")]
-  JumpHostConnectFailed,
+  #[error("Upstream proxy {0} failed: {1}")]
+  UpstreamProxyFailed(String, String),
+
+  #[error("Proxy command failed: {0}")]
+  ProxyCommandFailed(String),
+
+  #[error("Jump host {hop} {hostname}:{port} connect failed: {error}")]
+  JumpHostConnectFailed {
+    hop: usize,
+    hostname: String,
+    port: u16,
+    error: Box<SSHError>,
+  },
+
+  #[error(transparent)]
+  AuthenticationError(#[from] AuthenticationError),
 
   #[error("Session closed")]
   SessionClosed,
@@ -152,6 +178,26 @@ pub enum SSHError {
     fingerprint: Fingerprint,
   },
 
+  #[error(
+    "{} host key has changed from {} to {}",
+    algorithm,
+    old_fingerprint,
+    new_fingerprint
+  )]
+  HostKeyChanged {
+    algorithm: String,
+    old_fingerprint: String,
+    new_fingerprint: Fingerprint,
+    /// Line of the mismatching entry in the known_hosts file
+    line: usize,
+  },
+
+  #[error("{} key {} is revoked", algorithm, fingerprint)]
+  HostKeyRevoked {
+    algorithm: String,
+    fingerprint: Fingerprint,
+  },
+
   #[error("Not found session")]
   NotFoundSession,
 
@@ -161,12 +207,24 @@ pub enum SSHError {
   #[error("Not found sftp")]
   NotFoundSftp,
 
+  #[error("Not found shell")]
+  NotFoundShell,
+
+  #[error("Not found recording")]
+  NotFoundRecording,
+
+  #[error("{0} is not supported")]
+  Unsupported(String),
+
   #[error(transparent)]
   StdStrUtf8Error(#[from] std::str::Utf8Error),
 
   #[error(transparent)]
   UuidError(#[from] uuid::Error),
 
+  #[error(transparent)]
+  RegexError(#[from] regex::Error),
+
   #[error("{0}")]
   Error(String),
 }
@@ -180,12 +238,43 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
       SSHError::UnknownKey {
         algorithm,
         fingerprint,
+      }
+      | SSHError::HostKeyRevoked {
+        algorithm,
+        fingerprint,
       } => json!({
         "type": self.as_ref(),
         "message": self.to_string(),
         "algorithm": algorithm,
         "fingerprint": fingerprint.to_string(),
       }),
+      SSHError::HostKeyChanged {
+        algorithm,
+        old_fingerprint,
+        new_fingerprint,
+        line,
+      } => json!({
+        "type": self.as_ref(),
+        "message": self.to_string(),
+        "algorithm": algorithm,
+        "oldFingerprint": old_fingerprint,
+        "newFingerprint": new_fingerprint.to_string(),
+        "line": line,
+      }),
+      SSHError::JumpHostConnectFailed {
+        hop,
+        hostname,
+        port,
+        error,
+      } => json!({
+        "type": self.as_ref(),
+        "message": self.to_string(),
+        "hop": hop,
+        "hostname": hostname,
+        "port": port,
+        "error": error,
+      }),
+      SSHError::AuthenticationError(error) => json!(error),
       _ => json!({
         "type": self.as_ref(),
         "message": self.to_string(),
@@ -0,0 +1,124 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use russh::client::Prompt;
+use serde::Deserialize;
+
+use crate::{error::AuthenticationError, totp::Totp};
+
+/// Prompts answered with the TOTP code when no rule matches
+static TOTP_PROMPT: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(
+    r"(?i)\b(verification code|one[- ]time (code|password)|otp|token|authenticator|2fa|two[- ]factor)\b",
+  )
+    .expect("valid TOTP prompt pattern")
+});
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum KeyboardInteractiveAnswer {
+  /// The saved password
+  Password,
+  /// A code generated from the TOTP secret
+  Totp,
+  /// The `value` of the rule
+  Fixed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardInteractiveRule {
+  /// Matched against the prompt text
+  pub pattern: String,
+  pub answer: KeyboardInteractiveAnswer,
+  pub value: Option<String>,
+}
+
+/// Answers keyboard-interactive prompts without asking the user.
+///
+/// Rules are tried in order, prompts no rule matches fall back to the TOTP
+/// code for prompts that look like asking for one and, in the first round
+/// only, to the password for a lone hidden prompt that does not.
+#[derive(Default)]
+pub struct KeyboardInteractiveAnswers {
+  password: Option<String>,
+  totp: Option<Totp>,
+  rules: Vec<(Regex, KeyboardInteractiveRule)>,
+}
+
+impl KeyboardInteractiveAnswers {
+  pub fn new(
+    password: Option<String>,
+    totp_secret: Option<String>,
+    rules: Option<Vec<KeyboardInteractiveRule>>,
+  ) -> Result<Self, AuthenticationError> {
+    let totp = totp_secret
+      .filter(|totp_secret| !totp_secret.trim().is_empty())
+      .map(|totp_secret| Totp::parse(&totp_secret))
+      .transpose()
+      .map_err(|err| AuthenticationError::new(err.to_string()))?;
+
+    let rules = rules
+      .unwrap_or_default()
+      .into_iter()
+      .map(|rule| {
+        let regex = Regex::new(&rule.pattern).map_err(|err| {
+          AuthenticationError::new(format!(
+            "Invalid keyboard interactive rule {}: {}",
+            rule.pattern, err
+          ))
+        })?;
+        Ok((regex, rule))
+      })
+      .collect::<Result<Vec<_>, AuthenticationError>>()?;
+
+    Ok(Self {
+      password,
+      totp,
+      rules,
+    })
+  }
+
+  fn answer_rule(&self, rule: &KeyboardInteractiveRule) -> Option<String> {
+    match rule.answer {
+      KeyboardInteractiveAnswer::Password => self.password.clone(),
+      KeyboardInteractiveAnswer::Totp => self.totp.as_ref().map(Totp::now),
+      KeyboardInteractiveAnswer::Fixed => rule.value.clone(),
+    }
+  }
+
+  fn answer(&self, prompt: &Prompt, password_fallback: bool) -> Option<String> {
+    if let Some((_, rule)) = self
+      .rules
+      .iter()
+      .find(|(regex, _)| regex.is_match(&prompt.prompt))
+    {
+      return self.answer_rule(rule);
+    }
+
+    let totp_prompt = TOTP_PROMPT.is_match(&prompt.prompt);
+    if let Some(totp) = &self.totp
+      && totp_prompt
+    {
+      return Some(totp.now());
+    }
+
+    // Later hidden prompts are usually a second factor the password would
+    // only be wasted on
+    if password_fallback && !prompt.echo && !totp_prompt {
+      return self.password.clone();
+    }
+
+    None
+  }
+
+  /// Answers every prompt of an info request, `None` when any of them has to
+  /// be asked from the user. `first_round` tells whether nothing was answered
+  /// yet in this attempt.
+  pub fn answer_all(&self, prompts: &[Prompt], first_round: bool) -> Option<Vec<String>> {
+    let password_fallback = first_round && prompts.len() == 1;
+    prompts
+      .iter()
+      .map(|prompt| self.answer(prompt, password_fallback))
+      .collect()
+  }
+}
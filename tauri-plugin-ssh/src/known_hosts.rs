@@ -0,0 +1,374 @@
+use std::{
+  fs::{self, OpenOptions},
+  io::Write,
+  path::Path,
+};
+
+use data_encoding::BASE64;
+use hmac::{Hmac, Mac};
+use russh::keys::{HashAlg, PublicKey, parse_public_key_base64};
+use serde::Serialize;
+use sha1::Sha1;
+
+use crate::error::SSHResult;
+
+const MARKER_REVOKED: &str = "@revoked";
+const HASH_MAGIC: &str = "|1|";
+
+/// A parsed line of the known_hosts file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsEntry {
+  /// 1-based line number in the known_hosts file
+  pub line: usize,
+  pub marker: Option<String>,
+  pub hosts: Vec<String>,
+  pub algorithm: String,
+  pub fingerprint: String,
+  pub public_key: String,
+  pub comment: Option<String>,
+  #[serde(skip)]
+  key: PublicKey,
+}
+
+impl KnownHostsEntry {
+  fn parse(line: usize, content: &str) -> Option<Self> {
+    let content = content.trim();
+    if content.is_empty() || content.starts_with('#') {
+      return None;
+    }
+
+    let mut fields = content.split_whitespace();
+    let mut hosts = fields.next()?;
+    let marker = if hosts.starts_with('@') {
+      let marker = hosts.to_string();
+      hosts = fields.next()?;
+      Some(marker)
+    } else {
+      None
+    };
+    let algorithm = fields.next()?;
+    let data = fields.next()?;
+    let comment = fields.collect::<Vec<&str>>().join(" ");
+
+    let key = parse_public_key_base64(data).ok()?;
+
+    Some(KnownHostsEntry {
+      line,
+      marker,
+      hosts: hosts.split(',').map(String::from).collect(),
+      algorithm: algorithm.to_string(),
+      fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+      public_key: format!("{} {}", algorithm, data),
+      comment: if comment.is_empty() {
+        None
+      } else {
+        Some(comment)
+      },
+      key,
+    })
+  }
+
+  /// Whether the host patterns of this entry match `hostname:port`, honouring
+  /// `*`/`?` wildcards and `!` negations like OpenSSH
+  pub fn matches(&self, hostname: &str, port: u16) -> bool {
+    let host = host_pattern(hostname, port);
+
+    let mut matched = false;
+    for pattern in &self.hosts {
+      if let Some(pattern) = pattern.strip_prefix('!') {
+        if pattern_match(pattern, &host) {
+          return false;
+        }
+      } else if pattern_match(pattern, &host) {
+        matched = true;
+      }
+    }
+
+    matched
+  }
+
+  /// Whether this entry lists `hostname:port` literally, wildcard patterns
+  /// shared with other hosts do not count
+  pub fn contains(&self, hostname: &str, port: u16) -> bool {
+    let host = host_pattern(hostname, port);
+
+    self.hosts.iter().any(|pattern| {
+      pattern.eq_ignore_ascii_case(&host) || hashed_match(pattern, &host).unwrap_or(false)
+    })
+  }
+
+  pub fn is_revoked(&self) -> bool {
+    self.marker.as_deref() == Some(MARKER_REVOKED)
+  }
+
+  pub fn is_hashed(&self) -> bool {
+    self
+      .hosts
+      .iter()
+      .any(|pattern| pattern.starts_with(HASH_MAGIC))
+  }
+
+  pub fn matches_keyword(&self, keyword: &str) -> bool {
+    let keyword = keyword.to_lowercase();
+
+    self.hosts.iter().any(|host| {
+      host.to_lowercase().contains(&keyword) || hashed_match(host, &keyword).unwrap_or(false)
+    }) || self.algorithm.to_lowercase().contains(&keyword)
+      || self.fingerprint.to_lowercase().contains(&keyword)
+      || self
+        .comment
+        .as_ref()
+        .is_some_and(|comment| comment.to_lowercase().contains(&keyword))
+  }
+}
+
+/// Result of looking up a server key in the known_hosts file
+pub enum KnownHostsCheck {
+  Match,
+  /// The host is known with a different key of the same algorithm
+  Changed(KnownHostsEntry),
+  /// The key is listed in a `@revoked` line
+  Revoked,
+  Unknown,
+}
+
+/// The host field OpenSSH writes for `hostname:port`
+pub fn host_pattern(hostname: &str, port: u16) -> String {
+  if port == 22 {
+    hostname.to_string()
+  } else {
+    format!("[{}]:{}", hostname, port)
+  }
+}
+
+fn hash_host(salt: &[u8], host: &str) -> Vec<u8> {
+  let mut mac = Hmac::<Sha1>::new_from_slice(salt).expect("HMAC can take key of any size");
+  mac.update(host.to_lowercase().as_bytes());
+  mac.finalize().into_bytes().to_vec()
+}
+
+/// Hashes a host like `HashKnownHosts`, `|1|base64(salt)|base64(hmac-sha1(salt, host))`
+fn hashed_host_pattern(host: &str) -> String {
+  let salt: [u8; 20] = rand::random();
+
+  format!(
+    "{}{}|{}",
+    HASH_MAGIC,
+    BASE64.encode(&salt),
+    BASE64.encode(&hash_host(&salt, host))
+  )
+}
+
+/// Returns `None` when `pattern` is not a hashed host
+fn hashed_match(pattern: &str, host: &str) -> Option<bool> {
+  let (salt, hash) = pattern.strip_prefix(HASH_MAGIC)?.split_once('|')?;
+
+  let matched = match (
+    BASE64.decode(salt.as_bytes()),
+    BASE64.decode(hash.as_bytes()),
+  ) {
+    (Ok(salt), Ok(hash)) => hash_host(&salt, host) == hash,
+    _ => false,
+  };
+
+  Some(matched)
+}
+
+fn pattern_match(pattern: &str, host: &str) -> bool {
+  hashed_match(pattern, host).unwrap_or_else(|| wildcard_match(pattern, host))
+}
+
+fn is_literal_pattern(pattern: &str) -> bool {
+  !pattern.starts_with(HASH_MAGIC) && !pattern.contains(['*', '?', '!'])
+}
+
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+  let pattern = pattern.to_lowercase().chars().collect::<Vec<char>>();
+  let value = value.to_lowercase().chars().collect::<Vec<char>>();
+
+  let (mut p, mut v) = (0, 0);
+  let mut backtrack: Option<(usize, usize)> = None;
+  while v < value.len() {
+    if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+      p += 1;
+      v += 1;
+    } else if p < pattern.len() && pattern[p] == '*' {
+      backtrack = Some((p, v));
+      p += 1;
+    } else if let Some((star_p, star_v)) = backtrack {
+      p = star_p + 1;
+      v = star_v + 1;
+      backtrack = Some((star_p, star_v + 1));
+    } else {
+      return false;
+    }
+  }
+
+  pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub fn read_entries<P: AsRef<Path>>(path: P) -> SSHResult<Vec<KnownHostsEntry>> {
+  let content = fs::read_to_string(path)?;
+
+  Ok(
+    content
+      .lines()
+      .enumerate()
+      .filter_map(|(index, line)| KnownHostsEntry::parse(index + 1, line))
+      .collect(),
+  )
+}
+
+/// Looks up a plain server key. `@cert-authority` lines are listed and kept
+/// but never match: russh only negotiates plain host key algorithms, so the
+/// server never presents a certificate to check against them.
+pub fn check<P: AsRef<Path>>(
+  path: P,
+  hostname: &str,
+  port: u16,
+  public_key: &PublicKey,
+) -> SSHResult<KnownHostsCheck> {
+  let entries = read_entries(path)?;
+
+  let revoked = entries.iter().any(|entry| {
+    entry.is_revoked()
+      && entry.key.key_data() == public_key.key_data()
+      && entry.matches(hostname, port)
+  });
+  if revoked {
+    return Ok(KnownHostsCheck::Revoked);
+  }
+
+  let mut changed = None;
+  for entry in entries {
+    if entry.marker.is_some() || !entry.matches(hostname, port) {
+      continue;
+    }
+
+    if entry.key.key_data() == public_key.key_data() {
+      return Ok(KnownHostsCheck::Match);
+    }
+
+    if changed.is_none() && entry.key.algorithm() == public_key.algorithm() {
+      changed = Some(entry);
+    }
+  }
+
+  Ok(match changed {
+    Some(entry) => KnownHostsCheck::Changed(entry),
+    None => KnownHostsCheck::Unknown,
+  })
+}
+
+pub fn learn<P: AsRef<Path>>(
+  path: P,
+  hostname: &str,
+  port: u16,
+  public_key: &PublicKey,
+) -> SSHResult<()> {
+  let path = path.as_ref();
+  let needs_newline = fs::read(path)
+    .map(|content| content.last().is_some_and(|c| *c != b'\n'))
+    .unwrap_or(false);
+
+  // Follow the file, once it holds hashed hosts new hosts are hashed as well
+  let host = host_pattern(hostname, port);
+  let host = if read_entries(path)?.iter().any(|entry| entry.is_hashed()) {
+    hashed_host_pattern(&host)
+  } else {
+    host
+  };
+
+  let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+  if needs_newline {
+    writeln!(file)?;
+  }
+
+  writeln!(
+    file,
+    "{} {}",
+    host,
+    public_key.to_openssh().map_err(russh::keys::Error::from)?
+  )?;
+
+  Ok(())
+}
+
+/// Removes every plain entry of `hostname:port`, like `ssh-keygen -R`, and
+/// returns the number of removed lines. `@cert-authority` and `@revoked`
+/// lines are kept since they are not host specific.
+pub fn remove<P: AsRef<Path>>(path: P, hostname: &str, port: u16) -> SSHResult<usize> {
+  let path = path.as_ref();
+  let content = fs::read_to_string(path)?;
+
+  let mut removed = 0;
+  let mut lines = Vec::new();
+  for (index, line) in content.lines().enumerate() {
+    let is_host_entry = KnownHostsEntry::parse(index + 1, line)
+      .is_some_and(|entry| entry.marker.is_none() && entry.contains(hostname, port));
+
+    if is_host_entry {
+      removed += 1;
+    } else {
+      lines.push(line);
+    }
+  }
+
+  if removed > 0 {
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+      content.push('\n');
+    }
+    fs::write(path, content)?;
+  }
+
+  Ok(removed)
+}
+
+pub fn replace<P: AsRef<Path>>(
+  path: P,
+  hostname: &str,
+  port: u16,
+  public_key: &PublicKey,
+) -> SSHResult<()> {
+  remove(&path, hostname, port)?;
+  learn(&path, hostname, port, public_key)
+}
+
+/// Hashes every plain host like `ssh-keygen -H` and returns the number of
+/// hashed hosts. Lines with markers, wildcards or negations are kept as is.
+pub fn hash<P: AsRef<Path>>(path: P) -> SSHResult<usize> {
+  let path = path.as_ref();
+  let content = fs::read_to_string(path)?;
+
+  let mut hashed = 0;
+  let mut lines = Vec::new();
+  for (index, line) in content.lines().enumerate() {
+    let entry = KnownHostsEntry::parse(index + 1, line).filter(|entry| {
+      entry.marker.is_none() && entry.hosts.iter().all(|host| is_literal_pattern(host))
+    });
+
+    if let Some(entry) = entry {
+      for host in &entry.hosts {
+        let mut line = format!("{} {}", hashed_host_pattern(host), entry.public_key);
+        if let Some(comment) = &entry.comment {
+          line.push(' ');
+          line.push_str(comment);
+        }
+        lines.push(line);
+        hashed += 1;
+      }
+    } else {
+      lines.push(line.to_string());
+    }
+  }
+
+  if hashed > 0 {
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(path, content)?;
+  }
+
+  Ok(hashed)
+}
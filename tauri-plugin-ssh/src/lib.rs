@@ -1,8 +1,22 @@
 pub(crate) mod commands;
+pub(crate) mod connection_profile;
+pub(crate) mod diagnostics;
 pub(crate) mod error;
+pub(crate) mod keyboard_interactive;
+pub(crate) mod known_hosts;
+pub(crate) mod multiplex;
+pub(crate) mod proxy_command;
+pub(crate) mod reconnect;
+pub(crate) mod recording;
+pub(crate) mod shell_output;
+pub(crate) mod ssh_agent;
 pub(crate) mod ssh_client;
 pub(crate) mod ssh_manager;
+pub(crate) mod startup_script;
+pub(crate) mod totp;
+pub(crate) mod upstream_proxy;
 pub(crate) mod utils;
+pub(crate) mod x11_forwarding;
 
 use ssh_manager::SSHManager;
 use tauri::{
@@ -18,11 +32,28 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
     .invoke_handler(tauri::generate_handler![
       commands::session::session_connect,
       commands::session::session_authenticate,
+      commands::session::session_probe_auth,
+      commands::session::session_info,
       commands::session::session_disconnect,
+      commands::session::session_set_upstream_proxy,
+      commands::session::session_get_upstream_proxy,
+      commands::exec::session_exec,
+      commands::exec::session_exec_cancel,
       commands::shell::shell_open,
       commands::shell::shell_close,
       commands::shell::shell_resize,
       commands::shell::shell_send,
+      commands::shell::shell_output_ack,
+      commands::shell::shell_signal,
+      commands::shell::shell_break,
+      commands::broadcast::shell_broadcast,
+      commands::broadcast::shell_broadcast_group_set,
+      commands::broadcast::shell_broadcast_group_list,
+      commands::recording::shell_recording_start,
+      commands::recording::shell_recording_stop,
+      commands::recording::shell_recording_list,
+      commands::recording::shell_recording_export,
+      commands::recording::shell_recording_remove,
       commands::port_forwarding::port_forwarding_local_open,
       commands::port_forwarding::port_forwarding_local_close,
       commands::port_forwarding::port_forwarding_remote_open,
@@ -41,6 +72,11 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
       commands::sftp::sftp_rename,
       commands::sftp::sftp_exists,
       commands::sftp::sftp_canonicalize,
+      commands::known_hosts::known_hosts_list,
+      commands::known_hosts::known_hosts_search,
+      commands::known_hosts::known_hosts_remove,
+      commands::known_hosts::known_hosts_replace,
+      commands::known_hosts::known_hosts_hash,
     ])
     .setup(|app, _api| {
       app.manage(SSHManager::<R>::init());
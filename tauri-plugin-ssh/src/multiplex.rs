@@ -0,0 +1,386 @@
+use std::{
+  sync::{
+    Mutex, PoisonError,
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+  },
+  time::Duration,
+};
+
+use russh::Disconnect;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, Runtime, async_runtime, ipc::Channel};
+use tokio::time::{sleep, timeout};
+
+use crate::{
+  commands::{
+    port_forwarding::SSHPortForwarding,
+    session::{SSHSession, SSHSessionConnectParams, SSHSessionId, SessionIpcChannelData},
+  },
+  connection_profile::SSHConnectionProfile,
+  error::SSHError,
+  ssh_client::DisconnectReason,
+  ssh_manager::SSHManager,
+};
+
+const DEFAULT_LINGER_SECS: u64 = 60;
+const CHANNEL_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lets sessions to the same host share one connection, it stays open for
+/// `linger_secs` after the last session on it disconnects
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHSessionMultiplexOpts {
+  pub linger_secs: Option<u64>,
+}
+
+/// Connections are shared by the same user on the same host reached the same way
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SSHMultiplexKey {
+  hostname: String,
+  port: u16,
+  username: String,
+  proxy_command: Option<String>,
+  /// A connection negotiated with other algorithms or timeouts is not shared
+  connection_profile: Option<SSHConnectionProfile>,
+  /// `hostname`, `port`, `username` and connection profile of every jump
+  /// host, first hop first
+  jump_hosts: Vec<(String, u16, String, Option<SSHConnectionProfile>)>,
+}
+
+impl SSHMultiplexKey {
+  /// `None` when the username is only known at authentication
+  pub fn new(params: &SSHSessionConnectParams) -> Option<Self> {
+    Some(Self {
+      hostname: params.hostname.clone(),
+      port: params.port,
+      username: params.username.clone()?,
+      proxy_command: params
+        .proxy_command
+        .clone()
+        .filter(|proxy_command| !proxy_command.trim().is_empty()),
+      connection_profile: params.connection_profile.clone(),
+      jump_hosts: params
+        .jump_hosts
+        .iter()
+        .map(|jump_host| {
+          (
+            jump_host.hostname.clone(),
+            jump_host.port,
+            jump_host.username.clone(),
+            jump_host.connection_profile.clone(),
+          )
+        })
+        .collect(),
+    })
+  }
+}
+
+/// Kept on the session that opened a shared connection
+pub struct SSHSessionMultiplex {
+  key: SSHMultiplexKey,
+  linger: Duration,
+  /// Sessions on the connection, including the one that opened it until it
+  /// disconnects
+  users: AtomicUsize,
+  /// Only a connection authenticated as `key.username` is shared
+  authenticated: AtomicBool,
+  /// The session that opened the connection disconnected, the connection
+  /// stays for the others
+  detached: AtomicBool,
+  /// Bumped by every session joining, so that a pending close sees the reuse
+  generation: AtomicU64,
+  /// Where the connection reports once detached, a session still on it
+  event_sink: Mutex<Option<Channel<SessionIpcChannelData>>>,
+}
+
+impl SSHSessionMultiplex {
+  pub fn new(key: SSHMultiplexKey, opts: &SSHSessionMultiplexOpts) -> Self {
+    Self {
+      key,
+      linger: Duration::from_secs(opts.linger_secs.unwrap_or(DEFAULT_LINGER_SECS)),
+      users: AtomicUsize::new(1),
+      authenticated: AtomicBool::new(false),
+      detached: AtomicBool::new(false),
+      generation: AtomicU64::new(0),
+      event_sink: Mutex::default(),
+    }
+  }
+
+  pub fn set_authenticated(&self, username: &str) {
+    if username == self.key.username {
+      self.authenticated.store(true, Ordering::SeqCst);
+    }
+  }
+
+  pub fn is_detached(&self) -> bool {
+    self.detached.load(Ordering::SeqCst)
+  }
+
+  pub fn event_sink(&self) -> Option<Channel<SessionIpcChannelData>> {
+    self
+      .event_sink
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .clone()
+  }
+
+  fn set_event_sink(&self, ipc_channel: Option<Channel<SessionIpcChannelData>>) {
+    *self
+      .event_sink
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner) = ipc_channel;
+  }
+
+  /// Taken over by the session connected again after the connection dropped,
+  /// the sessions that shared it went down with it
+  pub fn reconnected(&self) {
+    self.users.store(1, Ordering::SeqCst);
+    self.generation.fetch_add(1, Ordering::SeqCst);
+  }
+
+  fn is_shared_with(&self, key: &SSHMultiplexKey) -> bool {
+    self.authenticated.load(Ordering::SeqCst) && self.key == *key
+  }
+}
+
+/// Opens a session on an authenticated connection matching `key`, the session
+/// is not registered with the manager
+pub fn multiplex_session<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  ssh_session_id: SSHSessionId,
+  key: &SSHMultiplexKey,
+  ipc_channel: &Channel<SessionIpcChannelData>,
+) -> Option<SSHSession<R>> {
+  // Joined under the lock so that a lingering connection is not closed
+  // in between
+  ssh_manager.sessions.update(|sessions| {
+    let connection = sessions.values().find(|session| {
+      !session.is_closed()
+        && session
+          .multiplex
+          .as_ref()
+          .is_some_and(|multiplex| multiplex.is_shared_with(key))
+    })?;
+    let multiplex = connection.multiplex.as_ref()?;
+    multiplex.users.fetch_add(1, Ordering::SeqCst);
+    multiplex.generation.fetch_add(1, Ordering::SeqCst);
+    if multiplex.is_detached() && multiplex.event_sink().is_none() {
+      multiplex.set_event_sink(Some(ipc_channel.clone()));
+    }
+
+    Some(SSHSession::multiplexed(
+      connection,
+      ssh_session_id,
+      ipc_channel.clone(),
+    ))
+  })
+}
+
+/// Reports the events of a detached connection to a session still on it
+fn multiplex_rebind<R: Runtime>(ssh_manager: &SSHManager<R>, connection_id: SSHSessionId) {
+  ssh_manager.sessions.update(|sessions| {
+    let Some(multiplex) = sessions
+      .get(&connection_id)
+      .and_then(|session| session.multiplex.as_ref())
+      .filter(|multiplex| multiplex.is_detached())
+    else {
+      return;
+    };
+    let ipc_channel = sessions
+      .values()
+      .find(|session| {
+        session.connection_id == connection_id && session.ssh_session_id != connection_id
+      })
+      .map(|session| session.ipc_channel.clone());
+    multiplex.set_event_sink(ipc_channel);
+  });
+}
+
+/// Closes the shells and sftps of a session leaving a connection that stays
+/// open, its execs are cancelled by `session_resources_close`
+async fn multiplex_channels_close<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  ssh_session_id: SSHSessionId,
+) {
+  let shells = ssh_manager
+    .shells
+    .values()
+    .into_iter()
+    .filter(|shell| shell.ssh_session_id == ssh_session_id);
+  for shell in shells {
+    shell.output.closing();
+    if let Err(err) = timeout(CHANNEL_CLOSE_TIMEOUT, shell.close())
+      .await
+      .map_err(SSHError::from)
+      .and_then(|closed| closed.map_err(SSHError::from))
+    {
+      log::warn!("shell {:?} close failed: {}", shell.ssh_shell_id, err);
+    }
+  }
+
+  let sftps = ssh_manager
+    .sftps
+    .values()
+    .into_iter()
+    .filter(|sftp| sftp.ssh_session_id == ssh_session_id);
+  for sftp in sftps {
+    if let Err(err) = timeout(CHANNEL_CLOSE_TIMEOUT, sftp.close())
+      .await
+      .map_err(SSHError::from)
+      .and_then(|closed| closed.map_err(SSHError::from))
+    {
+      log::warn!("sftp {:?} close failed: {}", sftp.ssh_sftp_id, err);
+    }
+  }
+}
+
+/// Disconnects a session sharing a connection, the connection is closed once
+/// no session used it for its linger time. Returns `false` for a session that
+/// does not share its connection.
+pub async fn multiplex_disconnect<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  ssh_session_id: SSHSessionId,
+) -> bool {
+  let ssh_manager = app_handle.state::<SSHManager<R>>();
+  let Some(session) = ssh_manager.sessions.get(&ssh_session_id) else {
+    return false;
+  };
+  let connection_id = session.connection_id;
+
+  if connection_id != ssh_session_id {
+    ssh_manager.sessions.remove(&ssh_session_id);
+  } else if let Some(multiplex) = &session.multiplex {
+    if multiplex.detached.swap(true, Ordering::SeqCst) {
+      return true;
+    }
+  } else {
+    return false;
+  }
+
+  // The connection stays, so the server has to stop forwarding to it
+  for port_forwarding in ssh_manager.port_forwardings.values() {
+    if let SSHPortForwarding::Remote {
+      ssh_session_id: port_forwarding_ssh_session_id,
+      remote_address,
+      remote_port,
+      ..
+    } = port_forwarding.as_ref()
+      && *port_forwarding_ssh_session_id == ssh_session_id
+      && let Err(err) = session
+        .handle_mut()
+        .await
+        .cancel_tcpip_forward(remote_address, *remote_port as u32)
+        .await
+    {
+      log::warn!(
+        "cancel remote forwarding {}:{} failed: {}",
+        remote_address,
+        remote_port,
+        err
+      );
+    }
+  }
+  multiplex_channels_close(&ssh_manager, ssh_session_id).await;
+  ssh_manager.session_resources_close(ssh_session_id).await;
+  drop(session);
+
+  multiplex_rebind(&ssh_manager, connection_id);
+  multiplex_release(app_handle, connection_id);
+  true
+}
+
+fn multiplex_release<R: Runtime>(app_handle: &AppHandle<R>, connection_id: SSHSessionId) {
+  let ssh_manager = app_handle.state::<SSHManager<R>>();
+  let Some(multiplex) = ssh_manager
+    .sessions
+    .get(&connection_id)
+    .and_then(|session| session.multiplex.clone())
+  else {
+    return;
+  };
+  if multiplex.users.fetch_sub(1, Ordering::SeqCst) != 1 {
+    return;
+  }
+
+  // A connection that never authenticated cannot be shared
+  let linger = if multiplex.authenticated.load(Ordering::SeqCst) {
+    multiplex.linger
+  } else {
+    Duration::ZERO
+  };
+  let generation = multiplex.generation.load(Ordering::SeqCst);
+  log::info!(
+    "multiplexed connection {:?} unused, close in {:?}",
+    connection_id,
+    linger
+  );
+
+  let app_handle = app_handle.clone();
+  async_runtime::spawn(async move {
+    sleep(linger).await;
+
+    let ssh_manager = app_handle.state::<SSHManager<R>>();
+    let session = ssh_manager.sessions.update(|sessions| {
+      let multiplex = sessions.get(&connection_id)?.multiplex.as_ref()?;
+      if multiplex.users.load(Ordering::SeqCst) > 0
+        || multiplex.generation.load(Ordering::SeqCst) != generation
+      {
+        return None;
+      }
+      sessions.remove(&connection_id)
+    });
+    let Some(session) = session else {
+      return;
+    };
+
+    log::info!("multiplexed connection {:?} close", connection_id);
+    ssh_manager.session_removed(&session).await;
+    ssh_manager.session_resources_close(connection_id).await;
+    if let Err(err) = session
+      .handle()
+      .await
+      .disconnect(Disconnect::ByApplication, "", "English")
+      .await
+    {
+      log::warn!(
+        "multiplexed connection {:?} disconnect failed: {}",
+        connection_id,
+        err
+      );
+    }
+  });
+}
+
+/// Sessions sharing a connection that dropped go down with it, they are not
+/// reconnected
+pub async fn multiplex_connection_lost<R: Runtime>(
+  ssh_manager: &SSHManager<R>,
+  connection_id: SSHSessionId,
+  reason: &DisconnectReason,
+) {
+  let sessions = ssh_manager.sessions.update(|sessions| {
+    let ssh_session_ids = sessions
+      .values()
+      .filter(|session| {
+        session.connection_id == connection_id && session.ssh_session_id != connection_id
+      })
+      .map(|session| session.ssh_session_id)
+      .collect::<Vec<_>>();
+    ssh_session_ids
+      .iter()
+      .filter_map(|ssh_session_id| sessions.remove(ssh_session_id))
+      .collect::<Vec<_>>()
+  });
+
+  for session in sessions {
+    log::info!(
+      "multiplexed session {:?} lost connection {:?}",
+      session.ssh_session_id,
+      connection_id
+    );
+    ssh_manager
+      .session_resources_close(session.ssh_session_id)
+      .await;
+    session.event(SessionIpcChannelData::Disconnect(reason.clone()));
+  }
+}
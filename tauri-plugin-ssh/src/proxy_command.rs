@@ -0,0 +1,141 @@
+use std::process::Stdio;
+
+use tauri::{async_runtime, ipc::Channel};
+use tokio::{
+  io::{self, AsyncBufReadExt, BufReader, Join},
+  process::{Child, ChildStdin, ChildStdout, Command},
+};
+
+use crate::{SSHError, SSHResult, commands::session::SessionIpcChannelData};
+
+pub type SSHProxyCommandStream = Join<ChildStdout, ChildStdin>;
+
+/// A local `ProxyCommand` process, its stdin/stdout carry the SSH connection
+/// and it is killed when dropped together with its session
+pub struct SSHProxyCommand {
+  command: String,
+  child: Child,
+}
+
+impl SSHProxyCommand {
+  pub fn spawn(
+    command: &str,
+    hostname: &str,
+    port: u16,
+    username: &str,
+    ipc_channel: Channel<SessionIpcChannelData>,
+  ) -> SSHResult<(Self, SSHProxyCommandStream)> {
+    check_substitution("hostname", hostname)?;
+    check_substitution("username", username)?;
+    let command = expand(command, hostname, port, username);
+    log::info!("proxy command spawn: {}", command);
+
+    let mut child = shell_command(&command)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .map_err(|err| SSHError::ProxyCommandFailed(format!("{}: {}", command, err)))?;
+
+    let (Some(stdin), Some(stdout), Some(stderr)) =
+      (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+      let _ = child.start_kill();
+      return Err(SSHError::ProxyCommandFailed(command));
+    };
+
+    async_runtime::spawn(async move {
+      let mut lines = BufReader::new(stderr).lines();
+      while let Ok(Some(line)) = lines.next_line().await {
+        log::warn!("proxy command stderr: {}", line);
+        if ipc_channel
+          .send(SessionIpcChannelData::ProxyCommandStderr(line))
+          .is_err()
+        {
+          break;
+        }
+      }
+    });
+
+    Ok((SSHProxyCommand { command, child }, io::join(stdout, stdin)))
+  }
+}
+
+impl Drop for SSHProxyCommand {
+  fn drop(&mut self) {
+    if let Ok(None) = self.child.try_wait() {
+      log::info!("proxy command kill: {}", self.command);
+      if let Err(err) = self.child.start_kill() {
+        log::error!("proxy command kill failed: {}", err);
+      }
+    }
+  }
+}
+
+/// Characters the `sh` or `cmd` running the command would interpret
+const SHELL_META_CHARACTERS: &str = "'`\"$\\;&<>|(){}*?[]!#~^%";
+
+/// Rejects values that could break out of the expanded command, the same
+/// way OpenSSH refuses such hostnames and usernames before running a
+/// `ProxyCommand`
+fn check_substitution(name: &str, value: &str) -> SSHResult<()> {
+  let invalid = value.starts_with('-')
+    || value
+      .chars()
+      .any(|c| c.is_whitespace() || c.is_control() || SHELL_META_CHARACTERS.contains(c));
+
+  if invalid {
+    return Err(SSHError::ProxyCommandFailed(format!(
+      "{} contains characters not allowed in a proxy command: {}",
+      name, value
+    )));
+  }
+
+  Ok(())
+}
+
+/// Substitutes `%h`, `%p`, `%r` and `%%` like OpenSSH does for `ProxyCommand`
+fn expand(command: &str, hostname: &str, port: u16, username: &str) -> String {
+  let mut expanded = String::with_capacity(command.len());
+
+  let mut chars = command.chars();
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      expanded.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('h') => expanded.push_str(hostname),
+      Some('p') => expanded.push_str(&port.to_string()),
+      Some('r') => expanded.push_str(username),
+      Some('%') => expanded.push('%'),
+      Some(c) => {
+        expanded.push('%');
+        expanded.push(c);
+      }
+      None => expanded.push('%'),
+    }
+  }
+
+  expanded
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+  let mut shell_command = Command::new("sh");
+  shell_command.arg("-c").arg(command);
+  shell_command
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+  const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+  let mut shell_command = Command::new("cmd");
+  shell_command
+    .arg("/C")
+    .arg(command)
+    .creation_flags(CREATE_NO_WINDOW);
+  shell_command
+}
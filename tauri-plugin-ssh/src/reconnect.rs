@@ -0,0 +1,313 @@
+use std::{
+  sync::{Arc, Mutex, PoisonError},
+  time::Duration,
+};
+
+use russh::Disconnect;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, Runtime, ipc::Channel};
+use tokio::{select, sync::Notify, time::sleep};
+
+use crate::{
+  commands::{
+    port_forwarding::port_forwarding_remote_restore,
+    session::{
+      AuthenticationData, SSHSession, SSHSessionConnectParams, SSHSessionId, SessionIpcChannelData,
+      authenticate_chain, open_session,
+    },
+    shell::shell_reopen,
+  },
+  error::{SSHError, SSHResult},
+  multiplex::SSHSessionMultiplex,
+  ssh_client::DisconnectReason,
+  ssh_manager::SSHManager,
+};
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_INITIAL_DELAY_MILLIS: u64 = 1000;
+const DEFAULT_MAX_DELAY_MILLIS: u64 = 30_000;
+
+/// How a dropped session is connected again, the delay doubles after every
+/// failed attempt up to `max_delay_millis`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHSessionReconnectPolicy {
+  pub max_attempts: Option<u32>,
+  pub initial_delay_millis: Option<u64>,
+  pub max_delay_millis: Option<u64>,
+}
+
+impl SSHSessionReconnectPolicy {
+  fn max_attempts(&self) -> u32 {
+    self.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS)
+  }
+
+  /// The delay before `attempt`, counted from 1
+  fn delay(&self, attempt: u32) -> Duration {
+    let initial_delay_millis = self
+      .initial_delay_millis
+      .unwrap_or(DEFAULT_INITIAL_DELAY_MILLIS);
+    let max_delay_millis = self.max_delay_millis.unwrap_or(DEFAULT_MAX_DELAY_MILLIS);
+
+    Duration::from_millis(
+      initial_delay_millis
+        .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+        .min(max_delay_millis),
+    )
+  }
+}
+
+/// The authentication that last succeeded, repeated when reconnecting
+#[derive(Debug, Clone)]
+pub struct SSHSessionCredentials {
+  pub username: String,
+  pub authentication_data: AuthenticationData,
+  pub authentication_chain: Vec<AuthenticationData>,
+}
+
+/// Everything needed to connect a session again, shared by the sessions that
+/// replace each other
+pub struct SSHSessionReconnect {
+  policy: SSHSessionReconnectPolicy,
+  params: SSHSessionConnectParams,
+  credentials: Mutex<Option<SSHSessionCredentials>>,
+}
+
+impl SSHSessionReconnect {
+  pub fn new(policy: SSHSessionReconnectPolicy, params: SSHSessionConnectParams) -> Self {
+    Self {
+      policy,
+      params,
+      credentials: Mutex::default(),
+    }
+  }
+
+  pub fn set_credentials(&self, credentials: SSHSessionCredentials) {
+    *self
+      .credentials
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner) = Some(credentials);
+  }
+
+  fn credentials(&self) -> Option<SSHSessionCredentials> {
+    self
+      .credentials
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .clone()
+  }
+
+  /// Only sessions that were authenticated are reconnected
+  pub fn is_ready(&self) -> bool {
+    self
+      .credentials
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .is_some()
+  }
+
+  /// Connects and authenticates a session replacing the dropped one
+  async fn open<R: Runtime>(
+    self: &Arc<Self>,
+    app_handle: &AppHandle<R>,
+    ssh_session_id: SSHSessionId,
+    ipc_channel: &Channel<SessionIpcChannelData>,
+  ) -> SSHResult<SSHSession<R>> {
+    let ssh_manager = app_handle.state::<SSHManager<R>>();
+    let credentials = self
+      .credentials()
+      .ok_or(SSHError::new("No credentials to reconnect with"))?;
+
+    // The answer given to a host key prompt of the first connection is not
+    // repeated, a key changed in between fails the reconnect and is shown
+    let mut params = self.params.clone();
+    params.check_server_key = None;
+    for jump_host in &mut params.jump_hosts {
+      jump_host.check_server_key = None;
+    }
+
+    let mut session = open_session(
+      app_handle,
+      &ssh_manager,
+      ssh_session_id,
+      &params,
+      false,
+      ipc_channel,
+    )
+    .await?;
+
+    if let Err(err) = authenticate_chain(
+      &session,
+      &credentials.username,
+      credentials.authentication_data,
+      credentials.authentication_chain,
+    )
+    .await
+    {
+      close_session(&ssh_manager, &session).await;
+      return Err(err.into());
+    }
+
+    session.reconnect = Some(self.clone());
+    Ok(session)
+  }
+}
+
+async fn close_session<R: Runtime>(ssh_manager: &SSHManager<R>, session: &SSHSession<R>) {
+  let _ = session
+    .handle()
+    .await
+    .disconnect(Disconnect::ByApplication, "", "English")
+    .await;
+  if let Some(jump_host_ssh_session_id) = session.jump_host_ssh_session_id {
+    ssh_manager
+      .jump_host_release(jump_host_ssh_session_id)
+      .await;
+  }
+}
+
+/// The session is not registered while it reconnects, so its events go
+/// straight to its channel
+fn event(
+  ssh_session_id: SSHSessionId,
+  ipc_channel: &Channel<SessionIpcChannelData>,
+  data: SessionIpcChannelData,
+) {
+  if let Err(err) = ipc_channel.send(data) {
+    log::warn!("session event {:?} send failed: {}", ssh_session_id, err);
+  }
+}
+
+/// Connects a dropped session again under the same id, then reopens its
+/// shells and remote forwardings. Its sftps and execs are closed, and a
+/// shared connection is shared again. `session_disconnect` cancels it.
+pub async fn reconnect<R: Runtime>(
+  app_handle: AppHandle<R>,
+  ssh_session_id: SSHSessionId,
+  ipc_channel: Channel<SessionIpcChannelData>,
+  reconnect: Arc<SSHSessionReconnect>,
+  multiplex: Option<Arc<SSHSessionMultiplex>>,
+  error: String,
+) {
+  let ssh_manager = app_handle.state::<SSHManager<R>>();
+  let cancel = Arc::new(Notify::new());
+  ssh_manager
+    .reconnecting
+    .insert(ssh_session_id, cancel.clone());
+  ssh_manager.session_channels_close(ssh_session_id).await;
+
+  let max_attempts = reconnect.policy.max_attempts();
+  let mut error = error;
+  for attempt in 1..=max_attempts {
+    let delay = reconnect.policy.delay(attempt);
+    log::info!(
+      "session reconnect {:?} attempt {} in {:?}",
+      ssh_session_id,
+      attempt,
+      delay
+    );
+    event(
+      ssh_session_id,
+      &ipc_channel,
+      SessionIpcChannelData::Reconnecting {
+        attempt,
+        max_attempts,
+        delay_millis: delay.as_millis() as u64,
+        error: error.clone(),
+      },
+    );
+
+    select! {
+      _ = sleep(delay) => {},
+      _ = cancel.notified() => {
+        log::info!("session reconnect {:?} cancelled", ssh_session_id);
+        return;
+      }
+    }
+
+    let mut session = match reconnect
+      .open(&app_handle, ssh_session_id, &ipc_channel)
+      .await
+    {
+      Ok(session) => session,
+      Err(err) => {
+        log::warn!(
+          "session reconnect {:?} attempt {} failed: {}",
+          ssh_session_id,
+          attempt,
+          err
+        );
+        error = err.to_string();
+        continue;
+      }
+    };
+
+    // Disconnected while the attempt was running
+    if ssh_manager.reconnecting.remove(&ssh_session_id).is_none() {
+      log::info!("session reconnect {:?} cancelled", ssh_session_id);
+      close_session(&ssh_manager, &session).await;
+      return;
+    }
+
+    if let Some(multiplex) = &multiplex {
+      multiplex.reconnected();
+    }
+    session.multiplex = multiplex;
+    let session = Arc::new(session);
+    ssh_manager.sessions.insert(ssh_session_id, session.clone());
+    session.sample_rtt();
+    log::info!("session reconnect {:?} success", ssh_session_id);
+
+    if let Err(err) = port_forwarding_remote_restore(&ssh_manager, ssh_session_id).await {
+      log::error!(
+        "session reconnect {:?} restore forwardings failed: {}",
+        ssh_session_id,
+        err
+      );
+    }
+
+    let shells = ssh_manager
+      .shells
+      .values()
+      .into_iter()
+      .filter(|shell| shell.ssh_session_id == ssh_session_id)
+      .collect::<Vec<_>>();
+    for shell in &shells {
+      ssh_manager
+        .shell_sinks
+        .remove(&(shell.connection_id, shell.id()));
+    }
+    for shell in shells {
+      let ssh_shell_id = shell.ssh_shell_id;
+      if let Err(err) = shell_reopen(&app_handle, shell).await {
+        log::error!("shell reopen {:?} failed: {}", ssh_shell_id, err);
+      }
+    }
+
+    event(
+      ssh_session_id,
+      &ipc_channel,
+      SessionIpcChannelData::Reconnected { attempt },
+    );
+    return;
+  }
+
+  if ssh_manager.reconnecting.remove(&ssh_session_id).is_none() {
+    return;
+  }
+
+  log::warn!("session reconnect {:?} gave up: {}", ssh_session_id, error);
+  ssh_manager.session_resources_close(ssh_session_id).await;
+  event(
+    ssh_session_id,
+    &ipc_channel,
+    SessionIpcChannelData::ReconnectFailed {
+      error: error.clone(),
+    },
+  );
+  event(
+    ssh_session_id,
+    &ipc_channel,
+    SessionIpcChannelData::Disconnect(DisconnectReason::Error(error)),
+  );
+}
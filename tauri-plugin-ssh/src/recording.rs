@@ -0,0 +1,314 @@
+use std::{
+  fs::{self, File},
+  io::{BufRead, BufReader, BufWriter, Write},
+  path::{Path, PathBuf},
+  sync::mpsc::{self, Receiver, Sender},
+  time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::async_runtime;
+use uuid::Uuid;
+
+use crate::{
+  commands::shell::ShellSize,
+  error::{SSHError, SSHResult},
+};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct SSHShellRecordingId(Uuid);
+
+impl SSHShellRecordingId {
+  fn filename(&self) -> String {
+    format!("{}.cast", self.0)
+  }
+
+  pub fn path(&self, recordings_dir: &Path) -> PathBuf {
+    recordings_dir.join(self.filename())
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHShellRecordingOpts {
+  pub title: Option<String>,
+  /// Also record what is sent to the shell as `i` events
+  #[serde(default)]
+  pub record_input: bool,
+  /// Input typed after a password like prompt is recorded as `*` until the
+  /// line is submitted
+  #[serde(default)]
+  pub redact_secrets: bool,
+}
+
+/// A finished or in progress recording in the recordings directory
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHShellRecording {
+  pub id: SSHShellRecordingId,
+  pub title: Option<String>,
+  pub width: u32,
+  pub height: u32,
+  /// Unix timestamp of the start of the recording
+  pub timestamp: u64,
+  pub size: u64,
+  pub recording: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct AsciicastHeader {
+  width: u32,
+  height: u32,
+  timestamp: Option<u64>,
+  title: Option<String>,
+}
+
+const SECRET_PROMPTS: &[&str] = &[
+  "password",
+  "passphrase",
+  "passcode",
+  "pin",
+  "token",
+  "verification code",
+  "密码",
+];
+
+/// Writes a shell to an asciicast v2 file, one JSON event per line.
+///
+/// Events are written by a blocking task of their own, so recording never
+/// holds up the output of the shell.
+pub struct SSHShellRecorder {
+  id: SSHShellRecordingId,
+  writer: Sender<String>,
+  started_at: Instant,
+  opts: SSHShellRecordingOpts,
+  /// Trailing bytes of an incomplete UTF-8 sequence in the output
+  pending_output: Vec<u8>,
+  /// The last output line, used to detect secret prompts
+  last_line: String,
+  redacting: bool,
+}
+
+impl SSHShellRecorder {
+  pub fn create(
+    recordings_dir: &Path,
+    term: &str,
+    size: &ShellSize,
+    opts: SSHShellRecordingOpts,
+  ) -> SSHResult<Self> {
+    let id = SSHShellRecordingId(Uuid::new_v4());
+    let mut writer = BufWriter::new(File::create(id.path(recordings_dir))?);
+
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_secs())
+      .unwrap_or_default();
+    let mut header = json!({
+      "version": 2,
+      "width": size.col,
+      "height": size.row,
+      "timestamp": timestamp,
+      "env": {
+        "TERM": term,
+      },
+    });
+    if let Some(title) = &opts.title {
+      header["title"] = json!(title);
+    }
+    writeln!(writer, "{}", header)?;
+    writer.flush()?;
+
+    let (sender, receiver) = mpsc::channel();
+    async_runtime::spawn_blocking(move || write_events(id, writer, receiver));
+
+    log::info!("shell recording start {:?}", id);
+
+    Ok(SSHShellRecorder {
+      id,
+      writer: sender,
+      started_at: Instant::now(),
+      opts,
+      pending_output: Vec::new(),
+      last_line: String::new(),
+      redacting: false,
+    })
+  }
+
+  pub fn id(&self) -> SSHShellRecordingId {
+    self.id
+  }
+
+  fn write_event(&mut self, code: &str, data: &str) -> SSHResult<()> {
+    let time = self.started_at.elapsed().as_secs_f64();
+    self
+      .writer
+      .send(format!("[{:.6}, {}, {}]", time, json!(code), json!(data)))
+      .map_err(|_| SSHError::new("Shell recording is no longer written"))
+  }
+
+  pub fn output(&mut self, data: &[u8]) -> SSHResult<()> {
+    let text = decode_utf8(&mut self.pending_output, data);
+    if text.is_empty() {
+      return Ok(());
+    }
+
+    match text.rsplit_once('\n') {
+      Some((_, line)) => self.last_line = line.to_string(),
+      None => self.last_line.push_str(&text),
+    }
+    if self.last_line.len() > 256 {
+      let mut start = self.last_line.len() - 256;
+      while !self.last_line.is_char_boundary(start) {
+        start += 1;
+      }
+      self.last_line.drain(..start);
+    }
+
+    self.write_event("o", &text)
+  }
+
+  pub fn input(&mut self, data: &str) -> SSHResult<()> {
+    if !self.opts.record_input {
+      return Ok(());
+    }
+
+    if !self.opts.redact_secrets {
+      return self.write_event("i", data);
+    }
+
+    if !self.redacting && is_secret_prompt(&self.last_line) {
+      self.redacting = true;
+    }
+
+    let mut text = String::with_capacity(data.len());
+    for char in data.chars() {
+      if !self.redacting {
+        text.push(char);
+      } else if char == '\r' || char == '\n' {
+        self.redacting = false;
+        text.push(char);
+      } else {
+        text.push('*');
+      }
+    }
+
+    self.write_event("i", &text)
+  }
+
+  pub fn resize(&mut self, size: &ShellSize) -> SSHResult<()> {
+    self.write_event("r", &format!("{}x{}", size.col, size.row))
+  }
+
+  pub fn finish(mut self) -> SSHResult<SSHShellRecordingId> {
+    let pending_output = std::mem::take(&mut self.pending_output);
+    if !pending_output.is_empty() {
+      self.write_event("o", &String::from_utf8_lossy(&pending_output))?;
+    }
+
+    log::info!("shell recording stop {:?}", self.id);
+
+    Ok(self.id)
+  }
+}
+
+/// Writes the events of a recording until its recorder is dropped, flushing
+/// whenever the shell goes quiet
+fn write_events(id: SSHShellRecordingId, mut writer: BufWriter<File>, receiver: Receiver<String>) {
+  while let Ok(event) = receiver.recv() {
+    let mut result = writeln!(writer, "{}", event);
+    while result.is_ok()
+      && let Ok(event) = receiver.try_recv()
+    {
+      result = writeln!(writer, "{}", event);
+    }
+
+    if let Err(err) = result.and_then(|_| writer.flush()) {
+      log::error!("shell recording {:?} write failed: {}", id, err);
+      return;
+    }
+  }
+}
+
+/// Appends `data` to `pending` and returns the longest decodable prefix, an
+/// incomplete sequence at the end is kept for the next chunk
+fn decode_utf8(pending: &mut Vec<u8>, data: &[u8]) -> String {
+  pending.extend_from_slice(data);
+
+  let mut text = String::new();
+  let mut rest: &[u8] = &pending[..];
+  loop {
+    match std::str::from_utf8(rest) {
+      Ok(valid) => {
+        text.push_str(valid);
+        rest = &[];
+        break;
+      }
+      Err(err) => {
+        let (valid, invalid) = rest.split_at(err.valid_up_to());
+        text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+        match err.error_len() {
+          Some(len) => {
+            text.push(char::REPLACEMENT_CHARACTER);
+            rest = &invalid[len..];
+          }
+          None => {
+            rest = invalid;
+            break;
+          }
+        }
+      }
+    }
+  }
+
+  let rest = rest.to_vec();
+  *pending = rest;
+
+  text
+}
+
+fn is_secret_prompt(line: &str) -> bool {
+  let line = line.trim_end().to_lowercase();
+
+  (line.ends_with(':') || line.ends_with('：'))
+    && SECRET_PROMPTS.iter().any(|prompt| line.contains(prompt))
+}
+
+fn read_recording(path: &Path) -> Option<SSHShellRecording> {
+  if path.extension().is_none_or(|extension| extension != "cast") {
+    return None;
+  }
+
+  let id = path
+    .file_stem()
+    .and_then(|file_stem| file_stem.to_str())
+    .and_then(|file_stem| Uuid::parse_str(file_stem).ok())
+    .map(SSHShellRecordingId)?;
+
+  let file = File::open(path).ok()?;
+  let size = file.metadata().ok()?.len();
+  let mut header = String::new();
+  BufReader::new(file).read_line(&mut header).ok()?;
+  let header = serde_json::from_str::<AsciicastHeader>(&header).ok()?;
+
+  Some(SSHShellRecording {
+    id,
+    title: header.title,
+    width: header.width,
+    height: header.height,
+    timestamp: header.timestamp.unwrap_or_default(),
+    size,
+    recording: false,
+  })
+}
+
+pub fn read_recordings(recordings_dir: &Path) -> SSHResult<Vec<SSHShellRecording>> {
+  let mut recordings = fs::read_dir(recordings_dir)?
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| read_recording(&entry.path()))
+    .collect::<Vec<SSHShellRecording>>();
+
+  recordings.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+  Ok(recordings)
+}
@@ -0,0 +1,202 @@
+use std::{
+  collections::VecDeque,
+  sync::{Arc, Mutex, PoisonError},
+  time::Duration,
+};
+
+use tauri::ipc::Channel;
+use tokio::{sync::Notify, time::sleep};
+
+use crate::commands::shell::SHHShellIpcChannelData;
+
+/// How long a burst of output may accumulate before it is delivered
+const BATCH_DELAY: Duration = Duration::from_millis(8);
+/// Output is delivered without waiting for `BATCH_DELAY` once this much is queued
+const BATCH_BYTES: usize = 64 * 1024;
+/// Upper bound of a single `Data` message
+const MESSAGE_BYTES: usize = 256 * 1024;
+/// Queued plus unacknowledged output above which the consumer is considered behind
+const HIGH_WATER_BYTES: usize = 1024 * 1024;
+
+#[derive(Default)]
+struct SSHShellOutputState {
+  queue: VecDeque<SHHShellIpcChannelData>,
+  queued_bytes: usize,
+  /// Delivered `Data` bytes not yet acknowledged with `shell_output_ack`
+  unacked_bytes: usize,
+  /// The shell is closing, output is no longer held back
+  closing: bool,
+  closed: bool,
+}
+
+impl SSHShellOutputState {
+  fn is_full(&self, flow_control: bool) -> bool {
+    let pending_bytes = if flow_control {
+      self.queued_bytes + self.unacked_bytes
+    } else {
+      self.queued_bytes
+    };
+    !self.closing && pending_bytes >= HIGH_WATER_BYTES
+  }
+}
+
+/// Delivers the events of a shell to its IPC channel in order.
+///
+/// Consecutive `Data` chunks are coalesced into one message. With flow control
+/// the consumer acknowledges the bytes it has processed and delivery pauses
+/// while too much is unacknowledged. Output is queued up to `HIGH_WATER_BYTES`,
+/// beyond that `send_data` waits, which only holds back the reader of this
+/// shell's channel and never the connection handler.
+pub struct SSHShellOutput {
+  state: Mutex<SSHShellOutputState>,
+  notify: Notify,
+  /// Wakes `send_data` once the queue has room again
+  room: Notify,
+  flow_control: bool,
+}
+
+impl SSHShellOutput {
+  pub fn new(flow_control: bool) -> Self {
+    Self {
+      state: Mutex::default(),
+      notify: Notify::new(),
+      room: Notify::new(),
+      flow_control,
+    }
+  }
+
+  fn state(&self) -> std::sync::MutexGuard<'_, SSHShellOutputState> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  pub fn send(&self, data: SHHShellIpcChannelData) {
+    {
+      let mut state = self.state();
+      match data {
+        SHHShellIpcChannelData::Data(data) => {
+          state.queued_bytes += data.len();
+          match state.queue.back_mut() {
+            Some(SHHShellIpcChannelData::Data(last)) => last.extend_from_slice(&data),
+            _ => state.queue.push_back(SHHShellIpcChannelData::Data(data)),
+          }
+        }
+        data => state.queue.push_back(data),
+      }
+    }
+
+    self.notify.notify_one();
+  }
+
+  /// Queues output received from the server, waiting while the consumer is
+  /// behind. Output arriving after the pipeline stopped is dropped.
+  pub async fn send_data(&self, data: Vec<u8>) {
+    loop {
+      let room = self.room.notified();
+      {
+        let state = self.state();
+        if state.closed {
+          return;
+        }
+        if !state.is_full(self.flow_control) {
+          break;
+        }
+      }
+      room.await;
+    }
+
+    self.send(SHHShellIpcChannelData::Data(data));
+  }
+
+  pub fn ack(&self, bytes: usize) {
+    {
+      let mut state = self.state();
+      state.unacked_bytes = state.unacked_bytes.saturating_sub(bytes);
+    }
+
+    self.notify.notify_one();
+    self.room.notify_waiters();
+  }
+
+  /// Stops holding output back so that closing the channel is not stuck
+  /// behind output nobody consumes any more
+  pub fn closing(&self) {
+    self.state().closing = true;
+    self.notify.notify_one();
+    self.room.notify_waiters();
+  }
+
+  /// Delivers what is left regardless of flow control and stops the pipeline
+  pub fn shutdown(&self) {
+    self.state().closed = true;
+    self.notify.notify_one();
+    self.room.notify_waiters();
+  }
+
+  /// Takes the next event that may be delivered now
+  fn next(&self) -> Option<SHHShellIpcChannelData> {
+    let mut state = self.state();
+    if self.flow_control
+      && !state.closing
+      && !state.closed
+      && state.unacked_bytes >= HIGH_WATER_BYTES
+    {
+      return None;
+    }
+
+    let data = match state.queue.pop_front()? {
+      SHHShellIpcChannelData::Data(mut data) if data.len() > MESSAGE_BYTES => {
+        let rest = data.split_off(MESSAGE_BYTES);
+        state.queue.push_front(SHHShellIpcChannelData::Data(rest));
+        SHHShellIpcChannelData::Data(data)
+      }
+      data => data,
+    };
+
+    if let SHHShellIpcChannelData::Data(data) = &data {
+      state.queued_bytes -= data.len();
+      if self.flow_control {
+        state.unacked_bytes += data.len();
+      }
+      self.room.notify_waiters();
+    }
+
+    Some(data)
+  }
+
+  pub async fn run(self: Arc<Self>, ipc_channel: Channel<SHHShellIpcChannelData>) {
+    self.deliver(ipc_channel).await;
+
+    // Nothing drains the queue any more
+    self.shutdown();
+  }
+
+  async fn deliver(&self, ipc_channel: Channel<SHHShellIpcChannelData>) {
+    loop {
+      self.notify.notified().await;
+
+      let (queued_bytes, closed) = {
+        let state = self.state();
+        (state.queued_bytes, state.closed)
+      };
+      if !closed && queued_bytes < BATCH_BYTES {
+        sleep(BATCH_DELAY).await;
+      }
+
+      while let Some(data) = self.next() {
+        let is_close = matches!(data, SHHShellIpcChannelData::Close);
+        if let Err(err) = ipc_channel.send(data) {
+          log::error!("shell output send failed: {}", err);
+          return;
+        }
+        if is_close {
+          return;
+        }
+      }
+
+      let state = self.state();
+      if state.closed && state.queue.is_empty() {
+        return;
+      }
+    }
+  }
+}
@@ -0,0 +1,171 @@
+use std::sync::{
+  Arc,
+  atomic::{AtomicBool, Ordering},
+};
+
+use async_trait::async_trait;
+use futures::stream;
+use russh::{
+  Channel,
+  client::Msg,
+  keys::{
+    PrivateKey,
+    agent::{
+      client::AgentClient,
+      server::{Agent, MessageType, serve},
+    },
+    decode_secret_key,
+  },
+};
+use serde::Deserialize;
+use tauri::async_runtime;
+use tokio::{
+  io::{self, DuplexStream},
+  sync::mpsc,
+};
+
+use crate::{
+  error::{SSHError, SSHResult},
+  utils::connect_agent,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentForwardingKey {
+  pub private_key: String,
+  pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum AgentForwardingData {
+  /// Proxy to a local ssh-agent, `SSH_AUTH_SOCK` is used when the path is empty
+  Socket { socket_path: Option<String> },
+  /// Serve an in-process agent holding the given private keys
+  Keys { keys: Vec<AgentForwardingKey> },
+}
+
+/// Only lists keys and signs once its keys are loaded, so that the server
+/// cannot add, remove or lock keys through the forwarded agent
+#[derive(Clone)]
+struct VaultAgent {
+  sealed: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Agent for VaultAgent {
+  async fn confirm_request(&self, msg: MessageType) -> bool {
+    matches!(msg, MessageType::RequestKeys | MessageType::Sign)
+      || !self.sealed.load(Ordering::SeqCst)
+  }
+}
+
+/// Handles the `auth-agent@openssh.com` channels opened by the server
+#[derive(Clone)]
+pub enum SSHAgentForwarder {
+  Socket(Option<String>),
+  Keys(mpsc::UnboundedSender<DuplexStream>),
+}
+
+impl SSHAgentForwarder {
+  pub async fn new(agent_forwarding_data: AgentForwardingData) -> SSHResult<Self> {
+    match agent_forwarding_data {
+      AgentForwardingData::Socket { socket_path } => Ok(SSHAgentForwarder::Socket(
+        socket_path.filter(|socket_path| !socket_path.is_empty()),
+      )),
+      AgentForwardingData::Keys { keys } => {
+        let private_keys = keys
+          .iter()
+          .map(|key| {
+            let passphrase = key
+              .passphrase
+              .as_deref()
+              .filter(|passphrase| !passphrase.is_empty());
+            decode_secret_key(&key.private_key, passphrase)
+          })
+          .collect::<Result<Vec<PrivateKey>, _>>()?;
+
+        let (sender, receiver) = mpsc::unbounded_channel::<DuplexStream>();
+        let listener = Box::pin(stream::unfold(receiver, |mut receiver| async move {
+          receiver
+            .recv()
+            .await
+            .map(|stream| (Ok::<_, io::Error>(stream), receiver))
+        }));
+
+        // The agent stops once every sender is dropped, i.e. when the forwarder
+        // is removed together with its session
+        let sealed = Arc::new(AtomicBool::new(false));
+        let vault_agent = VaultAgent {
+          sealed: sealed.clone(),
+        };
+        async_runtime::spawn(async move {
+          if let Err(err) = serve(listener, vault_agent).await {
+            log::error!("agent forwarding serve failed: {}", err);
+          }
+        });
+
+        let forwarder = SSHAgentForwarder::Keys(sender);
+        let mut agent = AgentClient::connect(forwarder.connect_in_process()?);
+        for private_key in private_keys {
+          agent.add_identity(&private_key, &[]).await?;
+        }
+        sealed.store(true, Ordering::SeqCst);
+
+        Ok(forwarder)
+      }
+    }
+  }
+
+  fn connect_in_process(&self) -> SSHResult<DuplexStream> {
+    match self {
+      SSHAgentForwarder::Keys(sender) => {
+        let (client, server) = io::duplex(64 * 1024);
+        sender
+          .send(server)
+          .map_err(|_| SSHError::new("Agent forwarding closed"))?;
+        Ok(client)
+      }
+      SSHAgentForwarder::Socket(_) => Err(SSHError::new("Agent forwarding is not in process")),
+    }
+  }
+
+  pub async fn forward(&self, channel: Channel<Msg>) -> SSHResult<()> {
+    match self {
+      SSHAgentForwarder::Socket(socket_path) => {
+        let mut stream = match connect_agent(socket_path.as_deref()).await {
+          Ok(agent) => agent.into_inner(),
+          Err(err) => {
+            channel.close().await?;
+            return Err(err.into());
+          }
+        };
+
+        let mut channel_stream = channel.into_stream();
+        async_runtime::spawn(async move {
+          io::copy_bidirectional(&mut channel_stream, &mut stream).await?;
+
+          Ok::<(), SSHError>(())
+        });
+      }
+      SSHAgentForwarder::Keys(_) => {
+        let mut stream = match self.connect_in_process() {
+          Ok(stream) => stream,
+          Err(err) => {
+            channel.close().await?;
+            return Err(err);
+          }
+        };
+
+        let mut channel_stream = channel.into_stream();
+        async_runtime::spawn(async move {
+          io::copy_bidirectional(&mut channel_stream, &mut stream).await?;
+
+          Ok::<(), SSHError>(())
+        });
+      }
+    }
+
+    Ok(())
+  }
+}
@@ -1,24 +1,28 @@
-use std::future::Future;
+use std::{future::Future, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use russh::{
-  Channel, ChannelId,
+  Channel as RusshChannel, ChannelId,
   client::{self},
-  keys::{
-    HashAlg, PublicKey,
-    known_hosts::{check_known_hosts_path, learn_known_hosts_path},
-  },
+  keys::{HashAlg, PublicKey},
 };
 use serde::Serialize;
-use tauri::{AppHandle, Manager, Runtime, State, async_runtime};
+use tauri::{AppHandle, Manager, Runtime, State, async_runtime, ipc::Channel};
 use tokio::{io, net::TcpStream};
 
 use crate::{
-  SSHError,
+  SSHError, SSHResult,
   commands::{
-    port_forwarding::SSHPortForwarding,
-    session::{SSHSessionCheckServerKey, SSHSessionId, SessionIpcChannelData},
+    port_forwarding::{SSHPortForwarding, port_forwarding_connection},
+    session::{
+      SSHChannelKind, SSHSessionCheckServerKey, SSHSessionId, SSHSessionStrictHostKeyChecking,
+      SessionIpcChannelData,
+    },
   },
+  diagnostics::{SSHSessionDiagnostics, SSHSessionStage},
+  known_hosts::{self, KnownHostsCheck},
+  multiplex::multiplex_connection_lost,
+  reconnect,
   ssh_manager::SSHManager,
   utils::get_known_hosts_path,
 };
@@ -33,11 +37,15 @@ pub enum DisconnectReason {
 pub struct SSHClient<R: Runtime> {
   app_handle: AppHandle<R>,
   ssh_session_id: SSHSessionId,
+  /// The channel of the session being connected, a jump host reports to the
+  /// session it is connected for
+  ipc_channel: Channel<SessionIpcChannelData>,
   hostname: String,
   port: u16,
-  #[allow(unused)]
-  jump_host_ssh_session_id: Option<SSHSessionId>,
   check_server_key: Option<SSHSessionCheckServerKey>,
+  strict_host_key_checking: SSHSessionStrictHostKeyChecking,
+  diagnostics: Arc<SSHSessionDiagnostics>,
+  key_exchanges: u32,
 }
 
 #[async_trait]
@@ -49,49 +57,37 @@ fn check_server_key(
     server_public_key: &PublicKey,
   ) -> impl Future<Output = Result<bool, Self::Error>> + Send {
     async {
-      let known_hosts_path = get_known_hosts_path(&self.app_handle)?;
-      if check_known_hosts_path(
-        &self.hostname,
-        self.port,
-        server_public_key,
-        &known_hosts_path,
-      )? {
-        Ok(true)
-      } else if let Some(check_server_key) = &self.check_server_key {
-        match check_server_key {
-          SSHSessionCheckServerKey::Continue => Ok(true),
-          SSHSessionCheckServerKey::AddAndContinue => {
-            learn_known_hosts_path(
-              &self.hostname,
-              self.port,
-              server_public_key,
-              &known_hosts_path,
-            )?;
-            Ok(true)
-          }
+      let started = Instant::now();
+      let result = self.verify_server_key(server_public_key);
+
+      // Called again for every later key exchange
+      self.key_exchanges += 1;
+      if self.key_exchanges > 1 {
+        if result.is_ok() {
+          log::info!("session {:?} rekey", self.ssh_session_id);
+          self.send(SessionIpcChannelData::Rekey {
+            hostname: self.hostname.clone(),
+            port: self.port,
+          });
         }
-      } else {
-        Err(SSHError::UnknownKey {
-          algorithm: server_public_key.algorithm().to_string(),
-          fingerprint: server_public_key.fingerprint(HashAlg::Sha256),
-        })
+        return result;
       }
-    }
-  }
-
-  fn data(
-    &mut self,
-    channel_id: ChannelId,
-    data: &[u8],
-    _session: &mut client::Session,
-  ) -> impl Future<Output = Result<(), Self::Error>> + Send {
-    async move {
-      let ssh_manager = self.ssh_manager();
 
-      ssh_manager
-        .shell_channel_data(self.ssh_session_id, channel_id, data)
-        .await?;
-      Ok(())
+      self
+        .diagnostics
+        .set_host_key_algorithm(server_public_key.algorithm().to_string());
+      self
+        .diagnostics
+        .stage(SSHSessionStage::HostKey, started, &result);
+      if result.is_ok() {
+        self.send(SessionIpcChannelData::HostKeyAccepted {
+          hostname: self.hostname.clone(),
+          port: self.port,
+          algorithm: server_public_key.algorithm().to_string(),
+          fingerprint: server_public_key.fingerprint(HashAlg::Sha256).to_string(),
+        });
+      }
+      result
     }
   }
 
@@ -103,19 +99,11 @@ fn channel_eof(
     async move {
       let ssh_manager = self.ssh_manager();
 
-      if ssh_manager
-        .shell_channel_eof(self.ssh_session_id, channel_id)
-        .await?
-      {
-        return Ok(());
-      }
-
-      if ssh_manager
+      // Shells see their eof and close in the order of their output through
+      // `shell_read`
+      ssh_manager
         .sftp_channel_eof(self.ssh_session_id, channel_id)
-        .await?
-      {
-        return Ok(());
-      }
+        .await?;
 
       Ok(())
     }
@@ -129,19 +117,29 @@ fn channel_close(
     async move {
       let ssh_manager = self.ssh_manager();
 
-      if ssh_manager
-        .shell_channel_close(self.ssh_session_id, channel_id)
-        .await?
+      // Reported to the session owning the channel when the connection is shared
+      let owner = ssh_manager
+        .shell_sinks
+        .get(&(self.ssh_session_id, channel_id))
+        .map(|shell| shell.ssh_session_id)
+        .or_else(|| {
+          ssh_manager
+            .sftp_sinks
+            .get(&(self.ssh_session_id, channel_id))
+            .map(|sftp| sftp.ssh_session_id)
+        })
+        .unwrap_or(self.ssh_session_id);
+      if let Some(session) = ssh_manager.sessions.get(&owner)
+        && session.jump_host_key.is_none()
       {
-        return Ok(());
+        session.event(SessionIpcChannelData::ChannelClosed {
+          channel_id: channel_id.into(),
+        });
       }
 
-      if ssh_manager
+      ssh_manager
         .sftp_channel_close(self.ssh_session_id, channel_id)
-        .await?
-      {
-        return Ok(());
-      }
+        .await?;
 
       Ok(())
     }
@@ -149,40 +147,52 @@ fn channel_close(
 
   fn server_channel_open_forwarded_tcpip(
     &mut self,
-    channel: Channel<client::Msg>,
+    channel: RusshChannel<client::Msg>,
     connected_address: &str,
     connected_port: u32,
-    _originator_address: &str,
-    _originator_port: u32,
+    originator_address: &str,
+    originator_port: u32,
     _session: &mut client::Session,
   ) -> impl Future<Output = Result<(), Self::Error>> + Send {
     async move {
       let ssh_manager = self.ssh_manager();
 
-      let port_forwardings = ssh_manager.port_forwardings.lock().await;
+      let port_forwardings = ssh_manager.port_forwardings.values();
 
-      let addr = port_forwardings.values().find_map(|ssh_port_forwarding| {
+      let forwarding = port_forwardings.iter().find_map(|ssh_port_forwarding| {
         if let SSHPortForwarding::Remote {
+          ssh_port_forwarding_id,
           ssh_session_id,
           local_address,
           local_port,
           remote_address,
           remote_port,
           ..
-        } = ssh_port_forwarding
+        } = &**ssh_port_forwarding
         {
-          if self.ssh_session_id == *ssh_session_id
+          let connection_id = ssh_manager
+            .sessions
+            .get(ssh_session_id)
+            .map(|session| session.connection_id);
+          if connection_id == Some(self.ssh_session_id)
             && remote_address == connected_address
             && *remote_port == connected_port as u16
           {
             let addr = format!("{}:{}", local_address, local_port);
-            return Some(addr);
+            return Some((*ssh_session_id, *ssh_port_forwarding_id, addr));
           }
         }
         None
       });
 
-      if let Some(addr) = addr {
+      if let Some((ssh_session_id, ssh_port_forwarding_id, addr)) = forwarding {
+        port_forwarding_connection(
+          &ssh_manager,
+          ssh_session_id,
+          ssh_port_forwarding_id,
+          format!("{}:{}", originator_address, originator_port),
+        );
+        ssh_manager.channel_opened(ssh_session_id, channel.id(), SSHChannelKind::ForwardedTcpip);
         let mut stream = TcpStream::connect(addr).await?;
         async_runtime::spawn(async move {
           io::copy_bidirectional(&mut channel.into_stream(), &mut stream).await?;
@@ -200,27 +210,146 @@ fn server_channel_open_forwarded_tcpip(
     }
   }
 
+  fn server_channel_open_agent_forward(
+    &mut self,
+    channel: RusshChannel<client::Msg>,
+    _session: &mut client::Session,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+    async move {
+      let ssh_manager = self.ssh_manager();
+
+      let agent_forwarder = ssh_manager.agent_forwarders.get(&self.ssh_session_id);
+
+      if let Some(agent_forwarder) = agent_forwarder {
+        ssh_manager.channel_opened(
+          self.ssh_session_id,
+          channel.id(),
+          SSHChannelKind::AgentForward,
+        );
+        if let Err(err) = agent_forwarder.forward(channel).await {
+          log::error!("agent forwarding {:?} failed: {}", self.ssh_session_id, err);
+        }
+      } else {
+        log::warn!(
+          "agent forwarding {:?} is not enabled, reject channel",
+          self.ssh_session_id
+        );
+        channel.close().await?;
+      }
+
+      Ok(())
+    }
+  }
+
+  fn server_channel_open_x11(
+    &mut self,
+    channel: RusshChannel<client::Msg>,
+    originator_address: &str,
+    originator_port: u32,
+    _session: &mut client::Session,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+    async move {
+      let ssh_manager = self.ssh_manager();
+
+      let x11_forwarder = ssh_manager.x11_forwarders.get(&self.ssh_session_id);
+
+      if let Some(x11_forwarder) = x11_forwarder {
+        log::info!(
+          "x11 forwarding {:?} from {}:{}",
+          self.ssh_session_id,
+          originator_address,
+          originator_port
+        );
+        ssh_manager.channel_opened(self.ssh_session_id, channel.id(), SSHChannelKind::X11);
+        x11_forwarder.forward(self.ssh_session_id, channel);
+      } else {
+        log::warn!(
+          "x11 forwarding {:?} is not enabled, reject channel",
+          self.ssh_session_id
+        );
+        channel.close().await?;
+      }
+
+      Ok(())
+    }
+  }
+
+  fn auth_banner(
+    &mut self,
+    banner: &str,
+    _session: &mut client::Session,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+    async move {
+      log::info!("session {:?} auth banner", self.ssh_session_id);
+      self.send(SessionIpcChannelData::AuthBanner {
+        hostname: self.hostname.clone(),
+        port: self.port,
+        banner: banner.to_string(),
+      });
+      Ok(())
+    }
+  }
+
   fn disconnected(
     &mut self,
     reason: client::DisconnectReason<Self::Error>,
   ) -> impl Future<Output = Result<(), Self::Error>> + Send {
     async move {
       let ssh_manager = self.ssh_manager();
-      let mut sessions = ssh_manager.sessions.lock().await;
-      if let Some(session) = sessions.remove(&self.ssh_session_id) {
-        match reason {
-          client::DisconnectReason::ReceivedDisconnect(_) => {
+      let disconnect_reason = match &reason {
+        client::DisconnectReason::ReceivedDisconnect(_) => DisconnectReason::Server,
+        client::DisconnectReason::Error(error) => DisconnectReason::Error(error.to_string()),
+      };
+      multiplex_connection_lost(&ssh_manager, self.ssh_session_id, &disconnect_reason).await;
+
+      if let Some(session) = ssh_manager.session_remove(self.ssh_session_id).await {
+        // The session that opened a shared connection has already gone away
+        let detached = session
+          .multiplex
+          .as_ref()
+          .is_some_and(|multiplex| multiplex.is_detached());
+
+        if detached {
+          log::info!(
+            "multiplexed connection {:?} disconnected",
+            self.ssh_session_id
+          );
+          ssh_manager
+            .session_resources_close(self.ssh_session_id)
+            .await;
+        } else if let client::DisconnectReason::Error(error) = &reason
+          && let Some(session_reconnect) = session
+            .reconnect
+            .clone()
+            .filter(|session_reconnect| session_reconnect.is_ready())
+        {
+          async_runtime::spawn(reconnect::reconnect(
+            self.app_handle.clone(),
+            self.ssh_session_id,
+            session.ipc_channel.clone(),
+            session_reconnect,
+            session.multiplex.clone(),
+            error.to_string(),
+          ));
+        } else {
+          ssh_manager
+            .session_resources_close(self.ssh_session_id)
+            .await;
+
+          // A jump host reports to the session tunneled through it, which
+          // drops along with it and reports or reconnects on its own
+          if session.jump_host_key.is_some() {
+            log::info!("jump host session {:?} disconnected", self.ssh_session_id);
+          } else {
             session
               .ipc_channel
-              .send(SessionIpcChannelData::Disconnect(DisconnectReason::Server))?;
-          }
-          client::DisconnectReason::Error(error) => {
-            session.ipc_channel.send(SessionIpcChannelData::Disconnect(
-              DisconnectReason::Error(error.to_string()),
-            ))?;
-            return Err(error);
+              .send(SessionIpcChannelData::Disconnect(disconnect_reason))?;
           }
         }
+
+        if let client::DisconnectReason::Error(error) = reason {
+          return Err(error);
+        }
       }
       Ok(())
     }
@@ -228,21 +357,135 @@ fn disconnected(
 }
 
 impl<R: Runtime> SSHClient<R> {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     app_handle: AppHandle<R>,
     ssh_session_id: SSHSessionId,
+    ipc_channel: Channel<SessionIpcChannelData>,
     hostname: String,
     port: u16,
-    jump_host_ssh_session_id: Option<SSHSessionId>,
     check_server_key: Option<SSHSessionCheckServerKey>,
+    strict_host_key_checking: SSHSessionStrictHostKeyChecking,
+    diagnostics: Arc<SSHSessionDiagnostics>,
   ) -> Self {
     SSHClient {
       app_handle,
       ssh_session_id,
+      ipc_channel,
       hostname,
       port,
-      jump_host_ssh_session_id,
       check_server_key,
+      strict_host_key_checking,
+      diagnostics,
+      key_exchanges: 0,
+    }
+  }
+
+  /// A jump host shared by several chains reports to every session tunneled
+  /// through it, a registered session through `SSHSession::event`, until then
+  /// to the session it is connected for
+  fn send(&self, data: SessionIpcChannelData) {
+    let ssh_manager = self.ssh_manager();
+    let mut ipc_channels = ssh_manager.jump_host_ipc_channels(self.ssh_session_id);
+    if ipc_channels.is_empty() {
+      if let Some(session) = ssh_manager.sessions.get(&self.ssh_session_id) {
+        session.event(data);
+        return;
+      }
+      ipc_channels.push(self.ipc_channel.clone());
+    }
+
+    for ipc_channel in ipc_channels {
+      if let Err(err) = ipc_channel.send(data.clone()) {
+        log::warn!(
+          "session event {:?} send failed: {}",
+          self.ssh_session_id,
+          err
+        );
+      }
+    }
+  }
+
+  fn verify_server_key(&self, server_public_key: &PublicKey) -> SSHResult<bool> {
+    let known_hosts_path = get_known_hosts_path(&self.app_handle)?;
+    let strict_host_key_checking = self.strict_host_key_checking;
+
+    match known_hosts::check(
+      &known_hosts_path,
+      &self.hostname,
+      self.port,
+      server_public_key,
+    )? {
+      KnownHostsCheck::Match => Ok(true),
+      KnownHostsCheck::Revoked => Err(SSHError::HostKeyRevoked {
+        algorithm: server_public_key.algorithm().to_string(),
+        fingerprint: server_public_key.fingerprint(HashAlg::Sha256),
+      }),
+      KnownHostsCheck::Changed(entry) => {
+        let accept = match (strict_host_key_checking, &self.check_server_key) {
+          (SSHSessionStrictHostKeyChecking::Off, _) => Some(false),
+          (SSHSessionStrictHostKeyChecking::Yes, _)
+          | (SSHSessionStrictHostKeyChecking::AcceptNew, _) => None,
+          (_, Some(SSHSessionCheckServerKey::Continue)) => Some(false),
+          (_, Some(SSHSessionCheckServerKey::ReplaceAndContinue)) => Some(true),
+          (_, _) => None,
+        };
+
+        match accept {
+          Some(replace) => {
+            log::warn!(
+              "{}:{} host key has changed, line {} of known_hosts",
+              self.hostname,
+              self.port,
+              entry.line
+            );
+            if replace {
+              known_hosts::replace(
+                &known_hosts_path,
+                &self.hostname,
+                self.port,
+                server_public_key,
+              )?;
+            }
+            Ok(true)
+          }
+          None => Err(SSHError::HostKeyChanged {
+            algorithm: server_public_key.algorithm().to_string(),
+            old_fingerprint: entry.fingerprint,
+            new_fingerprint: server_public_key.fingerprint(HashAlg::Sha256),
+            line: entry.line,
+          }),
+        }
+      }
+      KnownHostsCheck::Unknown => {
+        let accept = match (strict_host_key_checking, &self.check_server_key) {
+          (SSHSessionStrictHostKeyChecking::Off, _)
+          | (SSHSessionStrictHostKeyChecking::AcceptNew, _) => Some(true),
+          (SSHSessionStrictHostKeyChecking::Yes, _) => None,
+          (_, Some(SSHSessionCheckServerKey::Continue)) => Some(false),
+          (_, Some(SSHSessionCheckServerKey::AddAndContinue))
+          | (_, Some(SSHSessionCheckServerKey::ReplaceAndContinue)) => Some(true),
+          (_, None) => None,
+        };
+
+        match accept {
+          Some(learn) => {
+            if learn {
+              known_hosts::learn(
+                &known_hosts_path,
+                &self.hostname,
+                self.port,
+                server_public_key,
+              )?;
+            }
+            Ok(true)
+          }
+          None => Err(SSHError::UnknownKey {
+            algorithm: server_public_key.algorithm().to_string(),
+            fingerprint: server_public_key.fingerprint(HashAlg::Sha256),
+          }),
+        }
+      }
     }
   }
 
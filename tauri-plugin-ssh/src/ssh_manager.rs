@@ -1,38 +1,232 @@
-use std::collections::HashMap;
+use std::{
+  collections::{HashMap, HashSet},
+  hash::Hash,
+  sync::{Arc, PoisonError, RwLock, atomic::Ordering},
+};
 
-use russh::ChannelId;
-use tauri::Runtime;
-use tokio::sync::Mutex;
+use russh::{ChannelId, Disconnect};
+use tauri::{Runtime, ipc::Channel};
+use tokio::sync::{Mutex, Notify};
 
 use crate::{
   SSHResult,
   commands::{
+    exec::{SSHExec, SSHExecId},
     port_forwarding::{SSHPortForwarding, SSHPortForwardingId},
-    session::{SSHSession, SSHSessionId},
+    session::{SSHChannelKind, SSHJumpHostKey, SSHSession, SSHSessionId, SessionIpcChannelData},
     sftp::{SSHSftp, SSHSftpId, SSHSftpIpcChannelData},
     shell::{SHHShellIpcChannelData, SSHShell, SSHShellId},
   },
+  ssh_agent::SSHAgentForwarder,
+  x11_forwarding::SSHX11Forwarder,
 };
 
-pub type Sessions<R> = Mutex<HashMap<SSHSessionId, SSHSession<R>>>;
-pub type Shells = Mutex<HashMap<SSHShellId, SSHShell>>;
-pub type SftpChannels = Mutex<HashMap<SSHSftpId, SSHSftp>>;
-pub type PortForwardings = Mutex<HashMap<SSHPortForwardingId, SSHPortForwarding>>;
+/// A map handing out `Arc`s of its entries.
+///
+/// The lock is only held while the map itself is read or changed and never
+/// across an await, so slow work on one entry, like an SFTP round-trip, never
+/// blocks lookups of the others.
+pub struct ArcMap<K, V> {
+  entries: RwLock<HashMap<K, Arc<V>>>,
+}
+
+impl<K, V> Default for ArcMap<K, V> {
+  fn default() -> Self {
+    Self {
+      entries: RwLock::default(),
+    }
+  }
+}
+
+impl<K: Eq + Hash, V> ArcMap<K, V> {
+  pub fn get(&self, key: &K) -> Option<Arc<V>> {
+    let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+    entries.get(key).cloned()
+  }
+
+  pub fn contains_key(&self, key: &K) -> bool {
+    let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+    entries.contains_key(key)
+  }
+
+  pub fn insert(&self, key: K, value: Arc<V>) -> Option<Arc<V>> {
+    let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+    entries.insert(key, value)
+  }
+
+  pub fn remove(&self, key: &K) -> Option<Arc<V>> {
+    let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+    entries.remove(key)
+  }
+
+  pub fn values(&self) -> Vec<Arc<V>> {
+    let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+    entries.values().cloned().collect()
+  }
+
+  /// Runs `f` with the map write locked, for changes that must be atomic
+  /// with respect to other lookups. `f` must not block.
+  pub fn update<T>(&self, f: impl FnOnce(&mut HashMap<K, Arc<V>>) -> T) -> T {
+    let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+    f(&mut entries)
+  }
+}
+
+pub type Sessions<R> = ArcMap<SSHSessionId, SSHSession<R>>;
+pub type Shells = ArcMap<SSHShellId, SSHShell>;
+pub type SftpChannels = ArcMap<SSHSftpId, SSHSftp>;
+/// The channel the server sends data on maps straight to its shell or sftp
+pub type ChannelSinks<T> = ArcMap<(SSHSessionId, ChannelId), T>;
+pub type PortForwardings = ArcMap<SSHPortForwardingId, SSHPortForwarding>;
+pub type Execs = ArcMap<SSHExecId, SSHExec>;
+pub type AgentForwarders = ArcMap<SSHSessionId, SSHAgentForwarder>;
+pub type X11Forwarders = ArcMap<SSHSessionId, SSHX11Forwarder>;
+/// Cancels the reconnect of a session when notified
+pub type Reconnecting = ArcMap<SSHSessionId, Notify>;
+pub type BroadcastGroups = Mutex<HashMap<String, HashSet<SSHShellId>>>;
+/// Held while a jump host session is opened, so that chains through the same
+/// hop wait for it instead of opening their own
+pub type JumpHostLocks = ArcMap<SSHJumpHostKey, Mutex<()>>;
 
 pub struct SSHManager<R: Runtime> {
   pub sessions: Sessions<R>,
   pub shells: Shells,
+  pub shell_sinks: ChannelSinks<SSHShell>,
   pub sftps: SftpChannels,
+  pub sftp_sinks: ChannelSinks<SSHSftp>,
   pub port_forwardings: PortForwardings,
+  pub execs: Execs,
+  pub agent_forwarders: AgentForwarders,
+  pub x11_forwarders: X11Forwarders,
+  /// Sessions that dropped and are being connected again
+  pub reconnecting: Reconnecting,
+  /// Named sets of shells that `shell_broadcast` can target
+  pub broadcast_groups: BroadcastGroups,
+  pub jump_host_locks: JumpHostLocks,
 }
 
 impl<R: Runtime> SSHManager<R> {
   pub fn init() -> Self {
     Self {
-      sessions: Mutex::default(),
-      shells: Mutex::default(),
-      sftps: Mutex::default(),
-      port_forwardings: Mutex::default(),
+      sessions: ArcMap::default(),
+      shells: ArcMap::default(),
+      shell_sinks: ArcMap::default(),
+      sftps: ArcMap::default(),
+      sftp_sinks: ArcMap::default(),
+      port_forwardings: ArcMap::default(),
+      execs: ArcMap::default(),
+      agent_forwarders: ArcMap::default(),
+      x11_forwarders: ArcMap::default(),
+      reconnecting: ArcMap::default(),
+      broadcast_groups: Mutex::default(),
+      jump_host_locks: ArcMap::default(),
+    }
+  }
+
+  /// Reports an event on the channel of a session, dropped while the session
+  /// is reconnecting
+  pub fn session_event(&self, ssh_session_id: SSHSessionId, data: SessionIpcChannelData) {
+    if let Some(session) = self.sessions.get(&ssh_session_id) {
+      session.event(data);
+    }
+  }
+
+  /// Reports a channel opened on a session
+  pub fn channel_opened(
+    &self,
+    ssh_session_id: SSHSessionId,
+    channel_id: ChannelId,
+    kind: SSHChannelKind,
+  ) {
+    self.session_event(
+      ssh_session_id,
+      SessionIpcChannelData::ChannelOpened {
+        channel_id: channel_id.into(),
+        kind,
+      },
+    );
+  }
+
+  /// Channels of the sessions tunneled through a jump host session, directly
+  /// or through further hops
+  pub fn jump_host_ipc_channels(
+    &self,
+    ssh_session_id: SSHSessionId,
+  ) -> Vec<Channel<SessionIpcChannelData>> {
+    let sessions = self.sessions.values();
+    let mut ipc_channels = Vec::new();
+    let mut jump_host_ssh_session_ids = vec![ssh_session_id];
+
+    while let Some(jump_host_ssh_session_id) = jump_host_ssh_session_ids.pop() {
+      for session in sessions
+        .iter()
+        .filter(|session| session.jump_host_ssh_session_id == Some(jump_host_ssh_session_id))
+      {
+        if session.jump_host_key.is_some() {
+          jump_host_ssh_session_ids.push(session.ssh_session_id);
+        } else {
+          ipc_channels.push(session.ipc_channel.clone());
+        }
+      }
+    }
+
+    ipc_channels
+  }
+
+  /// Removes a session and releases the jump host session it was tunneled through
+  pub async fn session_remove(&self, ssh_session_id: SSHSessionId) -> Option<Arc<SSHSession<R>>> {
+    let session = self.sessions.remove(&ssh_session_id)?;
+    self.session_removed(&session).await;
+
+    Some(session)
+  }
+
+  /// Cleans up after a session taken out of `sessions`
+  pub async fn session_removed(&self, session: &SSHSession<R>) {
+    self.agent_forwarders.remove(&session.ssh_session_id);
+    self.x11_forwarders.remove(&session.ssh_session_id);
+
+    if let Some(jump_host_ssh_session_id) = session.jump_host_ssh_session_id {
+      self.jump_host_release(jump_host_ssh_session_id).await;
+    }
+  }
+
+  /// Drops one reference to a jump host session, the session is disconnected
+  /// when its last dependent goes away and the release cascades up the chain.
+  pub async fn jump_host_release(&self, ssh_session_id: SSHSessionId) {
+    let mut next_ssh_session_id = Some(ssh_session_id);
+
+    while let Some(ssh_session_id) = next_ssh_session_id.take() {
+      let session = self.sessions.update(|sessions| {
+        let session = sessions.get(&ssh_session_id)?;
+
+        let dependents = session.dependents.load(Ordering::SeqCst).saturating_sub(1);
+        session.dependents.store(dependents, Ordering::SeqCst);
+        if dependents > 0 {
+          return None;
+        }
+
+        sessions.remove(&ssh_session_id)
+      });
+
+      if let Some(session) = session {
+        log::info!("release jump host session {:?}", ssh_session_id);
+        self.agent_forwarders.remove(&ssh_session_id);
+        self.x11_forwarders.remove(&ssh_session_id);
+        if let Err(err) = session
+          .handle()
+          .await
+          .disconnect(Disconnect::ByApplication, "", "English")
+          .await
+        {
+          log::error!(
+            "disconnect jump host session {:?} failed: {}",
+            ssh_session_id,
+            err
+          );
+        }
+        next_ssh_session_id = session.jump_host_ssh_session_id;
+      }
     }
   }
 
@@ -42,38 +236,99 @@ pub async fn shell_channel_data(
     channel_id: ChannelId,
     data: &[u8],
   ) -> SSHResult<bool> {
-    let shells = self.shells.lock().await;
+    let Some(shell) = self.shell_sinks.get(&(ssh_session_id, channel_id)) else {
+      return Ok(false);
+    };
 
-    let mut count = 0;
+    shell.output.send_data(data.to_vec()).await;
+    shell.record(|recorder| recorder.output(data));
+    shell.script_output(data);
 
-    for shell in shells.values() {
-      if shell.ssh_session_id == ssh_session_id && shell.id() == channel_id {
-        count += 1;
-        shell
-          .ipc_channel
-          .send(SHHShellIpcChannelData::Data(data.to_vec()))?;
-      }
+    Ok(true)
+  }
+
+  /// Sends `data` to the shell bound to `channel_id`, returns whether a shell
+  /// matched
+  async fn shell_channel_send(
+    &self,
+    ssh_session_id: SSHSessionId,
+    channel_id: ChannelId,
+    data: SHHShellIpcChannelData,
+  ) -> SSHResult<bool> {
+    let Some(shell) = self.shell_sinks.get(&(ssh_session_id, channel_id)) else {
+      return Ok(false);
+    };
+
+    shell.output.send(data);
+
+    Ok(true)
+  }
+
+  pub async fn shell_channel_extended_data(
+    &self,
+    ssh_session_id: SSHSessionId,
+    channel_id: ChannelId,
+    ext: u32,
+    data: &[u8],
+  ) -> SSHResult<bool> {
+    // Only SSH_EXTENDED_DATA_STDERR is defined by RFC 4254
+    if ext != 1 {
+      return Ok(false);
     }
 
-    Ok(count > 0)
+    self
+      .shell_channel_send(
+        ssh_session_id,
+        channel_id,
+        SHHShellIpcChannelData::Stderr(data.to_vec()),
+      )
+      .await
   }
 
-  pub async fn shell_channel_eof(
+  pub async fn shell_channel_exit_status(
     &self,
     ssh_session_id: SSHSessionId,
     channel_id: ChannelId,
+    exit_status: u32,
   ) -> SSHResult<bool> {
-    let shells = self.shells.lock().await;
+    self
+      .shell_channel_send(
+        ssh_session_id,
+        channel_id,
+        SHHShellIpcChannelData::ExitStatus(exit_status),
+      )
+      .await
+  }
 
-    let mut count = 0;
-    for shell in shells.values() {
-      if shell.ssh_session_id == ssh_session_id && shell.id() == channel_id {
-        count += 1;
-        shell.ipc_channel.send(SHHShellIpcChannelData::Eof)?;
-      }
-    }
+  pub async fn shell_channel_exit_signal(
+    &self,
+    ssh_session_id: SSHSessionId,
+    channel_id: ChannelId,
+    signal_name: String,
+    core_dumped: bool,
+    error_message: String,
+  ) -> SSHResult<bool> {
+    self
+      .shell_channel_send(
+        ssh_session_id,
+        channel_id,
+        SHHShellIpcChannelData::ExitSignal {
+          signal_name,
+          core_dumped,
+          error_message,
+        },
+      )
+      .await
+  }
 
-    Ok(count > 0)
+  pub async fn shell_channel_eof(
+    &self,
+    ssh_session_id: SSHSessionId,
+    channel_id: ChannelId,
+  ) -> SSHResult<bool> {
+    self
+      .shell_channel_send(ssh_session_id, channel_id, SHHShellIpcChannelData::Eof)
+      .await
   }
 
   pub async fn shell_channel_close(
@@ -81,19 +336,32 @@ pub async fn shell_channel_close(
     ssh_session_id: SSHSessionId,
     channel_id: ChannelId,
   ) -> SSHResult<bool> {
-    let mut shells = self.shells.lock().await;
+    let Some(shell) = self.shell_sinks.remove(&(ssh_session_id, channel_id)) else {
+      return Ok(false);
+    };
+    self.shell_close(&shell).await;
 
-    let extracted = shells.extract_if(|_shell_id, shell| {
-      shell.ssh_session_id == ssh_session_id && shell.id() == channel_id
-    });
+    Ok(true)
+  }
 
-    let mut count = 0;
-    for (_shell_id, shell) in extracted {
-      count += 1;
-      shell.ipc_channel.send(SHHShellIpcChannelData::Close)?;
+  /// Forgets a shell whose sink is already removed and sends it `Close`
+  async fn shell_close(&self, shell: &SSHShell) {
+    self.shells.remove(&shell.ssh_shell_id);
+
+    {
+      let mut broadcast_groups = self.broadcast_groups.lock().await;
+      broadcast_groups.retain(|_group, members| {
+        members.remove(&shell.ssh_shell_id);
+        !members.is_empty()
+      });
     }
 
-    Ok(count > 0)
+    if let Some(recorder) = shell.take_recorder()
+      && let Err(err) = recorder.finish()
+    {
+      log::error!("shell recording finish failed: {}", err);
+    }
+    shell.output.send(SHHShellIpcChannelData::Close);
   }
 
   pub async fn sftp_channel_eof(
@@ -101,17 +369,13 @@ pub async fn sftp_channel_eof(
     ssh_session_id: SSHSessionId,
     channel_id: ChannelId,
   ) -> SSHResult<bool> {
-    let sftps = self.sftps.lock().await;
+    let Some(sftp) = self.sftp_sinks.get(&(ssh_session_id, channel_id)) else {
+      return Ok(false);
+    };
 
-    let mut count = 0;
-    for sftp in sftps.values() {
-      if sftp.ssh_session_id == ssh_session_id && sftp.sftp_channel_id == channel_id {
-        count += 1;
-        sftp.ipc_channel.send(SSHSftpIpcChannelData::Eof)?;
-      }
-    }
+    sftp.ipc_channel.send(SSHSftpIpcChannelData::Eof)?;
 
-    Ok(count > 0)
+    Ok(true)
   }
 
   pub async fn sftp_channel_close(
@@ -119,18 +383,91 @@ pub async fn sftp_channel_close(
     ssh_session_id: SSHSessionId,
     channel_id: ChannelId,
   ) -> SSHResult<bool> {
-    let mut sftps = self.sftps.lock().await;
+    let Some(sftp) = self.sftp_sinks.remove(&(ssh_session_id, channel_id)) else {
+      return Ok(false);
+    };
+    self.sftps.remove(&sftp.ssh_sftp_id);
+
+    sftp.ipc_channel.send(SSHSftpIpcChannelData::Close)?;
+
+    Ok(true)
+  }
+
+  /// Drops the sftps and execs of a session whose connection is gone, each
+  /// gets `Close` on its IPC channel. Their channel ids would collide with
+  /// the channels of a new connection.
+  pub async fn session_channels_close(&self, ssh_session_id: SSHSessionId) {
+    let sftps = self
+      .sftps
+      .values()
+      .into_iter()
+      .filter(|sftp| sftp.ssh_session_id == ssh_session_id);
+    for sftp in sftps {
+      log::info!(
+        "session {:?} close sftp {:?}",
+        ssh_session_id,
+        sftp.ssh_sftp_id
+      );
+      self
+        .sftp_sinks
+        .remove(&(sftp.connection_id, sftp.sftp_channel_id));
+      self.sftps.remove(&sftp.ssh_sftp_id);
+      if let Err(err) = sftp.ipc_channel.send(SSHSftpIpcChannelData::Close) {
+        log::error!("sftp close send failed: {}", err);
+      }
+    }
 
-    let extracted = sftps.extract_if(|_sftp_id, sftp| {
-      sftp.ssh_session_id == ssh_session_id && sftp.sftp_channel_id == channel_id
+    let execs = self.execs.update(|execs| {
+      let mut removed = Vec::new();
+      execs.retain(|_, exec| {
+        if exec.ssh_session_id == ssh_session_id {
+          removed.push(exec.clone());
+          return false;
+        }
+        true
+      });
+      removed
     });
+    for exec in execs {
+      log::info!("session {:?} close exec", ssh_session_id);
+      exec.notify.notify_one();
+    }
+  }
 
-    let mut count = 0;
-    for (_sftp_id, sftp) in extracted {
-      count += 1;
-      sftp.ipc_channel.send(SSHSftpIpcChannelData::Close)?;
+  /// Closes everything opened on a session that ended for good, each gets
+  /// `Close` on its IPC channel
+  pub async fn session_resources_close(&self, ssh_session_id: SSHSessionId) {
+    let shells = self
+      .shells
+      .values()
+      .into_iter()
+      .filter(|shell| shell.ssh_session_id == ssh_session_id);
+    for shell in shells {
+      log::info!(
+        "session {:?} close shell {:?}",
+        ssh_session_id,
+        shell.ssh_shell_id
+      );
+      self.shell_sinks.remove(&(shell.connection_id, shell.id()));
+      self.shell_close(&shell).await;
     }
 
-    Ok(count > 0)
+    self.session_channels_close(ssh_session_id).await;
+
+    let port_forwardings = self.port_forwardings.update(|port_forwardings| {
+      let mut removed = Vec::new();
+      port_forwardings.retain(|_, port_forwarding| {
+        if port_forwarding.ssh_session_id() == ssh_session_id {
+          removed.push(port_forwarding.clone());
+          return false;
+        }
+        true
+      });
+      removed
+    });
+    for port_forwarding in port_forwardings {
+      log::info!("session {:?} close port forwarding", ssh_session_id);
+      port_forwarding.close();
+    }
   }
 }
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::{
+  sync::mpsc::UnboundedReceiver,
+  time::{sleep, timeout},
+};
+
+use crate::{
+  commands::shell::{SHHShellIpcChannelData, SSHShellId},
+  error::{SSHError, SSHResult},
+  ssh_manager::SSHManager,
+};
+
+/// Output kept for `Expect`, older output is dropped
+const EXPECT_BUFFER_BYTES: usize = 64 * 1024;
+const EXPECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum SSHShellStartupStep {
+  /// Sends `data` as if it was typed
+  Send {
+    data: String,
+  },
+  /// Sends `secret` followed by a newline, the secret is never logged and is
+  /// redacted in recordings
+  SendSecret {
+    secret: String,
+  },
+  /// Waits until the output since the previous match matches `pattern`
+  Expect {
+    pattern: String,
+    timeout_secs: Option<u64>,
+  },
+  Sleep {
+    millis: u64,
+  },
+}
+
+enum Step {
+  Send(String),
+  SendSecret(String),
+  Expect(Regex, Duration),
+  Sleep(Duration),
+}
+
+/// A startup script checked up front, so that an invalid pattern fails
+/// `shell_open` instead of the script
+pub struct SSHShellStartupScript {
+  steps: Vec<Step>,
+}
+
+impl SSHShellStartupScript {
+  pub fn new(steps: Vec<SSHShellStartupStep>) -> SSHResult<Self> {
+    let steps = steps
+      .into_iter()
+      .map(|step| {
+        Ok(match step {
+          SSHShellStartupStep::Send { data } => Step::Send(data),
+          SSHShellStartupStep::SendSecret { secret } => Step::SendSecret(secret),
+          SSHShellStartupStep::Expect {
+            pattern,
+            timeout_secs,
+          } => Step::Expect(
+            Regex::new(&pattern)?,
+            timeout_secs
+              .map(Duration::from_secs)
+              .unwrap_or(EXPECT_TIMEOUT),
+          ),
+          SSHShellStartupStep::Sleep { millis } => Step::Sleep(Duration::from_millis(millis)),
+        })
+      })
+      .collect::<SSHResult<Vec<Step>>>()?;
+
+    Ok(Self { steps })
+  }
+
+  /// Runs the steps in order, the first failing step stops the script and is
+  /// reported to the frontend
+  pub async fn run<R: Runtime>(
+    self,
+    app_handle: AppHandle<R>,
+    ssh_shell_id: SSHShellId,
+    mut output: UnboundedReceiver<Vec<u8>>,
+  ) {
+    let mut buffer = String::new();
+
+    for (index, step) in self.steps.into_iter().enumerate() {
+      let result = run_step(&app_handle, ssh_shell_id, step, &mut output, &mut buffer).await;
+      if let Err(err) = result {
+        log::warn!(
+          "shell startup script {:?} failed at step {}: {}",
+          ssh_shell_id,
+          index,
+          err
+        );
+        if let Some(shell) = app_handle
+          .state::<SSHManager<R>>()
+          .shells
+          .get(&ssh_shell_id)
+        {
+          shell
+            .output
+            .send(SHHShellIpcChannelData::StartupScriptFailed {
+              step: index,
+              message: err.to_string(),
+            });
+        }
+        break;
+      }
+    }
+
+    if let Some(shell) = app_handle
+      .state::<SSHManager<R>>()
+      .shells
+      .get(&ssh_shell_id)
+    {
+      shell.set_script_output(None);
+    }
+    log::info!("shell startup script {:?} finished", ssh_shell_id);
+  }
+}
+
+async fn run_step<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  ssh_shell_id: SSHShellId,
+  step: Step,
+  output: &mut UnboundedReceiver<Vec<u8>>,
+  buffer: &mut String,
+) -> SSHResult<()> {
+  match step {
+    Step::Send(data) => {
+      let shell = app_handle
+        .state::<SSHManager<R>>()
+        .shells
+        .get(&ssh_shell_id)
+        .ok_or(SSHError::NotFoundShell)?;
+      shell.data(data.as_bytes()).await?;
+      shell.record(|recorder| recorder.input(&data));
+    }
+    Step::SendSecret(secret) => {
+      let shell = app_handle
+        .state::<SSHManager<R>>()
+        .shells
+        .get(&ssh_shell_id)
+        .ok_or(SSHError::NotFoundShell)?;
+      shell.data(format!("{}\n", secret).as_bytes()).await?;
+      let redacted = format!("{}\n", "*".repeat(secret.chars().count()));
+      shell.record(|recorder| recorder.input(&redacted));
+    }
+    Step::Expect(regex, duration) => {
+      timeout(duration, async {
+        loop {
+          if let Some(found) = regex.find(buffer) {
+            buffer.drain(..found.end());
+            return Ok(());
+          }
+
+          let data = output.recv().await.ok_or(SSHError::NotFoundShell)?;
+          buffer.push_str(&String::from_utf8_lossy(&data));
+          if buffer.len() > EXPECT_BUFFER_BYTES {
+            let mut start = buffer.len() - EXPECT_BUFFER_BYTES;
+            while !buffer.is_char_boundary(start) {
+              start += 1;
+            }
+            buffer.drain(..start);
+          }
+        }
+      })
+      .await
+      .map_err(|_| SSHError::new(format!("Timed out waiting for {}", regex.as_str())))??;
+    }
+    Step::Sleep(duration) => sleep(duration).await,
+  }
+
+  Ok(())
+}
@@ -0,0 +1,283 @@
+use std::{
+  fs,
+  net::{IpAddr, Ipv4Addr},
+};
+
+use data_encoding::BASE64;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::TcpStream,
+};
+
+use crate::{
+  error::{SSHError, SSHResult},
+  utils::get_upstream_proxy_path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SSHUpstreamProxyType {
+  /// SOCKS4a, hostnames are resolved by the proxy
+  Socks4,
+  Socks5,
+  /// HTTP CONNECT
+  Http,
+}
+
+/// A proxy the TCP connection to the SSH server is tunneled through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHUpstreamProxy {
+  pub r#type: SSHUpstreamProxyType,
+  pub hostname: String,
+  pub port: u16,
+  pub username: Option<String>,
+  pub password: Option<String>,
+}
+
+impl SSHUpstreamProxy {
+  fn error<T: ToString>(&self, message: T) -> SSHError {
+    SSHError::UpstreamProxyFailed(
+      format!("{:?} {}:{}", self.r#type, self.hostname, self.port),
+      message.to_string(),
+    )
+  }
+
+  fn credentials(&self) -> Option<(&str, &str)> {
+    let username = self.username.as_deref().filter(|v| !v.is_empty())?;
+
+    Some((username, self.password.as_deref().unwrap_or_default()))
+  }
+
+  /// Connects to the proxy and asks it to open a tunnel to `hostname:port`
+  pub async fn connect(&self, hostname: &str, port: u16) -> SSHResult<TcpStream> {
+    log::info!(
+      "upstream proxy {:?} {}:{} connect to {}:{}",
+      self.r#type,
+      self.hostname,
+      self.port,
+      hostname,
+      port
+    );
+
+    let mut stream = TcpStream::connect((self.hostname.as_str(), self.port))
+      .await
+      .map_err(|err| self.error(err))?;
+    stream.set_nodelay(true)?;
+
+    let result = match self.r#type {
+      SSHUpstreamProxyType::Socks4 => self.socks4_handshake(&mut stream, hostname, port).await,
+      SSHUpstreamProxyType::Socks5 => self.socks5_handshake(&mut stream, hostname, port).await,
+      SSHUpstreamProxyType::Http => self.http_handshake(&mut stream, hostname, port).await,
+    };
+
+    match result {
+      Ok(()) => Ok(stream),
+      Err(SSHError::StdIoError(err)) => Err(self.error(err)),
+      Err(err) => Err(err),
+    }
+  }
+
+  async fn socks4_handshake(
+    &self,
+    stream: &mut TcpStream,
+    hostname: &str,
+    port: u16,
+  ) -> SSHResult<()> {
+    let (ip, domain) = match hostname.parse::<IpAddr>() {
+      Ok(IpAddr::V4(ip)) => (ip, None),
+      Ok(IpAddr::V6(_)) => return Err(self.error("SOCKS4 does not support IPv6")),
+      // SOCKS4a, an invalid IP 0.0.0.x tells the proxy to resolve the domain
+      Err(_) => (Ipv4Addr::new(0, 0, 0, 1), Some(hostname)),
+    };
+
+    let mut request = vec![0x04, 0x01];
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&ip.octets());
+    request.extend_from_slice(self.username.as_deref().unwrap_or_default().as_bytes());
+    request.push(0x00);
+    if let Some(domain) = domain {
+      request.extend_from_slice(domain.as_bytes());
+      request.push(0x00);
+    }
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 8];
+    stream.read_exact(&mut reply).await?;
+
+    match reply[1] {
+      0x5a => Ok(()),
+      0x5b => Err(self.error("request rejected or failed")),
+      0x5c => Err(self.error("identd is not reachable")),
+      0x5d => Err(self.error("identd user id mismatch")),
+      code => Err(self.error(format!("unknown reply 0x{:02x}", code))),
+    }
+  }
+
+  async fn socks5_handshake(
+    &self,
+    stream: &mut TcpStream,
+    hostname: &str,
+    port: u16,
+  ) -> SSHResult<()> {
+    let credentials = self.credentials();
+
+    let methods: &[u8] = if credentials.is_some() {
+      &[0x00, 0x02]
+    } else {
+      &[0x00]
+    };
+    let mut greeting = vec![0x05, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != 0x05 {
+      return Err(self.error("not a SOCKS5 proxy"));
+    }
+
+    match (reply[1], credentials) {
+      (0x00, _) => {}
+      (0x02, Some((username, password))) => {
+        if username.len() > 255 || password.len() > 255 {
+          return Err(self.error("username or password is too long"));
+        }
+
+        let mut auth = vec![0x01, username.len() as u8];
+        auth.extend_from_slice(username.as_bytes());
+        auth.push(password.len() as u8);
+        auth.extend_from_slice(password.as_bytes());
+        stream.write_all(&auth).await?;
+
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+          return Err(self.error("authentication failed"));
+        }
+      }
+      _ => return Err(self.error("no acceptable authentication method")),
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    match hostname.parse::<IpAddr>() {
+      Ok(IpAddr::V4(ip)) => {
+        request.push(0x01);
+        request.extend_from_slice(&ip.octets());
+      }
+      Ok(IpAddr::V6(ip)) => {
+        request.push(0x04);
+        request.extend_from_slice(&ip.octets());
+      }
+      Err(_) => {
+        if hostname.len() > 255 {
+          return Err(self.error("hostname is too long"));
+        }
+        request.push(0x03);
+        request.push(hostname.len() as u8);
+        request.extend_from_slice(hostname.as_bytes());
+      }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+      let message = match reply[1] {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+      };
+      return Err(self.error(message));
+    }
+
+    // Skip the bound address and port
+    let address_len = match reply[3] {
+      0x01 => 4,
+      0x04 => 16,
+      0x03 => stream.read_u8().await? as usize,
+      _ => return Err(self.error("invalid bound address type")),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+  }
+
+  async fn http_handshake(
+    &self,
+    stream: &mut TcpStream,
+    hostname: &str,
+    port: u16,
+  ) -> SSHResult<()> {
+    let authority = match hostname.parse::<IpAddr>() {
+      Ok(IpAddr::V6(_)) => format!("[{}]:{}", hostname, port),
+      _ => format!("{}:{}", hostname, port),
+    };
+
+    let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
+    if let Some((username, password)) = self.credentials() {
+      let credentials = BASE64.encode(format!("{}:{}", username, password).as_bytes());
+      request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read byte by byte so that nothing after the headers, such as the SSH
+    // banner, is consumed
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+      if response.len() > 8192 {
+        return Err(self.error("response headers are too long"));
+      }
+      response.push(stream.read_u8().await?);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+
+    if status == "200" {
+      Ok(())
+    } else {
+      Err(self.error(status_line))
+    }
+  }
+}
+
+/// The upstream proxy used by hosts that do not configure their own
+pub fn load_global_upstream_proxy<R: Runtime>(
+  app_handle: &AppHandle<R>,
+) -> SSHResult<Option<SSHUpstreamProxy>> {
+  let path = get_upstream_proxy_path(app_handle)?;
+  if !path.exists() {
+    return Ok(None);
+  }
+
+  let content = fs::read_to_string(path)?;
+  Ok(Some(serde_json::from_str(&content)?))
+}
+
+/// Saves the upstream proxy used by hosts without their own, `None` removes it
+pub fn save_global_upstream_proxy<R: Runtime>(
+  app_handle: &AppHandle<R>,
+  upstream_proxy: Option<&SSHUpstreamProxy>,
+) -> SSHResult<()> {
+  let path = get_upstream_proxy_path(app_handle)?;
+
+  match upstream_proxy {
+    Some(upstream_proxy) => fs::write(path, serde_json::to_string(upstream_proxy)?)?,
+    None if path.exists() => fs::remove_file(path)?,
+    None => {}
+  }
+
+  Ok(())
+}
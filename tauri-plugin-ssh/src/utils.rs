@@ -3,10 +3,16 @@
   path::PathBuf,
 };
 
+use russh::{
+  MethodKind, Sig,
+  keys::agent::client::{AgentClient, AgentStream},
+};
 use tauri::{AppHandle, Manager, Runtime};
 
 use crate::error::SSHResult;
 
+pub type SSHAgentClient = AgentClient<Box<dyn AgentStream + Send + Unpin + 'static>>;
+
 pub fn get_known_hosts_path<R: Runtime>(app_handle: &AppHandle<R>) -> SSHResult<PathBuf> {
   let app_local_data_dir = app_handle.path().app_local_data_dir()?;
 
@@ -22,3 +28,97 @@ pub fn get_known_hosts_path<R: Runtime>(app_handle: &AppHandle<R>) -> SSHResult<
 
   Ok(known_hosts_path)
 }
+
+/// Where the upstream proxy of hosts without their own is kept, the file only
+/// exists while one is set
+pub fn get_upstream_proxy_path<R: Runtime>(app_handle: &AppHandle<R>) -> SSHResult<PathBuf> {
+  let app_local_data_dir = app_handle.path().app_local_data_dir()?;
+
+  if !app_local_data_dir.exists() {
+    fs::create_dir_all(&app_local_data_dir)?;
+  }
+
+  Ok(app_local_data_dir.join("upstream_proxy.json"))
+}
+
+pub fn get_recordings_dir<R: Runtime>(app_handle: &AppHandle<R>) -> SSHResult<PathBuf> {
+  let recordings_dir = app_handle.path().app_local_data_dir()?.join("recordings");
+
+  if !recordings_dir.exists() {
+    fs::create_dir_all(&recordings_dir)?;
+  }
+
+  Ok(recordings_dir)
+}
+
+/// Authentication method as named by the frontend, a certificate is sent as
+/// a public key on the wire but `HostBased` has always stood in for it here
+pub fn method_kind_name(method_kind: &MethodKind) -> &'static str {
+  match method_kind {
+    MethodKind::None => "None",
+    MethodKind::Password => "Password",
+    MethodKind::PublicKey => "PublicKey",
+    MethodKind::HostBased => "Certificate",
+    MethodKind::KeyboardInteractive => "KeyboardInteractive",
+  }
+}
+
+/// Signal name without the `SIG` prefix, as sent in `exit-signal`
+pub fn signal_name(signal: Sig) -> String {
+  match signal {
+    Sig::Custom(name) => name,
+    signal => format!("{:?}", signal),
+  }
+}
+
+/// The reverse of `signal_name`, a leading `SIG` is accepted and names RFC
+/// 4254 does not list are sent as they are
+pub fn parse_signal(name: &str) -> Sig {
+  let name = name.trim().to_ascii_uppercase();
+  let name = name.strip_prefix("SIG").unwrap_or(&name);
+  match name {
+    "ABRT" => Sig::ABRT,
+    "ALRM" => Sig::ALRM,
+    "FPE" => Sig::FPE,
+    "HUP" => Sig::HUP,
+    "ILL" => Sig::ILL,
+    "INT" => Sig::INT,
+    "KILL" => Sig::KILL,
+    "PIPE" => Sig::PIPE,
+    "QUIT" => Sig::QUIT,
+    "SEGV" => Sig::SEGV,
+    "TERM" => Sig::TERM,
+    "USR1" => Sig::USR1,
+    name => Sig::Custom(name.to_string()),
+  }
+}
+
+/// Connects to the ssh-agent at `socket_path`, falling back to `SSH_AUTH_SOCK`
+#[cfg(unix)]
+pub async fn connect_agent(
+  socket_path: Option<&str>,
+) -> Result<SSHAgentClient, russh::keys::Error> {
+  let agent = match socket_path.filter(|socket_path| !socket_path.is_empty()) {
+    Some(socket_path) => AgentClient::connect_uds(socket_path).await?,
+    None => AgentClient::connect_env().await?,
+  };
+
+  Ok(agent.dynamic())
+}
+
+/// Connects to the ssh-agent named pipe at `socket_path`, falling back to
+/// `SSH_AUTH_SOCK` and then to the Windows OpenSSH agent pipe
+#[cfg(windows)]
+pub async fn connect_agent(
+  socket_path: Option<&str>,
+) -> Result<SSHAgentClient, russh::keys::Error> {
+  let socket_path = socket_path
+    .filter(|socket_path| !socket_path.is_empty())
+    .map(String::from)
+    .or_else(|| std::env::var("SSH_AUTH_SOCK").ok())
+    .unwrap_or(r"\\.\pipe\openssh-ssh-agent".to_string());
+
+  let agent = AgentClient::connect_named_pipe(socket_path).await?;
+
+  Ok(agent.dynamic())
+}
@@ -0,0 +1,236 @@
+use std::{env, sync::Arc};
+
+use data_encoding::HEXLOWER;
+use russh::{Channel, client::Msg};
+use serde::Deserialize;
+use tauri::async_runtime;
+use tokio::{
+  io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+  net::TcpStream,
+  process::Command,
+};
+
+use crate::{
+  commands::session::SSHSessionId,
+  error::{SSHError, SSHResult},
+};
+
+const X11_AUTH_PROTOCOL: &str = "MIT-MAGIC-COOKIE-1";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct X11ForwardingData {
+  /// Local display such as `:0` or `localhost:10.0`, `DISPLAY` is used when
+  /// empty
+  pub display: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct X11Display {
+  host: String,
+  number: u32,
+  screen: u32,
+}
+
+impl X11Display {
+  fn parse(display: &str) -> SSHResult<Self> {
+    let invalid = || SSHError::new(format!("Invalid X11 display: {}", display));
+
+    let (host, rest) = display.rsplit_once(':').ok_or_else(invalid)?;
+    let (number, screen) = rest.split_once('.').unwrap_or((rest, "0"));
+
+    Ok(X11Display {
+      host: host.to_string(),
+      number: number.parse().map_err(|_| invalid())?,
+      screen: screen.parse().map_err(|_| invalid())?,
+    })
+  }
+
+  fn name(&self) -> String {
+    format!("{}:{}.{}", self.host, self.number, self.screen)
+  }
+}
+
+/// Handles the `x11` channels opened by the server.
+///
+/// The server only ever sees a random fake cookie, the first packet of every
+/// X11 connection is checked against it and rewritten with the cookie of the
+/// local display before being passed on, like OpenSSH does.
+#[derive(Clone)]
+pub struct SSHX11Forwarder {
+  display: X11Display,
+  fake_cookie: [u8; 16],
+  real_cookie: Option<Vec<u8>>,
+}
+
+impl SSHX11Forwarder {
+  pub async fn new(x11_forwarding_data: X11ForwardingData) -> SSHResult<Self> {
+    let display = match x11_forwarding_data
+      .display
+      .filter(|display| !display.is_empty())
+    {
+      Some(display) => display,
+      None => env::var("DISPLAY").map_err(|_| SSHError::new("DISPLAY is not set"))?,
+    };
+    let display = X11Display::parse(&display)?;
+
+    let fake_cookie: [u8; 16] = rand::random();
+
+    let real_cookie = read_xauth_cookie(&display).await;
+    if real_cookie.is_none() {
+      log::warn!(
+        "no {} found for X11 display {}, connecting without authentication",
+        X11_AUTH_PROTOCOL,
+        display.name()
+      );
+    }
+
+    Ok(SSHX11Forwarder {
+      display,
+      fake_cookie,
+      real_cookie,
+    })
+  }
+
+  pub fn auth_protocol(&self) -> &'static str {
+    X11_AUTH_PROTOCOL
+  }
+
+  /// Hex encoded, as sent in the `x11-req` channel request
+  pub fn auth_cookie(&self) -> String {
+    HEXLOWER.encode(&self.fake_cookie)
+  }
+
+  pub fn screen(&self) -> u32 {
+    self.display.screen
+  }
+
+  /// Forwards `channel` to the local display in a task of its own, the setup
+  /// packet only arrives once the handler that received the channel returns
+  pub fn forward(self: Arc<Self>, ssh_session_id: SSHSessionId, channel: Channel<Msg>) {
+    let channel_stream = channel.into_stream();
+    async_runtime::spawn(async move {
+      if let Err(err) = self.forwarding(channel_stream).await {
+        log::error!("x11 forwarding {:?} failed: {}", ssh_session_id, err);
+      }
+    });
+  }
+
+  async fn forwarding<S>(&self, mut channel_stream: S) -> SSHResult<()>
+  where
+    S: AsyncRead + AsyncWrite + Unpin,
+  {
+    let setup = match self.read_setup(&mut channel_stream).await {
+      Ok(setup) => setup,
+      Err(err) => {
+        channel_stream.shutdown().await?;
+        return Err(err);
+      }
+    };
+
+    let host = self.display.host.as_str();
+    #[cfg(unix)]
+    if host.is_empty() || host == "unix" || host.starts_with('/') {
+      let socket_path = if host.starts_with('/') {
+        // XQuartz style display, e.g. /private/tmp/com.apple.launchd.xxx/org.xquartz:0
+        format!("{}:{}", host, self.display.number)
+      } else {
+        format!("/tmp/.X11-unix/X{}", self.display.number)
+      };
+      let stream = tokio::net::UnixStream::connect(socket_path).await?;
+      return pipe(channel_stream, stream, setup).await;
+    }
+
+    let host = if host.is_empty() || host == "unix" {
+      "localhost"
+    } else {
+      host
+    };
+    let port = 6000 + self.display.number;
+    let port = u16::try_from(port).map_err(|_| SSHError::new("Invalid X11 display number"))?;
+    let stream = TcpStream::connect((host, port)).await?;
+    stream.set_nodelay(true)?;
+
+    pipe(channel_stream, stream, setup).await
+  }
+
+  /// Reads the connection setup packet sent by the remote X client, verifies
+  /// the fake cookie and returns the packet carrying the real one
+  async fn read_setup<S: AsyncRead + Unpin>(&self, stream: &mut S) -> SSHResult<Vec<u8>> {
+    let mut header = [0u8; 12];
+    stream.read_exact(&mut header).await?;
+
+    let read_u16 = |bytes: [u8; 2]| match header[0] {
+      b'B' => Ok(u16::from_be_bytes(bytes)),
+      b'l' => Ok(u16::from_le_bytes(bytes)),
+      _ => Err(SSHError::new("Invalid X11 byte order")),
+    };
+    let name_len = read_u16([header[6], header[7]])? as usize;
+    let data_len = read_u16([header[8], header[9]])? as usize;
+
+    let mut name = vec![0u8; pad4(name_len)];
+    stream.read_exact(&mut name).await?;
+    let mut data = vec![0u8; pad4(data_len)];
+    stream.read_exact(&mut data).await?;
+
+    if &name[..name_len] != X11_AUTH_PROTOCOL.as_bytes() || data[..data_len] != self.fake_cookie {
+      return Err(SSHError::new("X11 connection uses invalid authentication"));
+    }
+
+    let (name, data): (&[u8], &[u8]) = match &self.real_cookie {
+      Some(real_cookie) => (X11_AUTH_PROTOCOL.as_bytes(), real_cookie),
+      None => (&[], &[]),
+    };
+    let write_u16 = |value: u16| match header[0] {
+      b'B' => value.to_be_bytes(),
+      _ => value.to_le_bytes(),
+    };
+
+    let mut setup = header[..6].to_vec();
+    setup.extend_from_slice(&write_u16(name.len() as u16));
+    setup.extend_from_slice(&write_u16(data.len() as u16));
+    setup.extend_from_slice(&header[10..]);
+    setup.extend_from_slice(name);
+    setup.resize(setup.len() + pad4(name.len()) - name.len(), 0);
+    setup.extend_from_slice(data);
+    setup.resize(setup.len() + pad4(data.len()) - data.len(), 0);
+
+    Ok(setup)
+  }
+}
+
+fn pad4(len: usize) -> usize {
+  len.div_ceil(4) * 4
+}
+
+async fn pipe<A, B>(mut channel_stream: A, mut stream: B, setup: Vec<u8>) -> SSHResult<()>
+where
+  A: AsyncRead + AsyncWrite + Unpin,
+  B: AsyncRead + AsyncWrite + Unpin,
+{
+  stream.write_all(&setup).await?;
+  io::copy_bidirectional(&mut channel_stream, &mut stream).await?;
+
+  Ok(())
+}
+
+/// Looks up the cookie of `display` with `xauth list`, as OpenSSH does
+async fn read_xauth_cookie(display: &X11Display) -> Option<Vec<u8>> {
+  let output = Command::new("xauth")
+    .arg("list")
+    .arg(display.name())
+    .output()
+    .await
+    .ok()?;
+
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .find_map(|line| {
+      let mut parts = line.split_whitespace();
+      let _display = parts.next()?;
+      if parts.next()? != X11_AUTH_PROTOCOL {
+        return None;
+      }
+      HEXLOWER.decode(parts.next()?.as_bytes()).ok()
+    })
+}
@@ -0,0 +1,55 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type SSHShellBroadcastResult = {
+  sshShellId: string;
+  /**
+   * 发送成功时为 null
+   */
+  error: {
+    type: string;
+    message: string;
+  } | null;
+};
+
+export type SSHShellBroadcastOpts = {
+  sshShellIds?: string[];
+  /**
+   * 广播组名称，与 sshShellIds 合并去重
+   */
+  group?: string;
+  data: string;
+};
+
+/**
+ * 同时向多个 shell 发送输入，单个 shell 失败不影响其他 shell
+ */
+export function shellBroadcast({
+  sshShellIds,
+  group,
+  data,
+}: SSHShellBroadcastOpts): Promise<SSHShellBroadcastResult[]> {
+  return invoke<SSHShellBroadcastResult[]>('plugin:ssh|shell_broadcast', {
+    sshShellIds,
+    group,
+    data,
+  });
+}
+
+/**
+ * 创建或替换广播组，sshShellIds 为空时删除该组，shell 关闭后自动移出
+ */
+export function shellBroadcastGroupSet(
+  group: string,
+  sshShellIds: string[]
+): Promise<string> {
+  return invoke<string>('plugin:ssh|shell_broadcast_group_set', {
+    group,
+    sshShellIds,
+  });
+}
+
+export function shellBroadcastGroupList(): Promise<Record<string, string[]>> {
+  return invoke<Record<string, string[]>>(
+    'plugin:ssh|shell_broadcast_group_list'
+  );
+}
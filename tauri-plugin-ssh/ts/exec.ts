@@ -0,0 +1,101 @@
+import { Channel, invoke } from '@tauri-apps/api/core';
+import { v4 as uuidV4 } from 'uuid';
+
+import { SSHSession } from './session';
+
+export type SSHExecExitSignal = {
+  signalName: string;
+  coreDumped: boolean;
+  errorMessage: string;
+};
+
+export type SSHExecOpts = {
+  session: SSHSession;
+  onStdout?: (data: Uint8Array) => unknown;
+  onStderr?: (data: Uint8Array) => unknown;
+  onExitStatus?: (exitStatus: number) => unknown;
+  onExitSignal?: (exitSignal: SSHExecExitSignal) => unknown;
+  /**
+   * 服务器拒绝执行命令
+   */
+  onFailure?: () => unknown;
+  onTimeout?: () => unknown;
+  onCancel?: () => unknown;
+  onClose?: () => unknown;
+};
+
+export type SSHExecRunOpts = {
+  command: string;
+  envs?: Record<string, string>;
+  /**
+   * 超时时间（秒），为空时不超时
+   */
+  timeoutSecs?: number;
+};
+
+export type SSHExecIpcChannelEvent =
+  | { type: 'stdout'; data: number[] }
+  | { type: 'stderr'; data: number[] }
+  | { type: 'exitStatus'; data: number }
+  | { type: 'exitSignal'; data: SSHExecExitSignal }
+  | { type: 'failure' | 'timeout' | 'cancel' | 'close' };
+
+/**
+ * 不分配 PTY 执行一条命令，stdout 和 stderr 分开输出
+ */
+export class SSHExec {
+  sshExecId: string;
+
+  private session: SSHSession;
+  private opts: SSHExecOpts;
+
+  constructor(opts: SSHExecOpts) {
+    this.sshExecId = uuidV4();
+    this.session = opts.session;
+    this.opts = opts;
+  }
+
+  run({ command, envs, timeoutSecs }: SSHExecRunOpts): Promise<string> {
+    return invoke<string>('plugin:ssh|session_exec', {
+      sshSessionId: this.session.sshSessionId,
+      sshExecId: this.sshExecId,
+      command,
+      envs,
+      timeoutSecs,
+      ipcChannel: new Channel<SSHExecIpcChannelEvent>((data) => {
+        switch (data.type) {
+          case 'stdout':
+            this.opts.onStdout?.(new Uint8Array(data.data));
+            break;
+          case 'stderr':
+            this.opts.onStderr?.(new Uint8Array(data.data));
+            break;
+          case 'exitStatus':
+            this.opts.onExitStatus?.(data.data);
+            break;
+          case 'exitSignal':
+            this.opts.onExitSignal?.(data.data);
+            break;
+          case 'failure':
+            this.opts.onFailure?.();
+            break;
+          case 'timeout':
+            this.opts.onTimeout?.();
+            break;
+          case 'cancel':
+            this.opts.onCancel?.();
+            break;
+          case 'close':
+            this.opts.onClose?.();
+            break;
+        }
+      }),
+    });
+  }
+
+  cancel(): Promise<string> {
+    return invoke<string>('plugin:ssh|session_exec_cancel', {
+      sshExecId: this.sshExecId,
+    });
+  }
+}
@@ -1,4 +1,8 @@
 export * from './shell';
+export * from './broadcast';
+export * from './exec';
 export * from './sftp';
 export * from './session';
 export * from './portForwarding';
+export * from './knownHosts';
+export * from './recording';
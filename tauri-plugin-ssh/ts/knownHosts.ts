@@ -0,0 +1,63 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type KnownHostsEntry = {
+  /**
+   * known_hosts 文件中的行号，从 1 开始
+   */
+  line: number;
+  /**
+   * @cert-authority 或 @revoked
+   */
+  marker?: string;
+  hosts: string[];
+  algorithm: string;
+  fingerprint: string;
+  publicKey: string;
+  comment?: string;
+};
+
+export function knownHostsList(): Promise<KnownHostsEntry[]> {
+  return invoke<KnownHostsEntry[]>('plugin:ssh|known_hosts_list');
+}
+
+export function knownHostsSearch(keyword: string): Promise<KnownHostsEntry[]> {
+  return invoke<KnownHostsEntry[]>('plugin:ssh|known_hosts_search', {
+    keyword,
+  });
+}
+
+/**
+ * 删除主机的所有记录，返回删除的行数
+ */
+export function knownHostsRemove(
+  hostname: string,
+  port: number
+): Promise<number> {
+  return invoke<number>('plugin:ssh|known_hosts_remove', {
+    hostname,
+    port,
+  });
+}
+
+/**
+ * 用新的公钥（OpenSSH 格式）替换主机的记录
+ */
+export function knownHostsReplace(
+  hostname: string,
+  port: number,
+  publicKey: string
+): Promise<null> {
+  return invoke<null>('plugin:ssh|known_hosts_replace', {
+    hostname,
+    port,
+    publicKey,
+  });
+}
+
+/**
+ * 类似 ssh-keygen -H，哈希所有明文主机名，返回哈希的主机数
+ * 文件中存在哈希记录后，新学习的主机也会被哈希
+ */
+export function knownHostsHash(): Promise<number> {
+  return invoke<number>('plugin:ssh|known_hosts_hash');
+}
@@ -1,10 +1,18 @@
-import { invoke } from '@tauri-apps/api/core';
+import { Channel, invoke } from '@tauri-apps/api/core';
 import { v4 as uuidV4 } from 'uuid';
 
 import { SSHSession } from './session';
 
 export type SSHPortForwardingOpts = {
   session: SSHSession;
+  /**
+   * 转发已停止，主动关闭或会话断开时触发
+   */
+  onClose?: () => unknown;
+};
+
+export type SSHPortForwardingIpcChannelEvent = {
+  type: 'Close';
 };
 
 export type SSHOpenLocalPortForwarding = {
@@ -31,9 +39,20 @@ export class SSHPortForwarding {
 
   session: SSHSession;
 
+  private opts: SSHPortForwardingOpts;
+
   constructor(opts: SSHPortForwardingOpts) {
     this.sshPortForwardingId = uuidV4();
     this.session = opts.session;
+    this.opts = opts;
+  }
+
+  private createIpcChannel() {
+    return new Channel<SSHPortForwardingIpcChannelEvent>((data) => {
+      if (data.type === 'Close') {
+        this.opts.onClose?.();
+      }
+    });
   }
 
   openLocalPortForwarding({
@@ -49,6 +68,7 @@ export class SSHPortForwarding {
       localPort,
       remoteAddress,
       remotePort,
+      ipcChannel: this.createIpcChannel(),
     });
   }
 
@@ -72,6 +92,7 @@ export class SSHPortForwarding {
       localPort,
       remoteAddress,
       remotePort,
+      ipcChannel: this.createIpcChannel(),
     });
   }
 
@@ -91,6 +112,7 @@ export class SSHPortForwarding {
       sshPortForwardingId: this.sshPortForwardingId,
       localAddress,
       localPort,
+      ipcChannel: this.createIpcChannel(),
     });
   }
 
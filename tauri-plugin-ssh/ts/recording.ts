@@ -0,0 +1,58 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type SSHShellRecordingOpts = {
+  /**
+   * 写入 asciicast 文件头的标题
+   */
+  title?: string;
+  /**
+   * 是否记录输入，记录为 asciicast 的 i 事件
+   */
+  recordInput?: boolean;
+  /**
+   * 在密码类提示符后输入的内容记录为 *，直到回车
+   */
+  redactSecrets?: boolean;
+};
+
+export type SSHShellRecording = {
+  id: string;
+  title?: string;
+  width: number;
+  height: number;
+  /**
+   * 开始录制的 Unix 时间戳，单位秒
+   */
+  timestamp: number;
+  /**
+   * 文件大小，单位字节
+   */
+  size: number;
+  /**
+   * 是否仍在录制中
+   */
+  recording: boolean;
+};
+
+export function shellRecordingList(): Promise<SSHShellRecording[]> {
+  return invoke<SSHShellRecording[]>('plugin:ssh|shell_recording_list');
+}
+
+/**
+ * 将录制完成的 asciicast v2 文件导出到 localFilename
+ */
+export function shellRecordingExport(
+  recordingId: string,
+  localFilename: string
+): Promise<string> {
+  return invoke<string>('plugin:ssh|shell_recording_export', {
+    recordingId,
+    localFilename,
+  });
+}
+
+export function shellRecordingRemove(recordingId: string): Promise<string> {
+  return invoke<string>('plugin:ssh|shell_recording_remove', {
+    recordingId,
+  });
+}
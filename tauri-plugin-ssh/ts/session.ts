@@ -6,33 +6,494 @@ export type SSHSessionDisconnectEvent = {
   data: string;
 };
 
-export type SSHSessionOpts = {
-  onDisconnect?: (data: SSHSessionDisconnectEvent) => unknown;
+export type SSHSessionProxyCommandStderrEvent = {
+  type: 'proxyCommandStderr';
+  data: string;
 };
 
-export type SSHSessionConnectOpts = {
+/**
+ * 服务端在认证前发送的 banner，跳板机的 banner 也通过该会话发送，用 hostname 和 port 区分
+ */
+export type SSHSessionAuthBannerEvent = {
+  type: 'authBanner';
+  data: {
+    hostname: string;
+    port: number;
+    banner: string;
+  };
+};
+
+export type SSHSessionStage =
+  | 'Dns'
+  | 'Tcp'
+  | 'UpstreamProxy'
+  | 'ProxyCommand'
+  | 'JumpHost'
+  | 'Handshake'
+  | 'HostKey'
+  | 'Authentication';
+
+export type SSHSessionStageTiming = {
+  stage: SSHSessionStage;
+  elapsedMillis: number;
+  /**
+   * 该阶段失败时的错误信息
+   */
+  error?: string;
+};
+
+/**
+ * 诊断模式下每个连接阶段完成时发送，跳板机的阶段用 hostname 和 port 区分
+ */
+export type SSHSessionDiagnoseStageEvent = {
+  type: 'diagnoseStage';
+  data: {
+    hostname: string;
+    port: number;
+    timing: SSHSessionStageTiming;
+  };
+};
+
+/**
+ * 会话意外断开后，等待 delayMillis 进行第 attempt 次重连，error 为断开或上次重连失败的原因
+ */
+export type SSHSessionReconnectingEvent = {
+  type: 'reconnecting';
+  data: {
+    attempt: number;
+    maxAttempts: number;
+    delayMillis: number;
+    error: string;
+  };
+};
+
+/**
+ * 重连并认证成功，shell 和远程端口转发已重新打开
+ */
+export type SSHSessionReconnectedEvent = {
+  type: 'reconnected';
+  data: {
+    attempt: number;
+  };
+};
+
+/**
+ * 重连次数用尽，随后发送 disconnect
+ */
+export type SSHSessionReconnectFailedEvent = {
+  type: 'reconnectFailed';
+  data: {
+    error: string;
+  };
+};
+
+/**
+ * 服务端的版本标识，例如 SSH-2.0-OpenSSH_9.6，跳板机用 hostname 和 port 区分
+ */
+export type SSHSessionServerBannerEvent = {
+  type: 'serverBanner';
+  data: {
+    hostname: string;
+    port: number;
+    identification: string;
+  };
+};
+
+/**
+ * 首次密钥交换时主机密钥通过 known_hosts 校验
+ */
+export type SSHSessionHostKeyAcceptedEvent = {
+  type: 'hostKeyAccepted';
+  data: {
+    hostname: string;
+    port: number;
+    algorithm: string;
+    fingerprint: string;
+  };
+};
+
+/**
+ * 之后的密钥交换更新了会话密钥
+ */
+export type SSHSessionRekeyEvent = {
+  type: 'rekey';
+  data: {
+    hostname: string;
+    port: number;
+  };
+};
+
+/**
+ * 认证后定期发送 keepalive 测得的往返时间，调用 info 时也会发送
+ */
+export type SSHSessionKeepaliveRttEvent = {
+  type: 'keepaliveRtt';
+  data: {
+    rttMillis: number;
+  };
+};
+
+/**
+ * DirectTcpip 为本地和动态端口转发，ForwardedTcpip 为远程端口转发
+ */
+export type SSHChannelKind =
+  | 'Shell'
+  | 'Sftp'
+  | 'Exec'
+  | 'DirectTcpip'
+  | 'ForwardedTcpip'
+  | 'AgentForward'
+  | 'X11';
+
+export type SSHSessionChannelOpenedEvent = {
+  type: 'channelOpened';
+  data: {
+    channelId: number;
+    kind: SSHChannelKind;
+  };
+};
+
+/**
+ * 任意一方关闭了通道
+ */
+export type SSHSessionChannelClosedEvent = {
+  type: 'channelClosed';
+  data: {
+    channelId: number;
+  };
+};
+
+/**
+ * 端口转发接受的新连接，originator 为对端地址
+ */
+export type SSHSessionPortForwardingConnectionEvent = {
+  type: 'portForwardingConnection';
+  data: {
+    sshPortForwardingId: string;
+    originator: string;
+  };
+};
+
+/**
+ * 服务端拒绝了 tcpip-forward 等全局请求
+ */
+export type SSHSessionGlobalRequestFailedEvent = {
+  type: 'globalRequestFailed';
+  data: {
+    request: string;
+    address: string;
+    port: number;
+    error: string;
+  };
+};
+
+/**
+ * 由双方的 KEXINIT 协商得出，AEAD 加密算法没有单独的 MAC
+ */
+export type SSHSessionAlgorithms = {
+  kex?: string;
+  hostKey?: string;
+  cipherClientToServer?: string;
+  cipherServerToClient?: string;
+  macClientToServer?: string;
+  macServerToClient?: string;
+  compressionClientToServer?: string;
+  compressionServerToClient?: string;
+};
+
+export type SSHSessionInfo = {
   hostname: string;
   port: number;
-  jumpHostSshSessionId?: string;
+  clientIdentification?: string;
+  serverIdentification?: string;
+  algorithms: SSHSessionAlgorithms;
+  hostKeyAlgorithm?: string;
+  /**
+   * 仅直连 TCP 时可用
+   */
+  localAddr?: string;
+  remoteAddr?: string;
+  /**
+   * 跳板机的 hostname:port，按连接顺序
+   */
+  jumpPath: string[];
+  /**
+   * 毫秒时间戳
+   */
+  connectedAt?: number;
+  bytesSent: number;
+  bytesReceived: number;
+  /**
+   * 最近一次 keepalive 测量的往返时间，调用时会重新测量
+   */
+  rttMillis?: number;
+  stages: SSHSessionStageTiming[];
+};
+
+export type SSHSessionOpts = {
+  onDisconnect?: (data: SSHSessionDisconnectEvent) => unknown;
+  onProxyCommandStderr?: (data: SSHSessionProxyCommandStderrEvent) => unknown;
+  onAuthBanner?: (data: SSHSessionAuthBannerEvent) => unknown;
+  onDiagnoseStage?: (data: SSHSessionDiagnoseStageEvent) => unknown;
+  onReconnecting?: (data: SSHSessionReconnectingEvent) => unknown;
+  onReconnected?: (data: SSHSessionReconnectedEvent) => unknown;
+  onReconnectFailed?: (data: SSHSessionReconnectFailedEvent) => unknown;
+  onServerBanner?: (data: SSHSessionServerBannerEvent) => unknown;
+  onHostKeyAccepted?: (data: SSHSessionHostKeyAcceptedEvent) => unknown;
+  onRekey?: (data: SSHSessionRekeyEvent) => unknown;
+  onKeepaliveRtt?: (data: SSHSessionKeepaliveRttEvent) => unknown;
+  onChannelOpened?: (data: SSHSessionChannelOpenedEvent) => unknown;
+  onChannelClosed?: (data: SSHSessionChannelClosedEvent) => unknown;
+  onPortForwardingConnection?: (
+    data: SSHSessionPortForwardingConnectionEvent
+  ) => unknown;
+  onGlobalRequestFailed?: (data: SSHSessionGlobalRequestFailedEvent) => unknown;
+  /**
+   * 收到任意事件时调用，用于记录日志
+   */
+  onEvent?: (data: SSHSessionIpcChannelEvent) => unknown;
 };
 
 export enum SSHSessionCheckServerKey {
   Continue = 'Continue',
   AddAndContinue = 'AddAndContinue',
+  /**
+   * 主机公钥变更时替换 known_hosts 中的记录
+   */
+  ReplaceAndContinue = 'ReplaceAndContinue',
 }
 
-export type SSHSessionIpcChannelEvent = SSHSessionDisconnectEvent;
+/**
+ * 对应 OpenSSH 的 StrictHostKeyChecking
+ */
+export type SSHSessionStrictHostKeyChecking =
+  | 'ask'
+  | 'accept-new'
+  | 'yes'
+  | 'off';
 
 export enum AuthenticationMethod {
   Password = 'Password',
   PublicKey = 'PublicKey',
   Certificate = 'Certificate',
   KeyboardInteractive = 'KeyboardInteractive',
+  Agent = 'Agent',
+}
+
+/**
+ * keyboard-interactive 提示的自动应答规则，pattern 为匹配提示文本的正则
+ */
+export type SSHKeyboardInteractiveRule = {
+  pattern: string;
+  answer: 'Password' | 'Totp' | 'Fixed';
+  /**
+   * answer 为 Fixed 时的应答内容
+   */
+  value?: string;
+};
+
+export type SSHSessionAuthenticationData =
+  | {
+      authenticationMethod: AuthenticationMethod.Password;
+      password: string;
+      /**
+       * base32 密钥或 otpauth:// 链接，服务端要求验证码时自动应答
+       */
+      totpSecret?: string;
+      keyboardInteractiveRules?: SSHKeyboardInteractiveRule[];
+    }
+  | {
+      authenticationMethod: AuthenticationMethod.PublicKey;
+      privateKey: string;
+      passphrase?: string;
+    }
+  | {
+      authenticationMethod: AuthenticationMethod.Certificate;
+      privateKey: string;
+      passphrase?: string;
+      certificate: string;
+    }
+  | {
+      authenticationMethod: AuthenticationMethod.KeyboardInteractive;
+      prompts?: string[];
+      password?: string;
+      totpSecret?: string;
+      keyboardInteractiveRules?: SSHKeyboardInteractiveRule[];
+    }
+  | {
+      authenticationMethod: AuthenticationMethod.Agent;
+      socketPath?: string;
+    };
+
+/**
+ * 跳板机链路中的一跳，由插件按顺序连接并认证
+ */
+export type SSHSessionJumpHost = {
+  hostname: string;
+  port: number;
+  username: string;
+  checkServerKey?: SSHSessionCheckServerKey;
+  strictHostKeyChecking?: SSHSessionStrictHostKeyChecking;
+  /**
+   * 仅在链路的第一跳生效
+   */
+  proxyCommand?: string;
+  /**
+   * 仅在链路的第一跳生效
+   */
+  upstreamProxy?: SSHUpstreamProxy;
+  authenticationData: SSHSessionAuthenticationData;
+  /**
+   * 部分认证成功（partialSuccess）后依次尝试的认证步骤
+   */
+  authenticationChain?: SSHSessionAuthenticationData[];
+  connectionProfile?: SSHSessionConnectionProfile;
+};
+
+export enum SSHUpstreamProxyType {
+  /**
+   * SOCKS4a，由代理解析主机名
+   */
+  Socks4 = 'Socks4',
+  Socks5 = 'Socks5',
+  /**
+   * HTTP CONNECT
+   */
+  Http = 'Http',
 }
 
+export type SSHUpstreamProxy = {
+  type: SSHUpstreamProxyType;
+  hostname: string;
+  port: number;
+  username?: string;
+  password?: string;
+};
+
+/**
+ * 自动重连策略，每次重连的等待时间翻倍，使用最近一次成功认证的凭据重新认证
+ */
+export type SSHSessionReconnectPolicy = {
+  /**
+   * 默认 5
+   */
+  maxAttempts?: number;
+  /**
+   * 默认 1000
+   */
+  initialDelayMillis?: number;
+  /**
+   * 默认 30000
+   */
+  maxDelayMillis?: number;
+};
+
+/**
+ * 已有同一用户、主机和跳板机的已认证连接时复用该连接，认证直接成功
+ */
+export type SSHSessionMultiplexOpts = {
+  /**
+   * 最后一个会话断开后连接保持的秒数，默认 60
+   */
+  lingerSecs?: number;
+};
+
+/**
+ * 连接参数，未设置的字段使用默认值，不支持的算法名称会导致连接失败
+ */
+export type SSHSessionConnectionProfile = {
+  /**
+   * 连接和密钥交换的超时秒数，默认 5
+   */
+  connectTimeoutSecs?: number;
+  /**
+   * 空闲断开的秒数，默认 1800，0 表示不断开
+   */
+  inactivityTimeoutSecs?: number;
+  /**
+   * 心跳间隔秒数，默认 5，0 表示不发送心跳
+   */
+  keepaliveIntervalSecs?: number;
+  /**
+   * 心跳无响应多少次后断开，默认 3
+   */
+  keepaliveMax?: number;
+  /**
+   * 为空时使用默认的压缩算法
+   */
+  compression?: boolean;
+  /**
+   * 算法列表按优先级排列，且只协商列表中的算法，
+   * 旧设备所需的 diffie-hellman-group1-sha1、ssh-rsa、aes128-cbc 等需显式列出
+   */
+  kexAlgorithms?: string[];
+  hostKeyAlgorithms?: string[];
+  ciphers?: string[];
+  macs?: string[];
+  /**
+   * 传输多少字节后重新协商密钥，默认 1GiB
+   */
+  rekeyLimitBytes?: number;
+  /**
+   * 多少秒后重新协商密钥，默认 3600
+   */
+  rekeyTimeLimitSecs?: number;
+};
+
+export type SSHSessionConnectOpts = {
+  hostname: string;
+  port: number;
+  /**
+   * 用于替换 proxyCommand 中的 %r
+   */
+  username?: string;
+  /**
+   * 本地执行的代理命令，支持 %h %p %r，存在跳板机时忽略；主机名或用户名含有 shell 特殊字符时拒绝执行
+   */
+  proxyCommand?: string;
+  /**
+   * 为空时使用全局代理，存在跳板机时忽略
+   */
+  upstreamProxy?: SSHUpstreamProxy;
+  jumpHosts?: SSHSessionJumpHost[];
+  strictHostKeyChecking?: SSHSessionStrictHostKeyChecking;
+  /**
+   * 诊断模式，每个连接阶段完成时通过 onDiagnoseStage 上报耗时和错误
+   */
+  diagnose?: boolean;
+  /**
+   * 为空时不重连
+   */
+  reconnect?: SSHSessionReconnectPolicy;
+  /**
+   * 为空时不复用连接，需要设置 username，诊断模式下不复用
+   */
+  multiplex?: SSHSessionMultiplexOpts;
+  connectionProfile?: SSHSessionConnectionProfile;
+};
+
+export type SSHSessionIpcChannelEvent =
+  | SSHSessionDisconnectEvent
+  | SSHSessionProxyCommandStderrEvent
+  | SSHSessionAuthBannerEvent
+  | SSHSessionDiagnoseStageEvent
+  | SSHSessionReconnectingEvent
+  | SSHSessionReconnectedEvent
+  | SSHSessionReconnectFailedEvent
+  | SSHSessionServerBannerEvent
+  | SSHSessionHostKeyAcceptedEvent
+  | SSHSessionRekeyEvent
+  | SSHSessionKeepaliveRttEvent
+  | SSHSessionChannelOpenedEvent
+  | SSHSessionChannelClosedEvent
+  | SSHSessionPortForwardingConnectionEvent
+  | SSHSessionGlobalRequestFailedEvent;
+
 export type SSHSessionAuthenticatePasswordOpts = {
   username: string;
   password: string;
+  totpSecret?: string;
+  keyboardInteractiveRules?: SSHKeyboardInteractiveRule[];
 };
 export type SSHSessionAuthenticatePublicKeyOpts = {
   username: string;
@@ -49,6 +510,38 @@ export type SSHSessionAuthenticateCertificateOpts = {
 export type SSHSessionAuthenticateKeyboardInteractiveOpts = {
   username: string;
   prompts?: string[];
+  password?: string;
+  totpSecret?: string;
+  keyboardInteractiveRules?: SSHKeyboardInteractiveRule[];
+};
+
+export type SSHSessionAuthenticateAgentOpts = {
+  username: string;
+  /**
+   * 为空时使用 SSH_AUTH_SOCK
+   */
+  socketPath?: string;
+};
+
+export type SSHSessionAuthenticateOpts = {
+  username: string;
+  authenticationData: SSHSessionAuthenticationData;
+  /**
+   * 部分认证成功后，插件从中选择服务端仍接受的认证方法继续认证，
+   * 例如服务端配置了 AuthenticationMethods publickey,password
+   */
+  authenticationChain?: SSHSessionAuthenticationData[];
+};
+
+export type SSHSessionProbeAuthResult = {
+  /**
+   * 服务端接受 none 认证，会话已经认证完成
+   */
+  authenticated: boolean;
+  /**
+   * 服务端接受的认证方法
+   */
+  methods: (AuthenticationMethod | 'None')[];
 };
 
 export class SSHSession {
@@ -70,13 +563,67 @@ export class SSHSession {
       sshSessionId: this.sshSessionId,
       checkServerKey,
       ipcChannel: new Channel<SSHSessionIpcChannelEvent>((data) => {
+        this.opts.onEvent?.(data);
         if (data.type === 'disconnect') {
           this.opts.onDisconnect?.(data);
+        } else if (data.type === 'proxyCommandStderr') {
+          this.opts.onProxyCommandStderr?.(data);
+        } else if (data.type === 'authBanner') {
+          this.opts.onAuthBanner?.(data);
+        } else if (data.type === 'diagnoseStage') {
+          this.opts.onDiagnoseStage?.(data);
+        } else if (data.type === 'reconnecting') {
+          this.opts.onReconnecting?.(data);
+        } else if (data.type === 'reconnected') {
+          this.opts.onReconnected?.(data);
+        } else if (data.type === 'reconnectFailed') {
+          this.opts.onReconnectFailed?.(data);
+        } else if (data.type === 'serverBanner') {
+          this.opts.onServerBanner?.(data);
+        } else if (data.type === 'hostKeyAccepted') {
+          this.opts.onHostKeyAccepted?.(data);
+        } else if (data.type === 'rekey') {
+          this.opts.onRekey?.(data);
+        } else if (data.type === 'keepaliveRtt') {
+          this.opts.onKeepaliveRtt?.(data);
+        } else if (data.type === 'channelOpened') {
+          this.opts.onChannelOpened?.(data);
+        } else if (data.type === 'channelClosed') {
+          this.opts.onChannelClosed?.(data);
+        } else if (data.type === 'portForwardingConnection') {
+          this.opts.onPortForwardingConnection?.(data);
+        } else if (data.type === 'globalRequestFailed') {
+          this.opts.onGlobalRequestFailed?.(data);
         }
       }),
     });
   }
 
+  info(): Promise<SSHSessionInfo> {
+    return invoke<SSHSessionInfo>('plugin:ssh|session_info', {
+      sshSessionId: this.sshSessionId,
+    });
+  }
+
+  /**
+   * 发送 none 认证请求，获取服务端接受的认证方法
+   */
+  probe_auth(username: string): Promise<SSHSessionProbeAuthResult> {
+    return invoke<SSHSessionProbeAuthResult>('plugin:ssh|session_probe_auth', {
+      username,
+      sshSessionId: this.sshSessionId,
+    });
+  }
+
+  authenticate(opts: SSHSessionAuthenticateOpts): Promise<string> {
+    return invoke<string>('plugin:ssh|session_authenticate', {
+      username: opts.username,
+      authenticationData: opts.authenticationData,
+      authenticationChain: opts.authenticationChain,
+      sshSessionId: this.sshSessionId,
+    });
+  }
+
   authenticate_password(
     opts: SSHSessionAuthenticatePasswordOpts
   ): Promise<string> {
@@ -85,6 +632,8 @@ export class SSHSession {
       authenticationData: {
         authenticationMethod: AuthenticationMethod.Password,
         password: opts.password,
+        totpSecret: opts.totpSecret,
+        keyboardInteractiveRules: opts.keyboardInteractiveRules,
       },
       sshSessionId: this.sshSessionId,
     });
@@ -127,6 +676,20 @@ export class SSHSession {
       authenticationData: {
         authenticationMethod: AuthenticationMethod.KeyboardInteractive,
         prompts: opts.prompts,
+        password: opts.password,
+        totpSecret: opts.totpSecret,
+        keyboardInteractiveRules: opts.keyboardInteractiveRules,
+      },
+      sshSessionId: this.sshSessionId,
+    });
+  }
+
+  authenticate_agent(opts: SSHSessionAuthenticateAgentOpts): Promise<string> {
+    return invoke<string>('plugin:ssh|session_authenticate', {
+      username: opts.username,
+      authenticationData: {
+        authenticationMethod: AuthenticationMethod.Agent,
+        socketPath: opts.socketPath,
       },
       sshSessionId: this.sshSessionId,
     });
@@ -138,3 +701,20 @@ export class SSHSession {
     });
   }
 }
+
+/**
+ * 设置全局代理，没有配置代理的主机使用该代理，传 null 时直连，重启后保留
+ */
+export function setUpstreamProxy(
+  upstreamProxy: SSHUpstreamProxy | null
+): Promise<null> {
+  return invoke<null>('plugin:ssh|session_set_upstream_proxy', {
+    upstreamProxy,
+  });
+}
+
+export function getUpstreamProxy(): Promise<SSHUpstreamProxy | null> {
+  return invoke<SSHUpstreamProxy | null>(
+    'plugin:ssh|session_get_upstream_proxy'
+  );
+}
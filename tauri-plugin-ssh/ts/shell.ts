@@ -2,12 +2,30 @@ import { Channel, invoke } from '@tauri-apps/api/core';
 import { v4 as uuidV4 } from 'uuid';
 
 import { SSHSession } from './session';
+import type { SSHShellRecordingOpts } from './recording';
 
 export type SSHShellOpts = {
   session: SSHSession;
   onData?: (data: Uint8Array) => unknown;
+  onStderr?: (data: Uint8Array) => unknown;
+  /**
+   * 远程进程正常退出，随后会收到 onClose
+   */
+  onExitStatus?: (exitStatus: number) => unknown;
+  /**
+   * 远程进程被信号终止，随后会收到 onClose
+   */
+  onExitSignal?: (data: SSHShellExitSignal) => unknown;
+  /**
+   * 启动脚本在第 step 步（从 0 开始）失败，后续步骤不会执行
+   */
+  onStartupScriptFailed?: (data: SSHShellStartupScriptFailed) => unknown;
   onEof?: () => unknown;
   onClose?: () => unknown;
+  /**
+   * 会话重连后 shell 已重新打开，之前的远程进程已经结束
+   */
+  onReconnected?: () => unknown;
 };
 
 export type SSHShellSize = {
@@ -17,16 +35,111 @@ export type SSHShellSize = {
   height: number;
 };
 
+export type SSHShellAgentForwardingKey = {
+  privateKey: string;
+  passphrase?: string;
+};
+
+export type SSHShellAgentForwarding =
+  | {
+      type: 'Socket';
+      /**
+       * 为空时使用 SSH_AUTH_SOCK
+       */
+      socketPath?: string;
+    }
+  | {
+      type: 'Keys';
+      keys: SSHShellAgentForwardingKey[];
+    };
+
+export type SSHShellX11Forwarding = {
+  /**
+   * 本地 X11 display，例如 :0，为空时使用 DISPLAY
+   */
+  display?: string;
+};
+
+export type SSHShellStartupStep =
+  | {
+      type: 'Send';
+      data: string;
+    }
+  | {
+      /**
+       * 发送 secret 并换行，不会写入日志，录制中显示为 *
+       */
+      type: 'SendSecret';
+      secret: string;
+    }
+  | {
+      /**
+       * 等待上次匹配之后的输出匹配正则 pattern，默认超时 30 秒
+       */
+      type: 'Expect';
+      pattern: string;
+      timeoutSecs?: number;
+    }
+  | {
+      type: 'Sleep';
+      millis: number;
+    };
+
+export type SSHShellStartupScriptFailed = {
+  step: number;
+  message: string;
+};
+
 export type SSHShellOpenOpts = {
   term?: string;
   envs?: Record<string, string>;
   size: SSHShellSize;
+  agentForwarding?: SSHShellAgentForwarding;
+  x11Forwarding?: SSHShellX11Forwarding;
+  /**
+   * 打开 shell 后立即开始录制
+   */
+  recording?: SSHShellRecordingOpts;
+  /**
+   * 开启后需要在处理完 onData 的数据后调用 ack，未确认的数据过多时会暂停输出
+   */
+  flowControl?: boolean;
+  /**
+   * shell 打开后按顺序执行的步骤
+   */
+  startupScript?: SSHShellStartupStep[];
 };
 
-export type SSHShellIpcChannelEventJson = {
-  type: 'Eof' | 'Close';
+export type SSHShellExitSignal = {
+  /**
+   * 不带 SIG 前缀，例如 TERM、KILL
+   */
+  signalName: string;
+  coreDumped: boolean;
+  errorMessage: string;
 };
 
+export type SSHShellIpcChannelEventJson =
+  | {
+      type: 'Eof' | 'Close' | 'Reconnected';
+    }
+  | {
+      type: 'Stderr';
+      data: number[];
+    }
+  | {
+      type: 'ExitStatus';
+      data: number;
+    }
+  | {
+      type: 'ExitSignal';
+      data: SSHShellExitSignal;
+    }
+  | {
+      type: 'StartupScriptFailed';
+      data: SSHShellStartupScriptFailed;
+    };
+
 export type SSHShellIpcChannelEvent = ArrayBuffer | SSHShellIpcChannelEventJson;
 
 export class SSHShell {
@@ -41,23 +154,47 @@ export class SSHShell {
     this.opts = opts;
   }
 
-  open({ term, envs, size }: SSHShellOpenOpts): Promise<string> {
+  open({
+    term,
+    envs,
+    size,
+    agentForwarding,
+    x11Forwarding,
+    recording,
+    flowControl,
+    startupScript,
+  }: SSHShellOpenOpts): Promise<string> {
     return invoke<string>('plugin:ssh|shell_open', {
       sshSessionId: this.session.sshSessionId,
       sshShellId: this.sshShellId,
       term,
       envs,
       size,
+      agentForwarding,
+      x11Forwarding,
+      recording,
+      flowControl,
+      startupScript,
       ipcChannel: new Channel<SSHShellIpcChannelEvent>((data) => {
         if (data instanceof ArrayBuffer) {
           this.opts.onData?.(new Uint8Array(data));
           return;
         }
 
-        if (data.type === 'Eof') {
+        if (data.type === 'Stderr') {
+          this.opts.onStderr?.(new Uint8Array(data.data));
+        } else if (data.type === 'ExitStatus') {
+          this.opts.onExitStatus?.(data.data);
+        } else if (data.type === 'ExitSignal') {
+          this.opts.onExitSignal?.(data.data);
+        } else if (data.type === 'StartupScriptFailed') {
+          this.opts.onStartupScriptFailed?.(data.data);
+        } else if (data.type === 'Eof') {
           this.opts.onEof?.();
         } else if (data.type === 'Close') {
           this.opts.onClose?.();
+        } else if (data.type === 'Reconnected') {
+          this.opts.onReconnected?.();
         }
       }),
     });
@@ -76,10 +213,60 @@ export class SSHShell {
     });
   }
 
+  /**
+   * 确认已处理的输出字节数，仅在 flowControl 开启时使用
+   */
+  ack(bytes: number): Promise<string> {
+    return invoke<string>('plugin:ssh|shell_output_ack', {
+      sshShellId: this.sshShellId,
+      bytes,
+    });
+  }
+
+  /**
+   * 向远程进程发送信号，例如 INT、TERM、KILL，可带 SIG 前缀
+   * 用于 PTY 吞掉 Ctrl-C 等场景，部分服务端会忽略信号请求
+   */
+  signal(signal: string): Promise<string> {
+    return invoke<string>('plugin:ssh|shell_signal', {
+      sshShellId: this.sshShellId,
+      signal,
+    });
+  }
+
+  /**
+   * 发送 BREAK（RFC 4335），目前 SSH 库不支持，调用会返回 Unsupported 错误
+   */
+  break(millis?: number): Promise<string> {
+    return invoke<string>('plugin:ssh|shell_break', {
+      sshShellId: this.sshShellId,
+      millis,
+    });
+  }
+
   resize(size: SSHShellSize): Promise<string> {
     return invoke<string>('plugin:ssh|shell_resize', {
       sshShellId: this.sshShellId,
       size,
     });
   }
+
+  /**
+   * 开始录制，返回录制 id
+   */
+  startRecording(opts?: SSHShellRecordingOpts): Promise<string> {
+    return invoke<string>('plugin:ssh|shell_recording_start', {
+      sshShellId: this.sshShellId,
+      opts,
+    });
+  }
+
+  /**
+   * 停止录制，未在录制时返回 null
+   */
+  stopRecording(): Promise<string | null> {
+    return invoke<string | null>('plugin:ssh|shell_recording_stop', {
+      sshShellId: this.sshShellId,
+    });
+  }
 }